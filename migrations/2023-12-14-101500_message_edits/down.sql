@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter table messages
+    drop column if exists edited_at,
+    drop column if exists deleted_at;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+alter table messages
+    add column edited_at  timestamptz,
+    add column deleted_at timestamptz;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+drop index if exists messages_reply_to_message_id_idx;
+
+alter table messages
+    drop column if exists reply_to_message_id;
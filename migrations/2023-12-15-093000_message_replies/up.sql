@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+alter table messages
+    add column reply_to_message_id int references messages (id);
+
+create index messages_reply_to_message_id_idx on messages (reply_to_message_id);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+
+CREATE TABLE message_reactions
+(
+    message_id int         not null references messages (id),
+    user_id    text        not null references users (id),
+    emoji      text        not null,
+    created_at timestamptz not null default (now() at time zone 'utc'),
+    primary key (message_id, user_id, emoji)
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+alter table users_chats
+    drop column if exists last_read_message_id;
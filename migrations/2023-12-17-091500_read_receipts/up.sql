@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+alter table users_chats
+    add column last_read_message_id int references messages (id) on delete set null;
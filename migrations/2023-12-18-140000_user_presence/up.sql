@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+create table user_presence
+(
+    user_id        text        not null primary key references users (id),
+    connections    int         not null default 0,
+    last_seen_at   timestamptz not null default (now() at time zone 'utc'),
+    last_active_at timestamptz not null default (now() at time zone 'utc')
+);
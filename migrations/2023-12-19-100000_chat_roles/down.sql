@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter table users_chats
+    drop column if exists joined_at,
+    drop column if exists role;
@@ -0,0 +1,21 @@
+-- Your SQL goes here
+
+alter table users_chats
+    add column role      text        not null default 'member'
+        check (role in ('owner', 'admin', 'member')),
+    add column joined_at timestamptz not null default (now() at time zone 'utc');
+
+-- Every member could invite before roles existed, keep it that way for existing chats.
+update users_chats
+set role = 'admin';
+
+-- Join times weren't recorded, so the member who wrote first owns the chat.
+update users_chats
+set role = 'owner'
+from (select distinct on (users_chats.chat_id) users_chats.chat_id, users_chats.user_id
+      from users_chats
+               left join messages
+                         on messages.chat_id = users_chats.chat_id and messages.user_id = users_chats.user_id
+      order by users_chats.chat_id, messages.created_at nulls last, users_chats.user_id) as owners
+where users_chats.chat_id = owners.chat_id
+  and users_chats.user_id = owners.user_id;
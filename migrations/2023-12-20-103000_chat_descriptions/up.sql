@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+alter table chats
+    add column description text not null default '';
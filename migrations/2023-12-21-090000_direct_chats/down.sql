@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+alter table chats
+    drop constraint if exists chats_direct_key_check,
+    drop column if exists direct_key,
+    drop column if exists kind;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+alter table chats
+    add column kind       text not null default 'group'
+        check (kind in ('group', 'direct')),
+    add column direct_key text unique,
+    add constraint chats_direct_key_check check ((kind = 'direct') = (direct_key is not null));
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+alter table invites
+    drop constraint if exists invites_chat_id_invitee_user_id_key,
+    drop column if exists expires_at;
@@ -0,0 +1,13 @@
+-- Your SQL goes here
+
+-- Only the latest invite of a user to a chat is kept.
+delete
+from invites a
+    using invites b
+where a.chat_id = b.chat_id
+  and a.invitee_user_id = b.invitee_user_id
+  and a.id < b.id;
+
+alter table invites
+    add column expires_at timestamptz not null default current_timestamp + interval '7 days',
+    add constraint invites_chat_id_invitee_user_id_key unique (chat_id, invitee_user_id);
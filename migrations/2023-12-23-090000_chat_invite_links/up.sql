@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+CREATE TABLE chat_invite_links
+(
+    code            text primary key,
+    chat_id         int         not null references chats (id),
+    creator_user_id text        not null references users (id),
+    max_uses        int check (max_uses > 0),
+    uses            int         not null default 0,
+    created_at      timestamptz not null default (now() at time zone 'utc'),
+    expires_at      timestamptz
+);
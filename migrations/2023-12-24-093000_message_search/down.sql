@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+drop index if exists messages_search_vector_idx;
+
+alter table messages
+    drop column if exists search_vector;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+alter table messages
+    add column search_vector tsvector generated always as (to_tsvector('english', text)) stored;
+
+create index messages_search_vector_idx on messages using gin (search_vector);
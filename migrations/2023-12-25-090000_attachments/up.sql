@@ -0,0 +1,16 @@
+-- Your SQL goes here
+
+CREATE TABLE attachments
+(
+    id               serial primary key,
+    uploader_user_id text        not null references users (id),
+    chat_id          int         not null references chats (id),
+    message_id       int references messages (id),
+    file_name        text        not null,
+    content_type     text        not null,
+    size             bigint      not null check (size > 0),
+    storage_key      text        not null unique,
+    created_at       timestamptz not null default (now() at time zone 'utc')
+);
+
+create index attachments_message_id_idx on attachments (message_id);
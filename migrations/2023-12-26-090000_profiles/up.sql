@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+create table profiles
+(
+    user_id          text        not null primary key references users (id),
+    display_name     text,
+    status_message   text        not null default '',
+    timezone         text        not null default 'UTC',
+    email_visibility text        not null default 'contacts'
+        check (email_visibility in ('everyone', 'contacts', 'nobody')),
+    updated_at       timestamptz not null default (now() at time zone 'utc')
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+drop index if exists profiles_display_name_trgm_idx;
+drop index if exists users_email_trgm_idx;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+create extension if not exists pg_trgm;
+
+-- Trigram indexes back both the prefix (ilike) and the fuzzy (<%) matches of the user search.
+create index users_email_trgm_idx on users using gin (email gin_trgm_ops);
+create index profiles_display_name_trgm_idx on profiles using gin (display_name gin_trgm_ops);
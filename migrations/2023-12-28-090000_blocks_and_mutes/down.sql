@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+
+alter table users_chats
+    drop column if exists muted;
+
+drop table if exists user_blocks;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+create table user_blocks
+(
+    blocker_user_id text        not null references users (id),
+    blocked_user_id text        not null references users (id),
+    created_at      timestamptz not null default (now() at time zone 'utc'),
+    primary key (blocker_user_id, blocked_user_id),
+    check (blocker_user_id <> blocked_user_id)
+);
+
+create index user_blocks_blocked_user_id_idx on user_blocks (blocked_user_id);
+
+alter table users_chats
+    add column muted boolean not null default false;
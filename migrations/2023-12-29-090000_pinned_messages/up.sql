@@ -0,0 +1,11 @@
+-- Your SQL goes here
+
+create table pinned_messages
+(
+    message_id        integer     not null primary key references messages (id),
+    chat_id           integer     not null references chats (id),
+    pinned_by_user_id text        not null references users (id),
+    pinned_at         timestamptz not null default (now() at time zone 'utc')
+);
+
+create index pinned_messages_chat_id_idx on pinned_messages (chat_id, pinned_at);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+
+create table mentions
+(
+    message_id        integer     not null references messages (id),
+    mentioned_user_id text        not null references users (id),
+    chat_id           integer     not null references chats (id),
+    created_at        timestamptz not null default (now() at time zone 'utc'),
+    primary key (message_id, mentioned_user_id)
+);
+
+create index mentions_mentioned_user_id_idx on mentions (mentioned_user_id, message_id);
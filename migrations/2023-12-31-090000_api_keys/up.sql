@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+create table api_keys
+(
+    id            serial primary key,
+    owner_user_id text        not null references users (id),
+    bot_user_id   text        not null references users (id),
+    name          text        not null,
+    key_prefix    text        not null,
+    secret_hash   text        not null unique,
+    scopes        text[]      not null,
+    created_at    timestamptz not null default (now() at time zone 'utc'),
+    last_used_at  timestamptz,
+    revoked_at    timestamptz
+);
+
+create index api_keys_owner_user_id_idx on api_keys (owner_user_id);
@@ -0,0 +1,19 @@
+use crab_messenger::utils::identity_provider::LocalIdentityProvider;
+
+/// Prints a token for one of the `LOCAL_AUTH_USERS`, to be used as `TEST_TOKEN_1`
+/// and friends when the server runs with `AUTH_PROVIDER=local`.
+fn main() -> anyhow::Result<()> {
+    let user_id = std::env::args()
+        .nth(1)
+        .ok_or_else(|| anyhow::anyhow!("Usage: issue-local-token <user_id> [hours]"))?;
+    let hours = match std::env::args().nth(2) {
+        Some(hours) => hours.parse()?,
+        None => 24,
+    };
+
+    let provider = LocalIdentityProvider::from_env()?;
+    let token = provider.issue_token(&user_id, chrono::Duration::hours(hours))?;
+
+    println!("{}", token);
+    Ok(())
+}
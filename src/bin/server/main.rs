@@ -1,4 +1,5 @@
 use crab_messenger::server::{build_server_module, Server};
+use crab_messenger::utils::identity_provider::AuthProvider;
 use shaku::HasComponent;
 use std::sync::Arc;
 use tracing_subscriber::{fmt, EnvFilter};
@@ -11,6 +12,9 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
+    let auth_provider = AuthProvider::from_env()?;
+    tracing::info!("Authenticating with {:?}", auth_provider);
+
     let module = build_server_module();
     let server: Arc<dyn Server> = module.resolve();
     server.run_server().await?;
@@ -8,7 +8,10 @@ use tokio_stream::wrappers::ReceiverStream;
 use tonic::metadata::MetadataValue;
 use tonic::{Request, Status};
 
-use crab_messenger::utils::messenger::{messenger_client::MessengerClient, SendMessage};
+use crab_messenger::utils::messenger::chat_request::Request as ChatRequestKind;
+use crab_messenger::utils::messenger::{
+    messenger_client::MessengerClient, ChatRequest, SendMessage,
+};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -64,9 +67,15 @@ async fn main() -> Result<()> {
         let send_msg = SendMessage {
             text: trimmed.to_string(),
             chat_id,
+            reply_to_message_id: None,
+            attachment_ids: vec![],
         };
 
-        tx.send(send_msg).await.expect("Failed to send message");
+        let chat_request = ChatRequest {
+            request: Some(ChatRequestKind::Message(send_msg)),
+        };
+
+        tx.send(chat_request).await.expect("Failed to send message");
         input.clear();
     }
 
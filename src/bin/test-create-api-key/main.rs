@@ -0,0 +1,27 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::CreateApiKeyRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let mut request = tonic::Request::new(CreateApiKeyRequest {
+        name: "CI bot".to_string(),
+        scopes: vec!["messages:read".to_string(), "messages:write".to_string()],
+        chat_ids: vec![1],
+    });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.create_api_key(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::CreateInviteLinkRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let mut request = tonic::Request::new(CreateInviteLinkRequest {
+        chat_id: 1,
+        max_uses: Some(5),
+        expires_at: None,
+    });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.create_invite_link(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
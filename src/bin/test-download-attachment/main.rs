@@ -0,0 +1,34 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::DownloadAttachmentRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let attachment_id = std::env::args()
+        .nth(1)
+        .expect("Usage: test-download-attachment <attachment id>")
+        .parse()?;
+
+    let mut request = tonic::Request::new(DownloadAttachmentRequest { attachment_id });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let mut stream = client.download_attachment(request).await?.into_inner();
+
+    let mut size = 0;
+    while let Some(chunk) = stream.message().await? {
+        println!("CHUNK={} bytes", chunk.data.len());
+        size += chunk.data.len();
+    }
+
+    println!("RESPONSE={} bytes", size);
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::{DeleteMessageRequest, EditMessageRequest};
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+
+    let message_id = 1;
+
+    let mut request = tonic::Request::new(EditMessageRequest {
+        message_id,
+        text: "Edited message".to_string(),
+    });
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata.clone());
+
+    let response = client.edit_message(request).await?;
+    println!("RESPONSE={:?}", response);
+
+    let mut request = tonic::Request::new(DeleteMessageRequest { message_id });
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.delete_message(request).await?;
+    println!("RESPONSE={:?}", response);
+
+    Ok(())
+}
@@ -22,6 +22,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut request = tonic::Request::new(GetMessagesRequest {
         chat_id,
         created_before: Some(timestamp),
+        before_id: None,
+        after_id: None,
+        limit: 20,
     });
 
 
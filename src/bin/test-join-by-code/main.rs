@@ -0,0 +1,27 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::JoinByCodeRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_2").expect("Failed to get token");
+
+    let code = std::env::args()
+        .nth(1)
+        .expect("Usage: test-join-by-code <code>");
+
+    let mut request = tonic::Request::new(JoinByCodeRequest { code });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.join_by_code(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
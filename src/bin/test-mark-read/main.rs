@@ -0,0 +1,29 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::MarkReadRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let chat_id = 1;
+    let message_id = 1;
+
+    let mut request = tonic::Request::new(MarkReadRequest {
+        chat_id,
+        message_id,
+    });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.mark_read(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
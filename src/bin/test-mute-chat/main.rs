@@ -0,0 +1,26 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::MuteChatRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let chat_id = 1;
+    let muted = std::env::args().nth(1).as_deref() != Some("off");
+
+    let mut request = tonic::Request::new(MuteChatRequest { chat_id, muted });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.mute_chat(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::RemoveMemberRequest;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let chat_id = 1;
+    let user_id = "auth0|657821f5a1e9bf99450fff22".to_string();
+
+    let mut request = tonic::Request::new(RemoveMemberRequest { chat_id, user_id });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.remove_member(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
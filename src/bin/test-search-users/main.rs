@@ -0,0 +1,32 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::SearchUserQuery;
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+    let query = std::env::args()
+        .nth(1)
+        .expect("Usage: test-search-users <query>");
+
+    let mut request = tonic::Request::new(SearchUserQuery {
+        user_id: None,
+        email: None,
+        query: Some(query),
+        offset: 0,
+        limit: 10,
+    });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.search_user(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
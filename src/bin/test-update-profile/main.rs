@@ -0,0 +1,28 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::{EmailVisibility, UpdateProfileRequest};
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let mut request = tonic::Request::new(UpdateProfileRequest {
+        display_name: Some("Test User".to_string()),
+        status_message: Some("Testing profiles".to_string()),
+        timezone: Some("Europe/Kyiv".to_string()),
+        email_visibility: Some(EmailVisibility::Nobody as i32),
+    });
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.update_profile(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
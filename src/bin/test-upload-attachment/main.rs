@@ -0,0 +1,40 @@
+use crab_messenger::utils::messenger::messenger_client::MessengerClient;
+use crab_messenger::utils::messenger::upload_attachment_request::Payload;
+use crab_messenger::utils::messenger::{AttachmentInfo, UploadAttachmentRequest};
+use std::str::FromStr;
+use tonic::metadata::MetadataValue;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let mut client = MessengerClient::connect("http://[::1]:50051").await?;
+    dotenv::dotenv().ok();
+    let auth_token = std::env::var("TEST_TOKEN_1").expect("Failed to get token");
+
+    let chat_id = 1;
+    let path = std::env::args()
+        .nth(1)
+        .expect("Usage: test-upload-attachment <path>");
+    let data = std::fs::read(&path)?;
+
+    let mut requests = vec![UploadAttachmentRequest {
+        payload: Some(Payload::Info(AttachmentInfo {
+            chat_id,
+            file_name: path,
+        })),
+    }];
+    requests.extend(data.chunks(64 * 1024).map(|chunk| UploadAttachmentRequest {
+        payload: Some(Payload::Chunk(chunk.to_vec())),
+    }));
+
+    let mut request = tonic::Request::new(tokio_stream::iter(requests));
+
+    let token_metadata = MetadataValue::from_str(&auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let response = client.upload_attachment(request).await?;
+
+    println!("RESPONSE={:?}", response);
+    Ok(())
+}
@@ -41,6 +41,8 @@ impl Input for InputImpl {
                 }
 
                 dispatch.send(Action::Input(e)).expect("Couldn't send");
+            } else {
+                dispatch.send(Action::Tick).expect("Couldn't send");
             }
         });
     }
@@ -1,6 +1,9 @@
 use crate::client::redux::state::State;
 use crate::utils::auth::{AuthState, StartFlowResponse};
-use crate::utils::messenger::{Chat, Message, SendMessage, User};
+use crate::utils::messenger::{
+    Attachment, Chat, MemberRemoved, Message, MessageReactions, MessageUnpinned, PinnedMessage,
+    Presence, SearchHit, SendMessage, Typing, User,
+};
 use crossterm::event::Event;
 
 #[derive(Clone)]
@@ -13,14 +16,58 @@ pub enum Action {
     Tick,
     LoadUsers,
     LoadUsersSuccess(Vec<User>),
+    LoadPresence,
+    LoadPresenceSuccess(Vec<Presence>),
+    SetupPresenceStream,
+    PresenceUpdated(Presence),
     LoadChats,
     LoadChatsSuccess(Vec<Chat>),
     CheckChat,
     LoadMessages,
-    LoadMessagesSuccess(i32, Vec<Message>),
+    LoadMessagesSuccess(i32, Vec<Message>, bool),
     SetupMessagesStream,
     ReceivedMessage(Message),
+    UpdatedMessage(Message),
     SendMessage(SendMessage),
+    EditMessage(i32, String),
+    DeleteMessage(i32),
+    ReactionsChanged(MessageReactions),
+    AddReaction(i32, String),
+    RemoveReaction(i32, String),
+    LoadPinnedMessages(i32),
+    LoadPinnedMessagesSuccess(i32, Vec<PinnedMessage>),
+    PinMessage(i32, bool),
+    MessagePinned(PinnedMessage),
+    MessageUnpinned(MessageUnpinned),
+    MarkRead,
+    SetTyping(i32, bool),
+    TypingStarted(Typing),
+    TypingStopped(Typing),
+    OpenDirectChat(String),
+    DirectChatOpened(Chat),
+    LeaveChat(i32),
+    MuteChat(i32, bool),
+    ChatMuted(i32, bool),
+    MemberRemoved(MemberRemoved),
+    ChatUpdated(Chat),
+    ChatDeleted(i32),
+    SearchMessages(String, u32),
+    SearchMessagesSuccess(String, u32, Vec<SearchHit>, bool),
+    JumpToMessage(i32, i32),
+    UploadAttachment(i32, String),
+    AttachmentUploaded(i32, Attachment),
+    SaveAttachments(Vec<Attachment>),
+    SearchUsers(String, u32),
+    SearchUsersSuccess(String, u32, Vec<User>, bool),
+    SendInvite(String, i32),
+    InviteSent(String, i32),
+    LoadBlocked,
+    LoadBlockedSuccess(Vec<User>),
+    BlockUser(String, bool),
+    UserBlocked(String, bool),
+    LoadMentions(Option<i32>),
+    LoadMentionsSuccess(Option<i32>, Vec<Message>, bool),
+    Mentioned(Message),
 }
 
 pub enum ReduceResult {
@@ -4,15 +4,22 @@ use crate::client::redux::reducers::app::chats::{
 };
 use crate::client::redux::reducers::app::login::ReducersLoginModule;
 use crate::client::redux::reducers::app::login::{build_reducers_login_module, LoginReducer};
+use crate::client::redux::reducers::app::mentions::{MentionsReducer, MentionsReducerImpl};
 use crate::client::redux::reducers::app::messages::{
     build_messages_reducer_module, MessagesReducer, MessagesReducerModule,
 };
+use crate::client::redux::reducers::app::search::{
+    build_search_reducer_module, SearchReducer, SearchReducerModule,
+};
 use crate::client::redux::reducers::app::server::{
     build_server_reducer_module, ServerReducer, ServerReducerModule,
 };
 use crate::client::redux::reducers::app::typing::{
     build_typing_reducer_module, TypingReducer, TypingReducerModule,
 };
+use crate::client::redux::reducers::app::users::{
+    build_users_reducer_module, UsersReducer, UsersReducerModule,
+};
 use crate::client::redux::reducers::Reducer;
 use crate::client::redux::state::client_chat::ChatsState;
 use crate::client::redux::state::tab::TabState;
@@ -28,10 +35,16 @@ mod server;
 
 mod chats;
 
+mod mentions;
+
 mod messages;
 
+mod search;
+
 mod typing;
 
+mod users;
+
 pub trait AppReducer: Reducer + Interface {}
 
 #[derive(Component)]
@@ -47,6 +60,12 @@ pub struct AppReducerImpl {
     messages_reducer: Arc<dyn MessagesReducer>,
     #[shaku(inject)]
     typing_reducer: Arc<dyn TypingReducer>,
+    #[shaku(inject)]
+    users_reducer: Arc<dyn UsersReducer>,
+    #[shaku(inject)]
+    search_reducer: Arc<dyn SearchReducer>,
+    #[shaku(inject)]
+    mentions_reducer: Arc<dyn MentionsReducer>,
 }
 
 impl Reducer for AppReducerImpl {
@@ -94,7 +113,15 @@ impl Reducer for AppReducerImpl {
                 }
                 ChatsState::Typing => {
                     self.typing_reducer
-                        .reduce(action, state, dispatch_tx, handle)
+                        .reduce(action, state, dispatch_tx.clone(), handle.clone())
+                }
+                ChatsState::Search => {
+                    self.search_reducer
+                        .reduce(action, state, dispatch_tx.clone(), handle.clone())
+                }
+                ChatsState::Mentions => {
+                    self.mentions_reducer
+                        .reduce(action, state, dispatch_tx.clone(), handle.clone())
                 }
             };
 
@@ -108,6 +135,7 @@ impl Reducer for AppReducerImpl {
                     KeyCode::Char('l') => {
                         let mut new_state = state.clone();
                         new_state.chats_state = ChatsState::Messages;
+                        dispatch_tx.send(Action::MarkRead).unwrap();
                         return ReduceResult::Consumed(new_state);
                     }
                     KeyCode::Char('h') => {
@@ -118,6 +146,23 @@ impl Reducer for AppReducerImpl {
                     KeyCode::Char('i') => {
                         let mut new_state = state.clone();
                         new_state.chats_state = ChatsState::Typing;
+                        dispatch_tx.send(Action::MarkRead).unwrap();
+                        return ReduceResult::Consumed(new_state);
+                    }
+                    KeyCode::Char('/') => {
+                        let mut new_state = state.clone();
+                        new_state.chats_state = ChatsState::Search;
+                        return ReduceResult::Consumed(new_state);
+                    }
+                    KeyCode::Char('@') => {
+                        let mut new_state = state.clone();
+                        new_state.chats_state = ChatsState::Mentions;
+                        new_state.mentions.unseen = 0;
+                        if new_state.mentions.selected_mention.is_none()
+                            && !new_state.mentions.messages.is_empty()
+                        {
+                            new_state.mentions.selected_mention = Some(0);
+                        }
                         return ReduceResult::Consumed(new_state);
                     }
                     KeyCode::Esc => {
@@ -130,6 +175,17 @@ impl Reducer for AppReducerImpl {
             }
         }
 
+        if state.tab_state == TabState::Users {
+            let users_result =
+                self.users_reducer
+                    .reduce(action, state, dispatch_tx.clone(), handle);
+
+            match users_result {
+                ReduceResult::Ignored => {}
+                _ => return users_result,
+            }
+        }
+
         if let Action::Input(Event::Key(key)) = action {
             if key.code == KeyCode::Char('1') {
                 let mut new_state = state.clone();
@@ -165,7 +221,7 @@ impl AppReducer for AppReducerImpl {}
 
 module! {
     pub ReducersAppModule {
-        components = [AppReducerImpl],
+        components = [AppReducerImpl, MentionsReducerImpl],
         providers = [],
         use ReducersLoginModule {
             components = [dyn LoginReducer],
@@ -186,6 +242,14 @@ module! {
         use TypingReducerModule {
             components = [dyn TypingReducer],
             providers = [],
+        },
+        use UsersReducerModule {
+            components = [dyn UsersReducer],
+            providers = [],
+        },
+        use SearchReducerModule {
+            components = [dyn SearchReducer],
+            providers = [],
         }
     }
 }
@@ -197,6 +261,8 @@ pub fn build_reducers_app_module() -> Arc<ReducersAppModule> {
             build_chats_reducer_module(),
             build_messages_reducer_module(),
             build_typing_reducer_module(),
+            build_users_reducer_module(),
+            build_search_reducer_module(),
         )
         .build(),
     )
@@ -32,6 +32,30 @@ impl Reducer for ChatsReducerImpl {
                 let state_changed = match key_event.code {
                     KeyCode::Char('j') => self.update_selected_chat(&mut new_state, true),
                     KeyCode::Char('k') => self.update_selected_chat(&mut new_state, false),
+                    KeyCode::Char('x') => {
+                        if let Some(chat_id) = state
+                            .selected_chat
+                            .and_then(|i| state.chats.read().ok()?.get(i).map(|chat| chat.id))
+                        {
+                            dispatch_tx.send(Action::LeaveChat(chat_id)).unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    KeyCode::Char('m') => {
+                        if let Some((chat_id, muted)) = state.selected_chat.and_then(|i| {
+                            state
+                                .chats
+                                .read()
+                                .ok()?
+                                .get(i)
+                                .map(|chat| (chat.id, chat.muted))
+                        }) {
+                            dispatch_tx.send(Action::MuteChat(chat_id, !muted)).unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
                     _ => false,
                 };
 
@@ -2,6 +2,7 @@ use crate::client::redux::action::Action::LoginSuccess;
 use crate::client::redux::action::{Action, ReduceResult};
 use crate::client::redux::reducers::Reducer;
 use crate::client::redux::state::State;
+use crate::utils::auth::token::AccessToken;
 use crate::utils::auth::AuthModule;
 use crate::utils::auth::{build_auth_module, Auth};
 use crossbeam_channel::Sender;
@@ -70,6 +71,8 @@ impl Reducer for LoginReducerImpl {
                 let refresh_token = auth_state.refresh_token.clone();
                 let expires_in = auth_state.expires_in;
                 new_state.auth_state = Some(auth_state.clone());
+                new_state.user_id =
+                    AccessToken::read_unverified(&auth_state.access_token).map(|token| token.id);
 
                 let tx = dispatch_tx.clone();
                 let auth = self.auth.clone();
@@ -0,0 +1,80 @@
+use crossbeam_channel::Sender;
+use crossterm::event::{Event, KeyCode};
+use shaku::{Component, Interface};
+use tokio::runtime::Handle;
+
+use crate::client::redux::action::{Action, ReduceResult};
+use crate::client::redux::reducers::Reducer;
+use crate::client::redux::state::client_chat::ChatsState;
+use crate::client::redux::state::State;
+
+pub trait MentionsReducer: Interface + Reducer {}
+
+#[derive(Component)]
+#[shaku(interface = MentionsReducer)]
+pub struct MentionsReducerImpl {}
+
+impl MentionsReducer for MentionsReducerImpl {}
+
+impl Reducer for MentionsReducerImpl {
+    fn reduce(
+        &self,
+        action: &Action,
+        state: &State,
+        dispatch_tx: Sender<Action>,
+        _handle: Handle,
+    ) -> ReduceResult {
+        match action {
+            Action::Input(Event::Key(key_event)) => {
+                let mut new_state = state.clone();
+                let mentions = &mut new_state.mentions;
+
+                let state_changed = match key_event.code {
+                    KeyCode::Char('j') | KeyCode::Down => match mentions.selected_mention {
+                        Some(selected) if selected + 1 < mentions.messages.len() => {
+                            mentions.selected_mention = Some(selected + 1);
+                            true
+                        }
+                        // Past the last mention the next page is fetched and selected once it arrives.
+                        _ if mentions.has_more && !mentions.loading => {
+                            mentions.loading = true;
+                            let before_id = mentions.messages.last().map(|m| m.id);
+                            dispatch_tx.send(Action::LoadMentions(before_id)).unwrap();
+                            true
+                        }
+                        _ => false,
+                    },
+                    KeyCode::Char('k') | KeyCode::Up => match mentions.selected_mention {
+                        Some(selected) if selected > 0 => {
+                            mentions.selected_mention = Some(selected - 1);
+                            true
+                        }
+                        _ => false,
+                    },
+                    KeyCode::Enter => {
+                        if let Some(message) = mentions.get_selected_mention() {
+                            dispatch_tx
+                                .send(Action::JumpToMessage(message.chat_id, message.id))
+                                .unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    KeyCode::Esc => {
+                        new_state.chats_state = ChatsState::Messages;
+                        true
+                    }
+                    _ => false,
+                };
+
+                if state_changed {
+                    ReduceResult::Consumed(new_state)
+                } else {
+                    ReduceResult::Ignored
+                }
+            }
+
+            _ => ReduceResult::Ignored,
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::client::redux::action::{Action, ReduceResult};
 use crate::client::redux::reducers::app::chats::ChatsReducerImpl;
 use crate::client::redux::reducers::Reducer;
+use crate::client::redux::state::client_chat::{ChatsState, REACTION_EMOJIS};
 use crate::client::redux::state::State;
+use crate::utils::messenger::{Attachment, ChatRole};
 use crossbeam_channel::Sender;
 use crossterm::event::{Event, KeyCode};
 use shaku::{module, Component, Interface};
@@ -32,6 +34,10 @@ impl Reducer for MessagesReducerImpl {
     ) -> ReduceResult {
         match action {
             Action::Input(Event::Key(key_event)) => {
+                if state.reacting {
+                    return self.pick_reaction(state, key_event.code, dispatch_tx);
+                }
+
                 let mut new_state = state.clone();
 
                 let state_changed = match key_event.code {
@@ -44,6 +50,39 @@ impl Reducer for MessagesReducerImpl {
                     KeyCode::Char('g') => {
                         self.update_selected_message(&mut new_state, MoveDirection::Start)
                     }
+                    KeyCode::Char('e') => return self.start_editing(state),
+                    KeyCode::Char('r') => return self.start_replying(state),
+                    KeyCode::Char('a') => {
+                        if self.selected_message_id(state).is_some() {
+                            new_state.reacting = true;
+                            return ReduceResult::Consumed(new_state);
+                        }
+                        false
+                    }
+                    KeyCode::Char('s') => {
+                        if let Some(attachments) = self.selected_message_attachments(state) {
+                            dispatch_tx
+                                .send(Action::SaveAttachments(attachments))
+                                .unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    KeyCode::Char('p') => {
+                        if let Some(action) = self.toggle_pin(state) {
+                            dispatch_tx.send(action).unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    KeyCode::Char('P') => self.toggle_pinned_bar(&mut new_state),
+                    KeyCode::Char('d') => {
+                        if let Some(message_id) = self.selected_message_id(state) {
+                            dispatch_tx.send(Action::DeleteMessage(message_id)).unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
                     _ => false,
                 };
 
@@ -77,6 +116,144 @@ impl Reducer for MessagesReducerImpl {
 }
 
 impl MessagesReducerImpl {
+    fn selected_message_id(&self, state: &State) -> Option<i32> {
+        let selected_chat_index = state.selected_chat?;
+        let chats_lock = state.chats.read().ok()?;
+        let chat = chats_lock.get(selected_chat_index)?;
+        chat.get_selected_message()
+            .filter(|message| message.deleted_at.is_none())
+            .map(|message| message.id)
+    }
+
+    /// Pins the selected message or unpins it if it already is, admins only.
+    fn toggle_pin(&self, state: &State) -> Option<Action> {
+        let message_id = self.selected_message_id(state)?;
+        let chats_lock = state.chats.read().ok()?;
+        let chat = chats_lock.get(state.selected_chat?)?;
+
+        if chat.role < ChatRole::Admin {
+            return None;
+        }
+
+        Some(Action::PinMessage(message_id, !chat.is_pinned(message_id)))
+    }
+
+    fn toggle_pinned_bar(&self, state: &mut State) -> bool {
+        let selected_chat_index = match state.selected_chat {
+            Some(selected_chat_index) => selected_chat_index,
+            None => return false,
+        };
+
+        match state.chats.write() {
+            Ok(mut chats_lock) => match chats_lock.get_mut(selected_chat_index) {
+                Some(chat) if !chat.pinned_messages.is_empty() => {
+                    chat.pinned_expanded = !chat.pinned_expanded;
+                    true
+                }
+                _ => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    fn selected_message_attachments(&self, state: &State) -> Option<Vec<Attachment>> {
+        let selected_chat_index = state.selected_chat?;
+        let chats_lock = state.chats.read().ok()?;
+        let chat = chats_lock.get(selected_chat_index)?;
+        chat.get_selected_message()
+            .filter(|message| message.deleted_at.is_none() && !message.attachments.is_empty())
+            .map(|message| message.attachments.clone())
+    }
+
+    fn pick_reaction(
+        &self,
+        state: &State,
+        key_code: KeyCode,
+        dispatch_tx: Sender<Action>,
+    ) -> ReduceResult {
+        let mut new_state = state.clone();
+        new_state.reacting = false;
+
+        let emoji = match key_code {
+            KeyCode::Char(c) => c
+                .to_digit(10)
+                .and_then(|digit| REACTION_EMOJIS.get((digit as usize).checked_sub(1)?)),
+            _ => None,
+        };
+
+        if let (Some(emoji), Some(message_id)) = (emoji, self.selected_message_id(state)) {
+            let already_reacted = self.has_reacted(state, message_id, emoji);
+            let emoji = emoji.to_string();
+            let action = if already_reacted {
+                Action::RemoveReaction(message_id, emoji)
+            } else {
+                Action::AddReaction(message_id, emoji)
+            };
+            dispatch_tx.send(action).unwrap();
+        }
+
+        ReduceResult::Consumed(new_state)
+    }
+
+    fn has_reacted(&self, state: &State, message_id: i32, emoji: &str) -> bool {
+        let user_id = match &state.user_id {
+            Some(user_id) => user_id,
+            None => return false,
+        };
+        let chats_lock = match state.chats.read() {
+            Ok(chats_lock) => chats_lock,
+            Err(_) => return false,
+        };
+
+        state
+            .selected_chat
+            .and_then(|i| chats_lock.get(i))
+            .and_then(|chat| chat.messages.iter().find(|m| m.id == message_id))
+            .and_then(|message| message.reactions.iter().find(|r| r.emoji == emoji))
+            .is_some_and(|reaction| reaction.user_ids.contains(user_id))
+    }
+
+    fn start_editing(&self, state: &State) -> ReduceResult {
+        let mut new_state = state.clone();
+
+        if let Some(selected_chat_index) = new_state.selected_chat {
+            if let Ok(mut chats_lock) = new_state.chats.write() {
+                if let Some(chat) = chats_lock.get_mut(selected_chat_index) {
+                    if let Some((id, text)) = chat
+                        .get_selected_message()
+                        .filter(|message| message.deleted_at.is_none())
+                        .map(|message| (message.id, message.text.clone()))
+                    {
+                        chat.editing_message = Some(id);
+                        chat.text = text;
+                    } else {
+                        return ReduceResult::Ignored;
+                    }
+                }
+            }
+        }
+
+        new_state.chats_state = ChatsState::Typing;
+        ReduceResult::Consumed(new_state)
+    }
+
+    fn start_replying(&self, state: &State) -> ReduceResult {
+        let mut new_state = state.clone();
+
+        if let Some(message_id) = self.selected_message_id(state) {
+            if let Ok(mut chats_lock) = new_state.chats.write() {
+                if let Some(chat) = state.selected_chat.and_then(|i| chats_lock.get_mut(i)) {
+                    chat.replying_to = Some(message_id);
+                }
+            }
+
+            new_state.chats_state = ChatsState::Typing;
+            return ReduceResult::Consumed(new_state);
+        }
+
+        ReduceResult::Ignored
+    }
+
     fn update_selected_message(&self, state: &mut State, direction: MoveDirection) -> bool {
         if let Some(selected_chat_index) = state.selected_chat {
             if let Ok(mut chats_lock) = state.chats.write() {
@@ -0,0 +1,114 @@
+use std::sync::Arc;
+
+use crossbeam_channel::Sender;
+use crossterm::event::{Event, KeyCode};
+use shaku::{module, Component, Interface};
+use tokio::runtime::Handle;
+
+use crate::client::redux::action::{Action, ReduceResult};
+use crate::client::redux::reducers::Reducer;
+use crate::client::redux::state::client_chat::ChatsState;
+use crate::client::redux::state::State;
+
+pub trait SearchReducer: Interface + Reducer {}
+
+#[derive(Component)]
+#[shaku(interface = SearchReducer)]
+pub struct SearchReducerImpl {}
+
+impl SearchReducer for SearchReducerImpl {}
+
+impl Reducer for SearchReducerImpl {
+    fn reduce(
+        &self,
+        action: &Action,
+        state: &State,
+        dispatch_tx: Sender<Action>,
+        _handle: Handle,
+    ) -> ReduceResult {
+        match action {
+            Action::Input(Event::Key(key_event)) => {
+                let mut new_state = state.clone();
+                let search = &mut new_state.search;
+
+                let state_changed = match key_event.code {
+                    KeyCode::Char(c) => {
+                        search.query.push(c);
+                        true
+                    }
+                    KeyCode::Backspace => search.query.pop().is_some(),
+                    KeyCode::Down => match search.selected_hit {
+                        Some(selected_hit) if selected_hit + 1 < search.hits.len() => {
+                            search.selected_hit = Some(selected_hit + 1);
+                            true
+                        }
+                        // Past the last hit the next page is fetched and selected once it arrives.
+                        _ if search.has_more && !search.searching => {
+                            search.searching = true;
+                            dispatch_tx
+                                .send(Action::SearchMessages(
+                                    search.hits_query.clone(),
+                                    search.hits.len() as u32,
+                                ))
+                                .unwrap();
+                            true
+                        }
+                        _ => false,
+                    },
+                    KeyCode::Up => match search.selected_hit {
+                        Some(selected_hit) if selected_hit > 0 => {
+                            search.selected_hit = Some(selected_hit - 1);
+                            true
+                        }
+                        _ => false,
+                    },
+                    KeyCode::Enter => {
+                        let query = search.query.trim().to_string();
+                        match search
+                            .get_selected_hit()
+                            .and_then(|hit| hit.message.as_ref())
+                        {
+                            // Enter searches again once the prompt no longer matches the hits.
+                            Some(message) if query == search.hits_query => {
+                                dispatch_tx
+                                    .send(Action::JumpToMessage(message.chat_id, message.id))
+                                    .unwrap();
+                                return ReduceResult::ConsumedButKindaNot;
+                            }
+                            _ if !query.is_empty() => {
+                                search.searching = true;
+                                dispatch_tx.send(Action::SearchMessages(query, 0)).unwrap();
+                                true
+                            }
+                            _ => false,
+                        }
+                    }
+                    KeyCode::Esc => {
+                        new_state.chats_state = ChatsState::Messages;
+                        true
+                    }
+                    _ => false,
+                };
+
+                if state_changed {
+                    ReduceResult::Consumed(new_state)
+                } else {
+                    ReduceResult::Ignored
+                }
+            }
+
+            _ => ReduceResult::Ignored,
+        }
+    }
+}
+
+module! {
+    pub SearchReducerModule {
+        components = [SearchReducerImpl],
+        providers = [],
+    }
+}
+
+pub fn build_search_reducer_module() -> Arc<SearchReducerModule> {
+    Arc::new(SearchReducerModule::builder().build())
+}
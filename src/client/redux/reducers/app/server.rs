@@ -1,28 +1,49 @@
 use std::collections::HashSet;
+use std::path::Path;
 use std::str::FromStr;
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use anyhow::Error;
 use crossbeam_channel::Sender;
 use futures::stream::StreamExt;
 use shaku::{module, Component, Interface};
 use tokio::runtime::Handle;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, Mutex};
 use tokio::time::sleep;
-use tokio_stream::wrappers::ReceiverStream;
 use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
 use tonic::Request;
 
 use crate::client::redux::action::{Action, ReduceResult};
 use crate::client::redux::reducers::Reducer;
-use crate::client::redux::state::client_chat::ClientChatState;
+use crate::client::redux::state::client_chat::{
+    ChatsState, ClientChatState, ATTACHMENT_CHUNK_SIZE, DOWNLOADS_DIR, MESSAGES_PAGE_SIZE,
+    TYPING_TIMEOUT,
+};
+use crate::client::redux::state::mentions::MENTIONS_PAGE_SIZE;
+use crate::client::redux::state::search::SEARCH_PAGE_SIZE;
+use crate::client::redux::state::tab::TabState;
+use crate::client::redux::state::user_search::USER_SEARCH_PAGE_SIZE;
 use crate::client::redux::state::State;
+use crate::utils::messenger::chat_event::Event;
+use crate::utils::messenger::chat_request::Request as ChatRequestKind;
 use crate::utils::messenger::messenger_client::MessengerClient;
+use crate::utils::messenger::upload_attachment_request::Payload;
 use crate::utils::messenger::{
-    GetMessagesRequest, GetRelatedUsersRequest, GetUserChatsRequest, SendMessage,
+    Attachment, AttachmentInfo, BlockUserRequest, ChatRequest, DeleteMessageRequest,
+    DownloadAttachmentRequest, EditMessageRequest, GetMentionsRequest, GetMessagesRequest,
+    GetPinnedMessagesRequest, GetPresenceRequest, GetRelatedUsersRequest, GetUserChatsRequest,
+    LeaveChatRequest, ListBlockedRequest, MarkReadRequest, MemberRemoved, MuteChatRequest,
+    OpenDirectChatRequest, PinMessageRequest, PresenceUpdatesRequest, ReactionRequest,
+    ResumeRequest, SearchMessagesRequest, SearchUserQuery, SendInviteRequest, TypingRequest,
+    UnblockUserRequest, UnpinMessageRequest, UploadAttachmentRequest,
 };
 
+/// Delay before the first attempt to reconnect a dropped Chat stream.
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+/// Longest delay between two attempts to reconnect the Chat stream.
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
 pub trait ServerReducer: Reducer + Interface {}
 
 #[derive(Component)]
@@ -42,9 +63,13 @@ impl Reducer for ServerReducerImpl {
         match action {
             Action::Init => {
                 dispatch_tx.send(Action::LoadUsers).unwrap();
+                dispatch_tx.send(Action::LoadBlocked).unwrap();
                 dispatch_tx.send(Action::LoadChats).unwrap();
                 dispatch_tx.send(Action::CheckChat).unwrap();
                 dispatch_tx.send(Action::SetupMessagesStream).unwrap();
+                dispatch_tx.send(Action::LoadPresence).unwrap();
+                dispatch_tx.send(Action::SetupPresenceStream).unwrap();
+                dispatch_tx.send(Action::LoadMentions(None)).unwrap();
 
                 ReduceResult::ConsumedButKindaNot
             }
@@ -77,11 +102,88 @@ impl Reducer for ServerReducerImpl {
 
                 {
                     let mut users_lock = new_state.users.write().unwrap();
-                    users_lock.extend(users.iter().cloned().map(|mut u| {
-                        u.email = "That's confidential".to_string();
-                        u
-                    }));
+                    users_lock.extend(users.iter().cloned());
+                }
+                ReduceResult::Consumed(new_state)
+            }
+            Action::LoadPresence => {
+                let state = state.clone();
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+                    let mut request = tonic::Request::new(GetPresenceRequest { user_ids: vec![] });
+                    let auth_token = state.auth_state.unwrap().access_token;
+
+                    let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                    request
+                        .metadata_mut()
+                        .insert("authorization", token_metadata);
+
+                    match client.get_presence(request).await {
+                        Ok(response) => dispatch_tx
+                            .send(Action::LoadPresenceSuccess(response.into_inner().presences))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::LoadPresenceSuccess(presences) => {
+                let new_state = state.clone();
+
+                if let Ok(mut presences_lock) = new_state.presences.write() {
+                    presences_lock.extend(
+                        presences
+                            .iter()
+                            .cloned()
+                            .map(|presence| (presence.user_id.clone(), presence)),
+                    );
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::SetupPresenceStream => {
+                let mut request = tonic::Request::new(PresenceUpdatesRequest {});
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+                    let response_stream = client
+                        .presence_updates(request)
+                        .await
+                        .expect("Failed to start presence updates")
+                        .into_inner();
+                    response_stream
+                        .for_each(|presence| async {
+                            match presence {
+                                Ok(presence) => dispatch_tx
+                                    .clone()
+                                    .send(Action::PresenceUpdated(presence))
+                                    .unwrap(),
+                                Err(e) => eprintln!("Error: {:?}", e),
+                            }
+                        })
+                        .await;
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::PresenceUpdated(presence) => {
+                let new_state = state.clone();
+
+                if let Ok(mut presences_lock) = new_state.presences.write() {
+                    presences_lock.insert(presence.user_id.clone(), presence.clone());
                 }
+
                 ReduceResult::Consumed(new_state)
             }
             Action::LoadChats => {
@@ -112,74 +214,79 @@ impl Reducer for ServerReducerImpl {
 
                 {
                     let mut chats_lock = new_state.chats.write().unwrap();
-                    let client_chats: Vec<ClientChatState> =
-                        chats.iter().map(|chat| chat.into()).collect();
+                    // Chats are reloaded when one shows up, the known ones keep their state.
+                    let known_chat_ids: HashSet<_> = chats_lock.iter().map(|c| c.id).collect();
+                    let client_chats: Vec<ClientChatState> = chats
+                        .iter()
+                        .filter(|chat| !known_chat_ids.contains(&chat.id))
+                        .map(|chat| chat.into())
+                        .collect();
 
                     chats_lock.extend(client_chats);
                 }
                 ReduceResult::Consumed(new_state)
             }
             Action::LoadMessages => {
-                if let Some(selected_chat) = state.selected_chat {
-                    if let Ok(chats_lock) = state.chats.read() {
-                        if let Some(chat) = chats_lock.get(selected_chat) {
-                            let chat_id = chat.id;
-                            let now = SystemTime::now();
-                            let since_the_epoch = now.duration_since(UNIX_EPOCH).unwrap();
-                            let timestamp = prost_types::Timestamp {
-                                seconds: since_the_epoch.as_secs() as i64,
-                                nanos: since_the_epoch.subsec_nanos() as i32,
-                            };
+                let new_state = state.clone();
+                let mut page = None;
 
-                            let timestamp = if chat.messages.is_empty() {
-                                timestamp
-                            } else {
-                                match chat.messages.last() {
-                                    Some(message) => {
-                                        let created_at = message.created_at.clone();
-                                        match created_at {
-                                            Some(created_at) => created_at,
-                                            None => timestamp,
-                                        }
-                                    }
-                                    None => timestamp,
-                                }
-                            };
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = state.selected_chat.and_then(|i| chats_lock.get_mut(i)) {
+                        if chat.has_more_messages && !chat.loading_messages {
+                            chat.loading_messages = true;
+                            page = Some((chat.id, chat.messages.first().map(|m| m.id)));
+                        }
+                    }
+                }
 
-                            let mut request = tonic::Request::new(GetMessagesRequest {
-                                chat_id,
-                                created_before: Some(timestamp),
-                            });
+                let (chat_id, before_id) = match page {
+                    Some(page) => page,
+                    None => return ReduceResult::Ignored,
+                };
 
-                            let server_address = dotenv!("SERVER_ADDRESS");
-                            let auth_token = state.auth_state.clone().unwrap().access_token;
+                if before_id.is_none() {
+                    dispatch_tx
+                        .send(Action::LoadPinnedMessages(chat_id))
+                        .unwrap();
+                }
 
-                            let token_metadata =
-                                MetadataValue::from_str(&format!("{}", auth_token)).unwrap();
-                            request
-                                .metadata_mut()
-                                .insert("authorization", token_metadata);
+                let mut request = tonic::Request::new(GetMessagesRequest {
+                    chat_id,
+                    created_before: None,
+                    before_id,
+                    after_id: None,
+                    limit: MESSAGES_PAGE_SIZE,
+                });
 
-                            handle.spawn(async move {
-                                let mut client = MessengerClient::connect(server_address)
-                                    .await
-                                    .expect("Couldn't connect to server");
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
 
-                                let response = client.get_messages(request).await.unwrap();
-                                let messages = response.into_inner().messages;
-                                let action = Action::LoadMessagesSuccess(chat_id, messages);
-                                dispatch_tx.send(action).unwrap();
-                            });
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
 
-                            return ReduceResult::ConsumedButKindaNot;
+                    let (messages, has_more) = match client.get_messages(request).await {
+                        Ok(response) => {
+                            let response = response.into_inner();
+                            (response.messages, response.has_more)
                         }
-                    }
-                }
+                        Err(e) => {
+                            eprintln!("Error: {:?}", e);
+                            (vec![], true)
+                        }
+                    };
+                    let action = Action::LoadMessagesSuccess(chat_id, messages, has_more);
+                    dispatch_tx.send(action).unwrap();
+                });
 
-                ReduceResult::Ignored
+                ReduceResult::Consumed(new_state)
             }
-            Action::LoadMessagesSuccess(chat_id, messages) => {
-                let mut new_state = state.clone();
+            Action::LoadMessagesSuccess(chat_id, messages, has_more) => {
+                let new_state = state.clone();
 
                 if let Ok(mut chats_lock) = new_state.chats.write() {
                     if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == *chat_id) {
@@ -192,19 +299,14 @@ impl Reducer for ServerReducerImpl {
                                 .cloned()
                                 .filter(|m| !existing_message_ids.contains(&m.id)),
                         );
+                        chat.messages.sort_by_key(|m| m.id);
 
-                        chat.messages.sort_by(|a, b| {
-                            match (&a.created_at, &b.created_at) {
-                                (Some(a_created_at), Some(b_created_at)) => {
-                                    a_created_at.seconds.cmp(&b_created_at.seconds)
-                                }
-                                (Some(_), None) => std::cmp::Ordering::Greater, // Assume a is greater if b is None
-                                (None, Some(_)) => std::cmp::Ordering::Less, // Assume a is less if a is None
-                                (None, None) => std::cmp::Ordering::Equal, // Equal if both are None
-                            }
-                        });
-
-                        chat.selected_message = Some(0);
+                        chat.loading_messages = false;
+                        chat.has_more_messages = *has_more;
+                        // Selection counts from the newest message, so older pages keep it in place.
+                        if chat.selected_message.is_none() && !chat.messages.is_empty() {
+                            chat.selected_message = Some(0);
+                        }
                     }
                 }
 
@@ -216,58 +318,48 @@ impl Reducer for ServerReducerImpl {
                 new_state.send_message_tx = Some(tx);
 
                 let auth_token = state.auth_state.clone().unwrap().access_token;
-
-                let token_metadata = MetadataValue::from_str(&format!("{}", auth_token))
-                    .map_err(|e| Error::new(e))
-                    .unwrap();
-                let mut request = Request::new(ReceiverStream::new(rx));
-                request
-                    .metadata_mut()
-                    .insert("authorization", token_metadata);
+                let chats = state.chats.clone();
+                let outgoing = Arc::new(Mutex::new(rx));
 
                 handle.spawn(async move {
-                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
-                        .await
-                        .unwrap();
-                    let response_stream = client
-                        .chat(request)
-                        .await
-                        .expect("Failed to start chat")
-                        .into_inner();
-                    response_stream
-                        .for_each(|message| async {
-                            match message {
-                                Ok(msg) => dispatch_tx
-                                    .clone()
-                                    .send(Action::ReceivedMessage(msg))
-                                    .unwrap(),
-                                Err(e) => eprintln!("Error: {:?}", e),
-                            }
-                        })
-                        .await;
+                    let mut backoff = RECONNECT_BACKOFF_MIN;
+                    loop {
+                        match run_chat_stream(&auth_token, &chats, outgoing.clone(), &dispatch_tx)
+                            .await
+                        {
+                            Ok(()) => backoff = RECONNECT_BACKOFF_MIN,
+                            Err(e) => eprintln!("Error: {:?}", e),
+                        }
+
+                        sleep(backoff).await;
+                        backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+                    }
                 });
 
                 ReduceResult::Consumed(new_state)
             }
             Action::ReceivedMessage(message) => {
                 let mut new_state = state.clone();
+                let focused = state.focused_chat_id() == Some(message.chat_id);
 
                 if let Ok(mut chats_lock) = new_state.chats.write() {
                     if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == message.chat_id) {
+                        if chat.last_message.as_ref().is_none_or(|m| m.id < message.id) {
+                            chat.last_message = Some(message.clone());
+
+                            if focused {
+                                dispatch_tx.send(Action::MarkRead).unwrap();
+                            } else if state.user_id.as_ref() != Some(&message.user_id) {
+                                chat.unread_count += 1;
+                            }
+                        }
+
                         let existing_message_ids: HashSet<_> =
                             chat.messages.iter().map(|m| m.id).collect();
 
                         if !existing_message_ids.contains(&message.id) {
                             chat.messages.push(message.clone());
-                            chat.messages
-                                .sort_by(|a, b| match (&a.created_at, &b.created_at) {
-                                    (Some(a_created_at), Some(b_created_at)) => {
-                                        a_created_at.seconds.cmp(&b_created_at.seconds)
-                                    }
-                                    (Some(_), None) => std::cmp::Ordering::Greater,
-                                    (None, Some(_)) => std::cmp::Ordering::Less,
-                                    (None, None) => std::cmp::Ordering::Equal,
-                                });
+                            chat.messages.sort_by_key(|m| m.id);
 
                             // Increment the selected_message if it's not None
                             if let Some(selected_message) = chat.selected_message {
@@ -276,27 +368,1200 @@ impl Reducer for ServerReducerImpl {
                                 }
                             }
                         }
+                    } else {
+                        // Someone opened a direct chat with the user, or invited them in.
+                        dispatch_tx.send(Action::LoadChats).unwrap();
                     }
                 }
 
                 ReduceResult::Consumed(new_state)
             }
-            Action::SendMessage(send_message) => {
+            Action::UpdatedMessage(message) => {
                 let mut new_state = state.clone();
 
-                if let Some(tx) = new_state.send_message_tx.clone() {
-                    let send_message_clone = send_message.clone();
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == message.chat_id) {
+                        if let Some(existing_message) =
+                            chat.messages.iter_mut().find(|m| m.id == message.id)
+                        {
+                            // Edit events don't carry attachments, deleting drops them.
+                            let attachments = match message.deleted_at {
+                                Some(_) => vec![],
+                                None => std::mem::take(&mut existing_message.attachments),
+                            };
+                            *existing_message = message.clone();
+                            existing_message.attachments = attachments;
+                        }
+
+                        match message.deleted_at {
+                            Some(_) => remove_pin(chat, message.id),
+                            None => {
+                                if let Some(pinned_message) = chat
+                                    .pinned_messages
+                                    .iter_mut()
+                                    .filter_map(|pin| pin.message.as_mut())
+                                    .find(|m| m.id == message.id)
+                                {
+                                    pinned_message.text = message.text.clone();
+                                    pinned_message.edited_at = message.edited_at.clone();
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let mentions = &mut new_state.mentions;
+                match message.deleted_at {
+                    Some(_) => {
+                        mentions.messages.retain(|m| m.id != message.id);
+                        mentions.selected_mention = match mentions.messages.len() {
+                            0 => None,
+                            len => mentions
+                                .selected_mention
+                                .map(|selected| selected.min(len - 1)),
+                        };
+                    }
+                    None => {
+                        if let Some(mention) =
+                            mentions.messages.iter_mut().find(|m| m.id == message.id)
+                        {
+                            *mention = message.clone();
+                        }
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::MarkRead => {
+                let new_state = state.clone();
+                let mut read_up_to = None;
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = state.selected_chat.and_then(|i| chats_lock.get_mut(i)) {
+                        let latest_message_id = chat
+                            .messages
+                            .iter()
+                            .chain(chat.last_message.iter())
+                            .map(|m| m.id)
+                            .max();
+
+                        if latest_message_id > chat.last_read_message_id {
+                            chat.last_read_message_id = latest_message_id;
+                            read_up_to = latest_message_id.map(|id| (chat.id, id));
+                        }
+                        chat.unread_count = 0;
+                    }
+                }
+
+                if let Some((chat_id, message_id)) = read_up_to {
+                    let mut request = tonic::Request::new(MarkReadRequest {
+                        chat_id,
+                        message_id,
+                    });
+
+                    let auth_token = state.auth_state.clone().unwrap().access_token;
+                    let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                    request
+                        .metadata_mut()
+                        .insert("authorization", token_metadata);
+
                     handle.spawn(async move {
-                        tx.send(send_message_clone).await.unwrap();
+                        let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                            .await
+                            .expect("Couldn't connect to server");
+
+                        if let Err(e) = client.mark_read(request).await {
+                            eprintln!("Error: {:?}", e);
+                        }
                     });
                 }
 
+                ReduceResult::Consumed(new_state)
+            }
+            Action::ReactionsChanged(message_reactions) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(message) = chats_lock
+                        .iter_mut()
+                        .find(|c| c.id == message_reactions.chat_id)
+                        .and_then(|chat| {
+                            chat.messages
+                                .iter_mut()
+                                .find(|m| m.id == message_reactions.message_id)
+                        })
+                    {
+                        message.reactions = message_reactions.reactions.clone();
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::AddReaction(message_id, emoji) | Action::RemoveReaction(message_id, emoji) => {
+                let added = matches!(action, Action::AddReaction(..));
+                let mut request = tonic::Request::new(ReactionRequest {
+                    message_id: *message_id,
+                    emoji: emoji.clone(),
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let result = if added {
+                        client.add_reaction(request).await
+                    } else {
+                        client.remove_reaction(request).await
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Error: {:?}", e);
+                    }
+                });
+
                 ReduceResult::ConsumedButKindaNot
             }
+            Action::LoadPinnedMessages(chat_id) => {
+                let chat_id = *chat_id;
+                let mut request = tonic::Request::new(GetPinnedMessagesRequest { chat_id });
 
-            _ => ReduceResult::Ignored,
-        }
-    }
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.get_pinned_messages(request).await {
+                        Ok(response) => dispatch_tx
+                            .send(Action::LoadPinnedMessagesSuccess(
+                                chat_id,
+                                response.into_inner().pinned_messages,
+                            ))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::LoadPinnedMessagesSuccess(chat_id, pinned_messages) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == *chat_id) {
+                        chat.pinned_messages = pinned_messages.clone();
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::PinMessage(message_id, pinned) => {
+                let (message_id, pinned) = (*message_id, *pinned);
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+
+                // The chat stream brings the pin back to every member, this client included.
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let result = if pinned {
+                        let mut request = tonic::Request::new(PinMessageRequest { message_id });
+                        request
+                            .metadata_mut()
+                            .insert("authorization", token_metadata);
+                        client.pin_message(request).await.map(|_| ())
+                    } else {
+                        let mut request = tonic::Request::new(UnpinMessageRequest { message_id });
+                        request
+                            .metadata_mut()
+                            .insert("authorization", token_metadata);
+                        client.unpin_message(request).await.map(|_| ())
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Error: {:?}", e);
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::MessagePinned(pin) => {
+                let new_state = state.clone();
+                let message = match &pin.message {
+                    Some(message) => message,
+                    None => return ReduceResult::Ignored,
+                };
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == message.chat_id) {
+                        if !chat.is_pinned(message.id) {
+                            chat.pinned_messages.insert(0, pin.clone());
+                        }
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::MessageUnpinned(unpinned) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == unpinned.chat_id) {
+                        remove_pin(chat, unpinned.message_id);
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::EditMessage(message_id, text) => {
+                let mut request = tonic::Request::new(EditMessageRequest {
+                    message_id: *message_id,
+                    text: text.clone(),
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    if let Err(e) = client.edit_message(request).await {
+                        eprintln!("Error: {:?}", e);
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::DeleteMessage(message_id) => {
+                let mut request = tonic::Request::new(DeleteMessageRequest {
+                    message_id: *message_id,
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    if let Err(e) = client.delete_message(request).await {
+                        eprintln!("Error: {:?}", e);
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::SendMessage(send_message) => {
+                let mut new_state = state.clone();
+
+                if let Some(tx) = new_state.send_message_tx.clone() {
+                    let chat_request = ChatRequest {
+                        request: Some(ChatRequestKind::Message(send_message.clone())),
+                    };
+                    handle.spawn(async move {
+                        tx.send(chat_request).await.unwrap();
+                    });
+                }
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::SetTyping(chat_id, started) => {
+                if let Some(tx) = state.send_message_tx.clone() {
+                    let typing_request = TypingRequest { chat_id: *chat_id };
+                    let request = if *started {
+                        ChatRequestKind::TypingStart(typing_request)
+                    } else {
+                        ChatRequestKind::TypingStop(typing_request)
+                    };
+                    handle.spawn(async move {
+                        tx.send(ChatRequest {
+                            request: Some(request),
+                        })
+                        .await
+                        .unwrap();
+                    });
+                }
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::TypingStarted(typing) | Action::TypingStopped(typing) => {
+                let started = matches!(action, Action::TypingStarted(_));
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == typing.chat_id) {
+                        chat.typing_users
+                            .retain(|(user_id, _)| user_id != &typing.user_id);
+                        if started {
+                            chat.typing_users
+                                .push((typing.user_id.clone(), Instant::now()));
+                        }
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::OpenDirectChat(user_id) => {
+                let mut request = tonic::Request::new(OpenDirectChatRequest {
+                    user_id: user_id.clone(),
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.open_direct_chat(request).await {
+                        Ok(response) => {
+                            if let Some(chat) = response.into_inner().chat {
+                                dispatch_tx.send(Action::DirectChatOpened(chat)).unwrap();
+                            }
+                        }
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::DirectChatOpened(chat) => {
+                let mut new_state = state.clone();
+                let mut load_messages = false;
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    let index = match chats_lock.iter().position(|c| c.id == chat.id) {
+                        Some(index) => index,
+                        None => {
+                            chats_lock.push(chat.into());
+                            chats_lock.len() - 1
+                        }
+                    };
+
+                    load_messages = chats_lock[index].messages.is_empty();
+                    new_state.selected_chat = Some(index);
+                    new_state.tab_state = TabState::Chats;
+                    new_state.chats_state = ChatsState::Messages;
+                }
+
+                if load_messages {
+                    dispatch_tx.send(Action::LoadMessages).unwrap();
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::LeaveChat(chat_id) => {
+                let chat_id = *chat_id;
+                let mut request = tonic::Request::new(LeaveChatRequest { chat_id });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                let user_id = state.user_id.clone().unwrap_or_default();
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.leave_chat(request).await {
+                        // The stream is detached from the chat, so the event may never come.
+                        Ok(_) => dispatch_tx
+                            .send(Action::MemberRemoved(MemberRemoved {
+                                chat_id,
+                                user_id: user_id.clone(),
+                                removed_by: user_id,
+                            }))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::MuteChat(chat_id, muted) => {
+                let (chat_id, muted) = (*chat_id, *muted);
+                let mut request = tonic::Request::new(MuteChatRequest { chat_id, muted });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.mute_chat(request).await {
+                        Ok(_) => dispatch_tx.send(Action::ChatMuted(chat_id, muted)).unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::ChatMuted(chat_id, muted) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == *chat_id) {
+                        chat.muted = *muted;
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::MemberRemoved(removed) => {
+                let mut new_state = state.clone();
+
+                if state.user_id.as_ref() != Some(&removed.user_id) {
+                    if let Ok(mut chats_lock) = new_state.chats.write() {
+                        if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == removed.chat_id)
+                        {
+                            chat.typing_users
+                                .retain(|(user_id, _)| user_id != &removed.user_id);
+                        }
+                    }
+                    return ReduceResult::Consumed(new_state);
+                }
+
+                remove_chat(&mut new_state, removed.chat_id);
+                ReduceResult::Consumed(new_state)
+            }
+            Action::ChatUpdated(chat) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(client_chat) = chats_lock.iter_mut().find(|c| c.id == chat.id) {
+                        // The role in the event is the one of whoever made the change.
+                        client_chat.name = chat.name.clone();
+                        client_chat.description = chat.description.clone();
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::ChatDeleted(chat_id) => {
+                let mut new_state = state.clone();
+                remove_chat(&mut new_state, *chat_id);
+                ReduceResult::Consumed(new_state)
+            }
+            Action::SearchMessages(query, offset) => {
+                let (query, offset) = (query.clone(), *offset);
+                let mut request = tonic::Request::new(SearchMessagesRequest {
+                    query: query.clone(),
+                    chat_id: None,
+                    offset,
+                    limit: SEARCH_PAGE_SIZE,
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let (hits, has_more) = match client.search_messages(request).await {
+                        Ok(response) => {
+                            let response = response.into_inner();
+                            (response.hits, response.has_more)
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {:?}", e);
+                            (vec![], false)
+                        }
+                    };
+                    let action = Action::SearchMessagesSuccess(query, offset, hits, has_more);
+                    dispatch_tx.send(action).unwrap();
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::SearchMessagesSuccess(query, offset, hits, has_more) => {
+                let mut new_state = state.clone();
+                let search = &mut new_state.search;
+
+                if *offset == 0 {
+                    search.hits_query = query.clone();
+                    search.hits = hits.clone();
+                } else if *query == search.hits_query && *offset as usize == search.hits.len() {
+                    search.hits.extend(hits.iter().cloned());
+                } else {
+                    // A page of a search that has been replaced since.
+                    return ReduceResult::Ignored;
+                }
+
+                search.searching = false;
+                search.has_more = *has_more;
+                search.selected_hit = match search.hits.len() {
+                    0 => None,
+                    len => Some((*offset as usize).min(len - 1)),
+                };
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::JumpToMessage(chat_id, message_id) => {
+                let (chat_id, message_id) = (*chat_id, *message_id);
+                let mut new_state = state.clone();
+                let mut fetch_before = None;
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    let index = match chats_lock.iter().position(|c| c.id == chat_id) {
+                        Some(index) => index,
+                        None => return ReduceResult::Ignored,
+                    };
+
+                    let chat = &mut chats_lock[index];
+                    match chat.messages.iter().position(|m| m.id == message_id) {
+                        // Selection counts from the newest message.
+                        Some(position) => {
+                            chat.selected_message = Some(chat.messages.len() - 1 - position)
+                        }
+                        // Pages are loaded from the oldest known message down to the hit,
+                        // so the loaded messages stay contiguous.
+                        None if chat.has_more_messages && !chat.loading_messages => {
+                            chat.loading_messages = true;
+                            fetch_before = Some(chat.messages.first().map(|m| m.id));
+                        }
+                        None => {}
+                    }
+
+                    new_state.selected_chat = Some(index);
+                    new_state.chats_state = ChatsState::Messages;
+                }
+
+                if let Some(mut before_id) = fetch_before {
+                    let auth_token = state.auth_state.clone().unwrap().access_token;
+
+                    handle.spawn(async move {
+                        let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                            .await
+                            .expect("Couldn't connect to server");
+
+                        loop {
+                            let mut request = tonic::Request::new(GetMessagesRequest {
+                                chat_id,
+                                created_before: None,
+                                before_id,
+                                after_id: None,
+                                limit: MESSAGES_PAGE_SIZE,
+                            });
+                            let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                            request
+                                .metadata_mut()
+                                .insert("authorization", token_metadata);
+
+                            let response = match client.get_messages(request).await {
+                                Ok(response) => response.into_inner(),
+                                Err(e) => {
+                                    eprintln!("Error: {:?}", e);
+                                    let action = Action::LoadMessagesSuccess(chat_id, vec![], true);
+                                    dispatch_tx.send(action).unwrap();
+                                    return;
+                                }
+                            };
+
+                            let oldest = response.messages.first().map(|m| m.id);
+                            let found = response.messages.iter().any(|m| m.id == message_id);
+                            let has_more = response.has_more;
+                            let action =
+                                Action::LoadMessagesSuccess(chat_id, response.messages, has_more);
+                            dispatch_tx.send(action).unwrap();
+
+                            if found {
+                                dispatch_tx
+                                    .send(Action::JumpToMessage(chat_id, message_id))
+                                    .unwrap();
+                            }
+                            // The hit may have been deleted since, then the search stops at it.
+                            if found || !has_more || oldest.is_none_or(|id| id < message_id) {
+                                break;
+                            }
+                            before_id = oldest;
+                        }
+                    });
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::UploadAttachment(chat_id, path) => {
+                let (chat_id, path) = (*chat_id, path.clone());
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+
+                handle.spawn(async move {
+                    let data = match tokio::fs::read(&path).await {
+                        Ok(data) => data,
+                        Err(e) => {
+                            eprintln!("Error: {:?}", e);
+                            return;
+                        }
+                    };
+                    let file_name = Path::new(&path)
+                        .file_name()
+                        .map_or(path.clone(), |name| name.to_string_lossy().to_string());
+
+                    let info = UploadAttachmentRequest {
+                        payload: Some(Payload::Info(AttachmentInfo { chat_id, file_name })),
+                    };
+                    let chunks =
+                        data.chunks(ATTACHMENT_CHUNK_SIZE)
+                            .map(|chunk| UploadAttachmentRequest {
+                                payload: Some(Payload::Chunk(chunk.to_vec())),
+                            });
+                    let requests: Vec<_> = std::iter::once(info).chain(chunks).collect();
+
+                    let mut request = tonic::Request::new(tokio_stream::iter(requests));
+                    let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                    request
+                        .metadata_mut()
+                        .insert("authorization", token_metadata);
+
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.upload_attachment(request).await {
+                        Ok(response) => dispatch_tx
+                            .send(Action::AttachmentUploaded(chat_id, response.into_inner()))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::AttachmentUploaded(chat_id, attachment) => {
+                let new_state = state.clone();
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    if let Some(chat) = chats_lock.iter_mut().find(|c| c.id == *chat_id) {
+                        chat.pending_attachments.push(attachment.clone());
+                    }
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::SaveAttachments(attachments) => {
+                let attachments = attachments.clone();
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    for attachment in attachments {
+                        if let Err(e) = save_attachment(&mut client, &auth_token, &attachment).await
+                        {
+                            eprintln!("Error: {:?}", e);
+                        }
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::SearchUsers(query, offset) => {
+                let (query, offset) = (query.clone(), *offset);
+                let mut request = tonic::Request::new(SearchUserQuery {
+                    user_id: None,
+                    email: None,
+                    query: Some(query.clone()),
+                    offset,
+                    limit: USER_SEARCH_PAGE_SIZE,
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let (users, has_more) = match client.search_user(request).await {
+                        Ok(response) => {
+                            let response = response.into_inner();
+                            (response.users, response.has_more)
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {:?}", e);
+                            (vec![], false)
+                        }
+                    };
+                    let action = Action::SearchUsersSuccess(query, offset, users, has_more);
+                    dispatch_tx.send(action).unwrap();
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::SearchUsersSuccess(query, offset, users, has_more) => {
+                let mut new_state = state.clone();
+                let user_search = &mut new_state.user_search;
+
+                if *offset == 0 {
+                    user_search.hits_query = query.clone();
+                    user_search.hits = users.clone();
+                } else if *query == user_search.hits_query
+                    && *offset as usize == user_search.hits.len()
+                {
+                    user_search.hits.extend(users.iter().cloned());
+                } else {
+                    // A page of a search that has been replaced since.
+                    return ReduceResult::Ignored;
+                }
+
+                user_search.searching = false;
+                user_search.has_more = *has_more;
+                user_search.selected_hit = match user_search.hits.len() {
+                    0 => None,
+                    len => Some((*offset as usize).min(len - 1)),
+                };
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::SendInvite(user_id, chat_id) => {
+                let (user_id, chat_id) = (user_id.clone(), *chat_id);
+                let mut request = tonic::Request::new(SendInviteRequest {
+                    user_id: user_id.clone(),
+                    chat_id,
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.send_invite(request).await {
+                        Ok(_) => dispatch_tx
+                            .send(Action::InviteSent(user_id, chat_id))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::InviteSent(user_id, chat_id) => {
+                let mut new_state = state.clone();
+                new_state.user_search.last_invite = Some((user_id.clone(), *chat_id));
+                ReduceResult::Consumed(new_state)
+            }
+            Action::LoadBlocked => {
+                let mut request = tonic::Request::new(ListBlockedRequest {});
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    match client.list_blocked(request).await {
+                        Ok(response) => dispatch_tx
+                            .send(Action::LoadBlockedSuccess(response.into_inner().users))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::LoadBlockedSuccess(users) => {
+                let new_state = state.clone();
+
+                if let Ok(mut blocked_lock) = new_state.blocked_users.write() {
+                    blocked_lock.extend(users.iter().map(|user| user.id.clone()));
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::BlockUser(user_id, blocked) => {
+                let (user_id, blocked) = (user_id.clone(), *blocked);
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let response = if blocked {
+                        let mut request = tonic::Request::new(BlockUserRequest {
+                            user_id: user_id.clone(),
+                        });
+                        request
+                            .metadata_mut()
+                            .insert("authorization", token_metadata);
+                        client.block_user(request).await.map(|_| ())
+                    } else {
+                        let mut request = tonic::Request::new(UnblockUserRequest {
+                            user_id: user_id.clone(),
+                        });
+                        request
+                            .metadata_mut()
+                            .insert("authorization", token_metadata);
+                        client.unblock_user(request).await.map(|_| ())
+                    };
+
+                    match response {
+                        Ok(_) => dispatch_tx
+                            .send(Action::UserBlocked(user_id, blocked))
+                            .unwrap(),
+                        Err(e) => eprintln!("Error: {:?}", e),
+                    }
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::UserBlocked(user_id, blocked) => {
+                let mut new_state = state.clone();
+
+                if let Ok(mut blocked_lock) = new_state.blocked_users.write() {
+                    if *blocked {
+                        blocked_lock.insert(user_id.clone());
+                    } else {
+                        blocked_lock.remove(user_id);
+                    }
+                }
+
+                // Messages already on screen are dropped, unblocking only shows new ones.
+                if *blocked {
+                    if let Ok(mut chats_lock) = new_state.chats.write() {
+                        for chat in chats_lock.iter_mut() {
+                            chat.messages.retain(|message| &message.user_id != user_id);
+                            chat.typing_users
+                                .retain(|(typing_id, _)| typing_id != user_id);
+                            chat.pinned_messages.retain(|pin| {
+                                pin.message
+                                    .as_ref()
+                                    .is_some_and(|message| &message.user_id != user_id)
+                            });
+                            if chat
+                                .last_message
+                                .as_ref()
+                                .is_some_and(|message| &message.user_id == user_id)
+                            {
+                                chat.last_message = None;
+                            }
+                            chat.selected_message = chat.selected_message.map(|selected| {
+                                selected.min(chat.messages.len().saturating_sub(1))
+                            });
+                        }
+                    }
+
+                    let mentions = &mut new_state.mentions;
+                    mentions
+                        .messages
+                        .retain(|message| &message.user_id != user_id);
+                    mentions.selected_mention = match mentions.messages.len() {
+                        0 => None,
+                        len => mentions
+                            .selected_mention
+                            .map(|selected| selected.min(len - 1)),
+                    };
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::LoadMentions(before_id) => {
+                let before_id = *before_id;
+                let mut request = tonic::Request::new(GetMentionsRequest {
+                    before_id,
+                    limit: MENTIONS_PAGE_SIZE,
+                });
+
+                let auth_token = state.auth_state.clone().unwrap().access_token;
+                let token_metadata = MetadataValue::from_str(&auth_token).unwrap();
+                request
+                    .metadata_mut()
+                    .insert("authorization", token_metadata);
+
+                handle.spawn(async move {
+                    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS"))
+                        .await
+                        .expect("Couldn't connect to server");
+
+                    let (messages, has_more) = match client.get_mentions(request).await {
+                        Ok(response) => {
+                            let response = response.into_inner();
+                            (response.messages, response.has_more)
+                        }
+                        Err(e) => {
+                            eprintln!("Error: {:?}", e);
+                            (vec![], false)
+                        }
+                    };
+                    let action = Action::LoadMentionsSuccess(before_id, messages, has_more);
+                    dispatch_tx.send(action).unwrap();
+                });
+
+                ReduceResult::ConsumedButKindaNot
+            }
+            Action::LoadMentionsSuccess(before_id, messages, has_more) => {
+                let mut new_state = state.clone();
+                let mentions = &mut new_state.mentions;
+
+                // Pages come oldest first, the feed keeps the most recent mention on top.
+                let page = messages.iter().rev().cloned();
+                let loaded = mentions.messages.len();
+                match before_id {
+                    None => mentions.messages = page.collect(),
+                    Some(before_id)
+                        if mentions.messages.last().map(|m| m.id) == Some(*before_id) =>
+                    {
+                        mentions.messages.extend(page)
+                    }
+                    // A page of a feed that has been reloaded since.
+                    Some(_) => return ReduceResult::Ignored,
+                }
+
+                mentions.loading = false;
+                mentions.has_more = *has_more;
+                // Past the last mention the first one of the next page gets selected.
+                mentions.selected_mention = match (mentions.messages.len(), before_id) {
+                    (0, _) => None,
+                    (len, Some(_)) => Some(loaded.min(len - 1)),
+                    (_, None) => Some(0),
+                };
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::Mentioned(message) => {
+                let mut new_state = state.clone();
+                let mentions = &mut new_state.mentions;
+
+                if mentions.is_mentioned(message.id) {
+                    return ReduceResult::Ignored;
+                }
+
+                mentions.messages.insert(0, message.clone());
+                mentions.selected_mention = mentions.selected_mention.map(|selected| selected + 1);
+                if !(state.tab_state == TabState::Chats
+                    && state.chats_state == ChatsState::Mentions)
+                {
+                    mentions.unseen += 1;
+                }
+
+                ReduceResult::Consumed(new_state)
+            }
+            Action::Tick => {
+                let new_state = state.clone();
+                let mut expired = false;
+
+                if let Ok(mut chats_lock) = new_state.chats.write() {
+                    for chat in chats_lock.iter_mut() {
+                        let typing_count = chat.typing_users.len();
+                        chat.typing_users
+                            .retain(|(_, since)| since.elapsed() < TYPING_TIMEOUT);
+                        expired |= chat.typing_users.len() != typing_count;
+                    }
+                }
+
+                if expired {
+                    ReduceResult::Consumed(new_state)
+                } else {
+                    ReduceResult::Ignored
+                }
+            }
+
+            _ => ReduceResult::Ignored,
+        }
+    }
+}
+
+/// Downloads an attachment into the downloads directory, prefixed with its id so files
+/// with the same name don't overwrite each other.
+async fn save_attachment(
+    client: &mut MessengerClient<Channel>,
+    auth_token: &str,
+    attachment: &Attachment,
+) -> anyhow::Result<()> {
+    let mut request = tonic::Request::new(DownloadAttachmentRequest {
+        attachment_id: attachment.id,
+    });
+    let token_metadata = MetadataValue::from_str(auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let mut stream = client.download_attachment(request).await?.into_inner();
+    let mut data = Vec::with_capacity(attachment.size as usize);
+    while let Some(chunk) = stream.message().await? {
+        data.extend_from_slice(&chunk.data);
+    }
+
+    let file_name = Path::new(&attachment.file_name)
+        .file_name()
+        .map_or("attachment".to_string(), |name| {
+            name.to_string_lossy().to_string()
+        });
+    tokio::fs::create_dir_all(DOWNLOADS_DIR).await?;
+    tokio::fs::write(
+        Path::new(DOWNLOADS_DIR).join(format!("{}-{}", attachment.id, file_name)),
+        data,
+    )
+    .await?;
+
+    Ok(())
+}
+
+fn remove_pin(chat: &mut ClientChatState, message_id: i32) {
+    chat.pinned_messages
+        .retain(|pin| pin.message.as_ref().is_some_and(|m| m.id != message_id));
+}
+
+/// Drops a chat the user is no longer part of, keeping the selection on the same chat.
+fn remove_chat(state: &mut State, chat_id: i32) {
+    if let Ok(mut chats_lock) = state.chats.write() {
+        if let Some(index) = chats_lock.iter().position(|c| c.id == chat_id) {
+            chats_lock.remove(index);
+
+            state.selected_chat = match state.selected_chat {
+                Some(selected) if selected == index => {
+                    state.chats_state = ChatsState::Chats;
+                    None
+                }
+                Some(selected) if selected > index => Some(selected - 1),
+                selected => selected,
+            };
+        }
+    }
+}
+
+/// Runs a single Chat stream until it drops, asking the server to replay what was missed
+/// since the last message known in each chat. Returns an error if it could not connect.
+async fn run_chat_stream(
+    auth_token: &str,
+    chats: &Arc<RwLock<Vec<ClientChatState>>>,
+    outgoing: Arc<Mutex<mpsc::Receiver<ChatRequest>>>,
+    dispatch_tx: &Sender<Action>,
+) -> anyhow::Result<()> {
+    let resume_from = chats
+        .read()
+        .unwrap()
+        .iter()
+        .filter_map(|chat| chat.last_seen_message_id().map(|id| (chat.id, id)))
+        .collect();
+    let resume = ChatRequest {
+        request: Some(ChatRequestKind::Resume(ResumeRequest { resume_from })),
+    };
+
+    // The outgoing channel outlives the connection, so it is shared between attempts.
+    let outgoing_stream = futures::stream::unfold(outgoing, |outgoing| async move {
+        let request = outgoing.lock().await.recv().await;
+        request.map(|request| (request, outgoing))
+    });
+
+    let mut request = Request::new(futures::stream::once(async { resume }).chain(outgoing_stream));
+    let token_metadata = MetadataValue::from_str(auth_token)?;
+    request
+        .metadata_mut()
+        .insert("authorization", token_metadata);
+
+    let mut client = MessengerClient::connect(dotenv!("SERVER_ADDRESS")).await?;
+    let response_stream = client.chat(request).await?.into_inner();
+    response_stream
+        .for_each(|chat_event| async {
+            match chat_event.map(|chat_event| chat_event.event) {
+                Ok(Some(Event::NewMessage(msg))) => dispatch_tx
+                    .clone()
+                    .send(Action::ReceivedMessage(msg))
+                    .unwrap(),
+                Ok(Some(Event::MessageEdited(msg))) | Ok(Some(Event::MessageDeleted(msg))) => {
+                    dispatch_tx
+                        .clone()
+                        .send(Action::UpdatedMessage(msg))
+                        .unwrap()
+                }
+                Ok(Some(Event::ReactionsChanged(reactions))) => dispatch_tx
+                    .clone()
+                    .send(Action::ReactionsChanged(reactions))
+                    .unwrap(),
+                Ok(Some(Event::TypingStarted(typing))) => dispatch_tx
+                    .clone()
+                    .send(Action::TypingStarted(typing))
+                    .unwrap(),
+                Ok(Some(Event::TypingStopped(typing))) => dispatch_tx
+                    .clone()
+                    .send(Action::TypingStopped(typing))
+                    .unwrap(),
+                Ok(Some(Event::MemberRemoved(removed))) => dispatch_tx
+                    .clone()
+                    .send(Action::MemberRemoved(removed))
+                    .unwrap(),
+                Ok(Some(Event::ChatUpdated(chat))) => {
+                    dispatch_tx.clone().send(Action::ChatUpdated(chat)).unwrap()
+                }
+                Ok(Some(Event::ChatDeleted(deleted))) => dispatch_tx
+                    .clone()
+                    .send(Action::ChatDeleted(deleted.chat_id))
+                    .unwrap(),
+                Ok(Some(Event::MessagePinned(pin))) => dispatch_tx
+                    .clone()
+                    .send(Action::MessagePinned(pin))
+                    .unwrap(),
+                Ok(Some(Event::MessageUnpinned(unpinned))) => dispatch_tx
+                    .clone()
+                    .send(Action::MessageUnpinned(unpinned))
+                    .unwrap(),
+                Ok(Some(Event::Mentioned(message))) => dispatch_tx
+                    .clone()
+                    .send(Action::Mentioned(message))
+                    .unwrap(),
+                Ok(None) => {}
+                Err(e) => eprintln!("Error: {:?}", e),
+            }
+        })
+        .await;
+
+    Ok(())
 }
 
 module! {
@@ -1,4 +1,5 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use crossbeam_channel::Sender;
 use crossterm::event::{Event, KeyCode};
@@ -7,6 +8,7 @@ use tokio::runtime::Handle;
 
 use crate::client::redux::action::{Action, ReduceResult};
 use crate::client::redux::reducers::Reducer;
+use crate::client::redux::state::client_chat::{ChatsState, ClientChatState, TYPING_REFRESH};
 use crate::client::redux::state::State;
 use crate::utils::messenger::SendMessage;
 
@@ -30,6 +32,7 @@ impl Reducer for TypingReducerImpl {
             Action::Input(Event::Key(event)) => {
                 let mut new_state = state.clone();
                 let mut state_changed = false;
+                let mut stop_editing = false;
 
                 {
                     if let Some(selected_chat_index) = new_state.selected_chat {
@@ -44,22 +47,76 @@ impl Reducer for TypingReducerImpl {
                                     KeyCode::Enter => {
                                         let text = chat.text.clone();
                                         chat.text.clear();
-                                        let send_message = SendMessage {
-                                            chat_id: chat.id,
-                                            text,
+                                        // The server ends the typing notification itself
+                                        // once the message arrives.
+                                        chat.typing_sent_at = None;
+                                        let action = match chat.editing_message.take() {
+                                            Some(message_id) => {
+                                                stop_editing = true;
+                                                Action::EditMessage(message_id, text)
+                                            }
+                                            None => Action::SendMessage(SendMessage {
+                                                chat_id: chat.id,
+                                                text,
+                                                reply_to_message_id: chat.replying_to.take(),
+                                                attachment_ids: chat
+                                                    .pending_attachments
+                                                    .drain(..)
+                                                    .map(|attachment| attachment.id)
+                                                    .collect(),
+                                            }),
                                         };
-                                        dispatch_tx
-                                            .send(Action::SendMessage(send_message))
-                                            .unwrap();
+                                        dispatch_tx.send(action).unwrap();
+                                        true
+                                    }
+                                    // The typed text is taken as the path of the file to attach.
+                                    KeyCode::Tab if chat.editing_message.is_none() => {
+                                        let path = chat.text.trim().to_string();
+                                        if path.is_empty() {
+                                            false
+                                        } else {
+                                            chat.text.clear();
+                                            dispatch_tx
+                                                .send(Action::UploadAttachment(chat.id, path))
+                                                .unwrap();
+                                            true
+                                        }
+                                    }
+                                    KeyCode::Esc if chat.editing_message.is_some() => {
+                                        chat.editing_message = None;
+                                        chat.text.clear();
+                                        stop_editing = true;
+                                        true
+                                    }
+                                    KeyCode::Esc if chat.replying_to.is_some() => {
+                                        chat.replying_to = None;
+                                        stop_editing = true;
+                                        true
+                                    }
+                                    KeyCode::Esc if !chat.pending_attachments.is_empty() => {
+                                        chat.pending_attachments.clear();
+                                        stop_editing = true;
+                                        true
+                                    }
+                                    KeyCode::Esc => {
+                                        stop_editing = true;
                                         true
                                     }
                                     _ => false,
                                 };
+
+                                if let Some(action) = self.typing_notification(chat, stop_editing) {
+                                    dispatch_tx.send(action).unwrap();
+                                }
                             }
                         }
                     }
                 }
 
+                if stop_editing {
+                    new_state.chats_state = ChatsState::Messages;
+                }
+
                 if state_changed {
                     ReduceResult::Consumed(new_state)
                 } else {
@@ -71,6 +128,29 @@ impl Reducer for TypingReducerImpl {
     }
 }
 
+impl TypingReducerImpl {
+    /// Decides whether the other members of the chat should be told that the user
+    /// started or stopped typing. Edits aren't announced.
+    fn typing_notification(&self, chat: &mut ClientChatState, leaving: bool) -> Option<Action> {
+        let typing = !leaving && chat.editing_message.is_none() && !chat.text.is_empty();
+
+        if typing {
+            if chat
+                .typing_sent_at
+                .is_some_and(|sent_at| sent_at.elapsed() < TYPING_REFRESH)
+            {
+                return None;
+            }
+            chat.typing_sent_at = Some(Instant::now());
+            Some(Action::SetTyping(chat.id, true))
+        } else {
+            chat.typing_sent_at
+                .take()
+                .map(|_| Action::SetTyping(chat.id, false))
+        }
+    }
+}
+
 module! {
     pub TypingReducerModule {
         components = [TypingReducerImpl],
@@ -0,0 +1,193 @@
+use std::sync::Arc;
+
+use crossbeam_channel::Sender;
+use crossterm::event::{Event, KeyCode};
+use shaku::{module, Component, Interface};
+use tokio::runtime::Handle;
+
+use crate::client::redux::action::{Action, ReduceResult};
+use crate::client::redux::reducers::Reducer;
+use crate::client::redux::state::State;
+
+pub trait UsersReducer: Interface + Reducer {}
+
+#[derive(Component)]
+#[shaku(interface = UsersReducer)]
+pub struct UsersReducerImpl {}
+
+impl UsersReducer for UsersReducerImpl {}
+
+impl Reducer for UsersReducerImpl {
+    fn reduce(
+        &self,
+        action: &Action,
+        state: &State,
+        dispatch_tx: Sender<Action>,
+        _handle: Handle,
+    ) -> ReduceResult {
+        match action {
+            Action::Input(Event::Key(_)) if state.user_search.active => {
+                self.reduce_search(action, state, dispatch_tx)
+            }
+            Action::Input(Event::Key(key_event)) => {
+                let mut new_state = state.clone();
+
+                let state_changed = match key_event.code {
+                    KeyCode::Char('/') => {
+                        new_state.user_search.active = true;
+                        true
+                    }
+                    KeyCode::Char('j') => self.update_selected_user(&mut new_state, true),
+                    KeyCode::Char('k') => self.update_selected_user(&mut new_state, false),
+                    KeyCode::Enter => {
+                        if let Some(user_id) = state
+                            .selected_user
+                            .and_then(|i| state.users.read().ok()?.get(i).map(|u| u.id.clone()))
+                        {
+                            dispatch_tx.send(Action::OpenDirectChat(user_id)).unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    KeyCode::Char('b') => {
+                        if let Some(user_id) = state
+                            .selected_user
+                            .and_then(|i| state.users.read().ok()?.get(i).map(|u| u.id.clone()))
+                        {
+                            let blocked = state
+                                .blocked_users
+                                .read()
+                                .is_ok_and(|blocked_users| blocked_users.contains(&user_id));
+                            dispatch_tx
+                                .send(Action::BlockUser(user_id, !blocked))
+                                .unwrap();
+                            return ReduceResult::ConsumedButKindaNot;
+                        }
+                        false
+                    }
+                    _ => false,
+                };
+
+                if state_changed {
+                    ReduceResult::Consumed(new_state)
+                } else {
+                    ReduceResult::Ignored
+                }
+            }
+
+            _ => ReduceResult::Ignored,
+        }
+    }
+}
+
+impl UsersReducerImpl {
+    /// Keys typed while the directory search box is open.
+    fn reduce_search(
+        &self,
+        action: &Action,
+        state: &State,
+        dispatch_tx: Sender<Action>,
+    ) -> ReduceResult {
+        let key_event = match action {
+            Action::Input(Event::Key(key_event)) => key_event,
+            _ => return ReduceResult::Ignored,
+        };
+
+        let mut new_state = state.clone();
+        let user_search = &mut new_state.user_search;
+
+        let state_changed = match key_event.code {
+            KeyCode::Char(c) => {
+                user_search.query.push(c);
+                true
+            }
+            KeyCode::Backspace => user_search.query.pop().is_some(),
+            KeyCode::Down => match user_search.selected_hit {
+                Some(selected_hit) if selected_hit + 1 < user_search.hits.len() => {
+                    user_search.selected_hit = Some(selected_hit + 1);
+                    true
+                }
+                // Past the last user the next page is fetched and selected once it arrives.
+                _ if user_search.has_more && !user_search.searching => {
+                    user_search.searching = true;
+                    dispatch_tx
+                        .send(Action::SearchUsers(
+                            user_search.hits_query.clone(),
+                            user_search.hits.len() as u32,
+                        ))
+                        .unwrap();
+                    true
+                }
+                _ => false,
+            },
+            KeyCode::Up => match user_search.selected_hit {
+                Some(selected_hit) if selected_hit > 0 => {
+                    user_search.selected_hit = Some(selected_hit - 1);
+                    true
+                }
+                _ => false,
+            },
+            KeyCode::Enter => {
+                let query = user_search.query.trim().to_string();
+                match (user_search.get_selected_hit(), state.selected_chat_id()) {
+                    // Enter searches again once the box no longer matches the results.
+                    (Some(user), Some(chat_id)) if query == user_search.hits_query => {
+                        dispatch_tx
+                            .send(Action::SendInvite(user.id.clone(), chat_id))
+                            .unwrap();
+                        return ReduceResult::ConsumedButKindaNot;
+                    }
+                    _ if !query.is_empty() && query != user_search.hits_query => {
+                        user_search.searching = true;
+                        user_search.last_invite = None;
+                        dispatch_tx.send(Action::SearchUsers(query, 0)).unwrap();
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            KeyCode::Esc => {
+                new_state.user_search = Default::default();
+                true
+            }
+            _ => false,
+        };
+
+        if state_changed {
+            ReduceResult::Consumed(new_state)
+        } else {
+            ReduceResult::Ignored
+        }
+    }
+
+    fn update_selected_user(&self, state: &mut State, move_down: bool) -> bool {
+        let users_count = state.users.read().unwrap().len();
+        match state.selected_user.as_mut() {
+            Some(selected_user) if move_down && *selected_user + 1 < users_count => {
+                *selected_user += 1;
+                true
+            }
+            Some(selected_user) if !move_down && *selected_user > 0 => {
+                *selected_user -= 1;
+                true
+            }
+            Some(_) => false,
+            None if users_count > 0 => {
+                state.selected_user = Some(0);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+module! {
+    pub UsersReducerModule {
+        components = [UsersReducerImpl],
+        providers = [],
+    }
+}
+
+pub fn build_users_reducer_module() -> Arc<UsersReducerModule> {
+    Arc::new(UsersReducerModule::builder().build())
+}
@@ -1,26 +1,59 @@
 use crate::client::redux::state::client_chat::{ChatsState, ClientChatState};
+use crate::client::redux::state::mentions::MentionsState;
+use crate::client::redux::state::search::SearchState;
 use crate::client::redux::state::tab::TabState;
+use crate::client::redux::state::user_search::UserSearchState;
 use crate::utils::auth::AuthState;
-use crate::utils::messenger::{Chat, SendMessage, User};
+use crate::utils::messenger::{ChatRequest, Presence, User};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc;
 
 pub mod client_chat;
+pub mod mentions;
+pub mod search;
 pub mod tab;
+pub mod user_search;
 
 #[derive(Default, Clone)]
 pub struct State {
     pub tab_state: TabState,
     pub auth_state: Option<AuthState>,
+    pub user_id: Option<String>,
     pub code: Option<String>,
     pub link: Option<String>,
     pub messages: Arc<RwLock<Vec<String>>>,
     pub users: Arc<RwLock<Vec<User>>>,
+    pub selected_user: Option<usize>,
+    pub presences: Arc<RwLock<HashMap<String, Presence>>>,
+    pub blocked_users: Arc<RwLock<HashSet<String>>>,
     pub chats: Arc<RwLock<Vec<ClientChatState>>>,
     pub selected_chat: Option<usize>,
     pub chats_state: ChatsState,
     pub should_exit: bool,
-    pub send_message_tx: Option<mpsc::Sender<SendMessage>>,
+    pub send_message_tx: Option<mpsc::Sender<ChatRequest>>,
+    pub reacting: bool,
+    pub search: SearchState,
+    pub user_search: UserSearchState,
+    pub mentions: MentionsState,
+}
+
+impl State {
+    /// Id of the chat whose messages pane currently has focus, if any.
+    pub fn focused_chat_id(&self) -> Option<i32> {
+        if self.tab_state != TabState::Chats || self.chats_state == ChatsState::Chats {
+            return None;
+        }
+
+        let chats_lock = self.chats.read().ok()?;
+        chats_lock.get(self.selected_chat?).map(|chat| chat.id)
+    }
+
+    /// Chat picked on the Chats tab, the one users found in the directory are invited to.
+    pub fn selected_chat_id(&self) -> Option<i32> {
+        let chats_lock = self.chats.read().ok()?;
+        chats_lock.get(self.selected_chat?).map(|chat| chat.id)
+    }
 }
 
 // impl State {
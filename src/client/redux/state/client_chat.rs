@@ -1,10 +1,35 @@
-use crate::utils::messenger::{Chat, Message};
+use std::time::{Duration, Instant};
+
+use crate::utils::messenger::{Attachment, Chat, ChatRole, Message, PinnedMessage};
+
+/// How long a typing notification is shown without being refreshed.
+pub const TYPING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often a typing notification is refreshed while the user keeps typing.
+pub const TYPING_REFRESH: Duration = Duration::from_secs(3);
+
+/// Number of messages fetched at once when scrolling up a chat.
+pub const MESSAGES_PAGE_SIZE: u32 = 50;
+
+/// Size of the chunks attachments are uploaded in.
+pub const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Directory received attachments are saved to.
+pub const DOWNLOADS_DIR: &str = "downloads";
+
+/// Emojis offered by the reaction picker, selected with the keys 1 to 5.
+pub const REACTION_EMOJIS: [&str; 5] = ["👍", "❤️", "😂", "🎉", "👀"];
+
+/// Most pinned messages listed at once when the pinned bar is expanded.
+pub const PINNED_BAR_MAX_ROWS: usize = 5;
 
 #[derive(Clone, Copy, PartialOrd, PartialEq)]
 pub enum ChatsState {
     Chats,
     Messages,
     Typing,
+    Search,
+    Mentions,
 }
 
 impl Default for ChatsState {
@@ -17,9 +42,25 @@ impl Default for ChatsState {
 pub struct ClientChatState {
     pub id: i32,
     pub name: String,
+    pub description: String,
+    pub role: ChatRole,
     pub selected_message: Option<usize>,
     pub messages: Vec<Message>,
+    pub has_more_messages: bool,
+    pub loading_messages: bool,
     pub text: String,
+    pub editing_message: Option<i32>,
+    pub replying_to: Option<i32>,
+    pub pending_attachments: Vec<Attachment>,
+    pub unread_count: i32,
+    pub muted: bool,
+    pub last_message: Option<Message>,
+    pub last_read_message_id: Option<i32>,
+    pub typing_users: Vec<(String, Instant)>,
+    pub typing_sent_at: Option<Instant>,
+    /// Most recently pinned first.
+    pub pinned_messages: Vec<PinnedMessage>,
+    pub pinned_expanded: bool,
 }
 
 impl ClientChatState {
@@ -27,15 +68,57 @@ impl ClientChatState {
         Self {
             id,
             name,
+            description: String::new(),
+            role: ChatRole::Member,
             selected_message: None,
             messages: Vec::new(),
+            has_more_messages: true,
+            loading_messages: false,
             text: String::new(),
+            editing_message: None,
+            replying_to: None,
+            pending_attachments: Vec::new(),
+            unread_count: 0,
+            muted: false,
+            last_message: None,
+            last_read_message_id: None,
+            typing_users: Vec::new(),
+            typing_sent_at: None,
+            pinned_messages: Vec::new(),
+            pinned_expanded: false,
         }
     }
+
+    /// Id of the newest message the client knows about in this chat.
+    pub fn last_seen_message_id(&self) -> Option<i32> {
+        self.messages
+            .iter()
+            .chain(self.last_message.iter())
+            .map(|message| message.id)
+            .max()
+    }
+
+    pub fn is_pinned(&self, message_id: i32) -> bool {
+        self.pinned_messages
+            .iter()
+            .any(|pin| pin.message.as_ref().is_some_and(|m| m.id == message_id))
+    }
+
+    pub fn get_selected_message(&self) -> Option<&Message> {
+        let selected_message = self.selected_message?;
+        let index = self.messages.len().checked_sub(selected_message + 1)?;
+        self.messages.get(index)
+    }
 }
 
 impl From<&Chat> for ClientChatState {
     fn from(chat: &Chat) -> Self {
-        Self::new(chat.id, chat.name.clone())
+        let mut client_chat = Self::new(chat.id, chat.name.clone());
+        client_chat.description = chat.description.clone();
+        client_chat.role = chat.role();
+        client_chat.unread_count = chat.unread_count;
+        client_chat.muted = chat.muted;
+        client_chat.last_message = chat.last_message.clone();
+        client_chat
     }
 }
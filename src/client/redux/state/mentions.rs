@@ -0,0 +1,25 @@
+use crate::utils::messenger::Message;
+
+/// Number of mentions fetched at once while going through the feed.
+pub const MENTIONS_PAGE_SIZE: u32 = 20;
+
+#[derive(Clone, Debug, Default)]
+pub struct MentionsState {
+    /// Messages mentioning the user, most recent first.
+    pub messages: Vec<Message>,
+    pub selected_mention: Option<usize>,
+    pub has_more: bool,
+    pub loading: bool,
+    /// Mentions received since the feed was last opened.
+    pub unseen: u32,
+}
+
+impl MentionsState {
+    pub fn get_selected_mention(&self) -> Option<&Message> {
+        self.messages.get(self.selected_mention?)
+    }
+
+    pub fn is_mentioned(&self, message_id: i32) -> bool {
+        self.messages.iter().any(|message| message.id == message_id)
+    }
+}
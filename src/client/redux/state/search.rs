@@ -0,0 +1,21 @@
+use crate::utils::messenger::SearchHit;
+
+/// Number of hits fetched at once while going through search results.
+pub const SEARCH_PAGE_SIZE: u32 = 20;
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchState {
+    pub query: String,
+    /// Query the current hits were found for, the prompt may have changed since.
+    pub hits_query: String,
+    pub hits: Vec<SearchHit>,
+    pub selected_hit: Option<usize>,
+    pub has_more: bool,
+    pub searching: bool,
+}
+
+impl SearchState {
+    pub fn get_selected_hit(&self) -> Option<&SearchHit> {
+        self.hits.get(self.selected_hit?)
+    }
+}
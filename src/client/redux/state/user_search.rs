@@ -0,0 +1,25 @@
+use crate::utils::messenger::User;
+
+/// Number of users fetched at once while going through the directory.
+pub const USER_SEARCH_PAGE_SIZE: u32 = 20;
+
+#[derive(Clone, Debug, Default)]
+pub struct UserSearchState {
+    /// Whether the Users tab shows the search box instead of the related users.
+    pub active: bool,
+    pub query: String,
+    /// Query the current hits were found for, the box may have changed since.
+    pub hits_query: String,
+    pub hits: Vec<User>,
+    pub selected_hit: Option<usize>,
+    pub has_more: bool,
+    pub searching: bool,
+    /// User and chat of the last invite sent from the search, shown above the results.
+    pub last_invite: Option<(String, i32)>,
+}
+
+impl UserSearchState {
+    pub fn get_selected_hit(&self) -> Option<&User> {
+        self.hits.get(self.selected_hit?)
+    }
+}
@@ -1,10 +1,20 @@
+use crate::client::redux::state::client_chat::ChatsState;
 use crate::client::redux::state::State;
 use crate::client::view::app::chats_container::chats::{
     build_chat_view_module, ChatView, ChatViewModule,
 };
+use crate::client::view::app::chats_container::mentions::{
+    build_mentions_view_module, MentionsView, MentionsViewModule,
+};
 use crate::client::view::app::chats_container::messages::{
     build_messages_view_module, MessagesView, MessagesViewModule,
 };
+use crate::client::view::app::chats_container::pinned::{
+    build_pinned_view_module, pinned_bar_height, PinnedView, PinnedViewModule,
+};
+use crate::client::view::app::chats_container::search::{
+    build_search_view_module, SearchView, SearchViewModule,
+};
 use crate::client::view::app::chats_container::typing::{
     build_typing_view_module, TypingView, TypingViewModule,
 };
@@ -15,7 +25,10 @@ use shaku::{module, Component, Interface};
 use std::sync::Arc;
 
 mod chats;
+mod mentions;
 mod messages;
+mod pinned;
+mod search;
 mod typing;
 
 pub trait ChatContainerView: View + Interface {}
@@ -29,6 +42,12 @@ pub struct ChatContainerViewImpl {
     messages: Arc<dyn MessagesView>,
     #[shaku(inject)]
     typing: Arc<dyn TypingView>,
+    #[shaku(inject)]
+    search: Arc<dyn SearchView>,
+    #[shaku(inject)]
+    pinned: Arc<dyn PinnedView>,
+    #[shaku(inject)]
+    mentions: Arc<dyn MentionsView>,
 }
 
 impl ChatContainerView for ChatContainerViewImpl {}
@@ -51,7 +70,23 @@ impl View for ChatContainerViewImpl {
         let typing_chunk = chunks_right[0];
 
         self.chats.draw(f, chats_chunk, state.clone())?;
-        self.messages.draw(f, messages_chunk, state.clone())?;
+        if state.chats_state == ChatsState::Search {
+            self.search.draw(f, messages_chunk, state.clone())?;
+        } else if state.chats_state == ChatsState::Mentions {
+            self.mentions.draw(f, messages_chunk, state.clone())?;
+        } else {
+            let pinned_height = state
+                .selected_chat
+                .and_then(|i| state.chats.read().ok()?.get(i).map(pinned_bar_height))
+                .unwrap_or(0);
+            let chunks_messages = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(pinned_height), Constraint::Min(1)].as_ref())
+                .split(messages_chunk);
+
+            self.pinned.draw(f, chunks_messages[0], state.clone())?;
+            self.messages.draw(f, chunks_messages[1], state.clone())?;
+        }
         self.typing.draw(f, typing_chunk, state)?;
 
         Ok(())
@@ -74,6 +109,18 @@ module! {
             components = [dyn TypingView],
             providers = [],
         },
+        use SearchViewModule {
+            components = [dyn SearchView],
+            providers = [],
+        },
+        use PinnedViewModule {
+            components = [dyn PinnedView],
+            providers = [],
+        },
+        use MentionsViewModule {
+            components = [dyn MentionsView],
+            providers = [],
+        },
     }
 }
 pub fn build_chat_container_view_module() -> Arc<ChatContainerViewModule> {
@@ -82,6 +129,9 @@ pub fn build_chat_container_view_module() -> Arc<ChatContainerViewModule> {
             build_chat_view_module(),
             build_messages_view_module(),
             build_typing_view_module(),
+            build_search_view_module(),
+            build_pinned_view_module(),
+            build_mentions_view_module(),
         )
         .build(),
     )
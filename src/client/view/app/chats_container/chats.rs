@@ -3,7 +3,7 @@ use crate::client::redux::state::State;
 use crate::client::view::View;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::prelude::Style;
-use ratatui::style::Color;
+use ratatui::style::{Color, Modifier};
 use ratatui::widgets::{Block, BorderType, Borders, List, ListItem, ListState};
 use ratatui::Frame;
 use shaku::{module, Component, Interface};
@@ -25,7 +25,25 @@ impl View for ChatViewImpl {
 
         let items: Vec<ListItem> = chats_lock
             .iter()
-            .map(|chat| ListItem::new(chat.name.as_str()).style(Style::default().fg(Color::White))) // Set chat text color to white
+            .map(|chat| {
+                if chat.muted {
+                    // Muted chats still count unread messages but don't draw attention to them.
+                    let name = match chat.unread_count {
+                        0 => format!("{} (muted)", chat.name),
+                        unread_count => format!("{} ({}, muted)", chat.name, unread_count),
+                    };
+                    ListItem::new(name).style(Style::default().fg(Color::DarkGray))
+                } else if chat.unread_count > 0 {
+                    ListItem::new(format!("{} ({})", chat.name, chat.unread_count)).style(
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    // Set chat text color to white
+                    ListItem::new(chat.name.as_str()).style(Style::default().fg(Color::White))
+                }
+            })
             .collect();
 
         let color = match state.chats_state {
@@ -33,11 +51,16 @@ impl View for ChatViewImpl {
             _ => Color::White,
         };
 
+        let title = match state.mentions.unseen {
+            0 => "Chats".to_string(),
+            unseen => format!("Chats | @ {} new mentions", unseen),
+        };
+
         let chats_list = List::new(items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title("Chats")
+                    .title(title)
                     .style(Style::default().fg(color)),
             )
             .highlight_style(Style::default().bg(Color::Red))
@@ -0,0 +1,88 @@
+use crate::client::redux::state::State;
+use crate::client::view::app::users::display_name;
+use crate::client::view::View;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Color, Line, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
+use ratatui::Frame;
+use shaku::{module, Component, Interface};
+use std::sync::Arc;
+
+pub trait MentionsView: View + Interface {}
+
+#[derive(Component)]
+#[shaku(interface = MentionsView)]
+pub struct MentionsViewImpl {}
+
+impl MentionsView for MentionsViewImpl {}
+
+impl View for MentionsViewImpl {
+    fn draw(&self, f: &mut Frame, rect: Rect, state: State) -> anyhow::Result<()> {
+        let chats_lock = state.chats.read().unwrap();
+        let users_lock = state.users.read().unwrap();
+        let mentions = &state.mentions;
+
+        let mut list_state = ListState::default();
+        list_state.select(mentions.selected_mention);
+
+        let width = rect.width.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = mentions
+            .messages
+            .iter()
+            .map(|message| {
+                let chat_name = chats_lock
+                    .iter()
+                    .find(|chat| chat.id == message.chat_id)
+                    .map_or("Unknown chat".to_string(), |chat| chat.name.clone());
+                let user_name = users_lock
+                    .iter()
+                    .find(|user| user.id == message.user_id)
+                    .map_or("Unknown".to_string(), display_name);
+
+                let mut lines = vec![Line::styled(
+                    format!("{} [{}]", chat_name, user_name),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                lines.extend(
+                    textwrap::wrap(&message.text.replace('\n', " "), width.max(1))
+                        .iter()
+                        .map(|line| Line::from(line.to_string())),
+                );
+                ListItem::new(lines)
+            })
+            .collect();
+
+        let status = if mentions.loading {
+            " | Loading…"
+        } else if mentions.messages.is_empty() {
+            " | No mentions"
+        } else {
+            ""
+        };
+
+        let mentions_list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Mentions{}", status))
+                    .style(Style::default().fg(Color::Red)),
+            )
+            .style(Style::default().fg(Color::White))
+            .highlight_style(Style::default().bg(Color::Red));
+
+        f.render_stateful_widget(mentions_list, rect, &mut list_state);
+
+        Ok(())
+    }
+}
+
+module! {
+    pub MentionsViewModule {
+        components = [MentionsViewImpl],
+        providers = [],
+    }
+}
+
+pub fn build_mentions_view_module() -> Arc<MentionsViewModule> {
+    Arc::new(MentionsViewModule::builder().build())
+}
@@ -1,8 +1,13 @@
-use crate::client::redux::state::client_chat::ChatsState;
+use crate::client::redux::state::client_chat::{
+    ChatsState, ClientChatState, REACTION_EMOJIS, TYPING_TIMEOUT,
+};
 use crate::client::redux::state::State;
+use crate::client::view::app::users::display_name;
 use crate::client::view::View;
+use crate::utils::messenger::{Attachment, Message, Reaction, User};
 use ratatui::layout::Rect;
 use ratatui::prelude::{Color, Line, Style};
+use ratatui::widgets::block::{Position, Title};
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
 use ratatui::Frame;
 use shaku::{module, Component, Interface};
@@ -36,21 +41,63 @@ impl View for MessagesViewImpl {
                     .enumerate()
                     .rev()
                     .map(|(index, message)| {
-                        let user_email = users_lock
+                        let user_name = users_lock
                             .iter()
                             .find(|user| user.id == message.user_id)
-                            .map_or("Unknown".to_string(), |user| user.email.clone());
+                            .map_or("Unknown".to_string(), display_name);
 
                         let message_number = chat_messages.len() - 1 - index;
+                        let text = if message.deleted_at.is_some() {
+                            "[message deleted]".to_string()
+                        } else if message.edited_at.is_some() {
+                            format!("{} (edited)", message.text)
+                        } else {
+                            message.text.clone()
+                        };
+                        let pin = if selected_chat.is_pinned(message.id) {
+                            "📌 "
+                        } else {
+                            ""
+                        };
                         let full_message =
-                            format!("{} [{}]: {}", message_number, user_email, message.text);
+                            format!("{}{} [{}]: {}", pin, message_number, user_name, text);
                         let width = rect.width as usize - 2;
                         let options = textwrap::Options::new(width);
-                        let message_lines = textwrap::wrap(&full_message, options)
+                        // Messages mentioning the user stand out from the rest.
+                        let text_style = if state.mentions.is_mentioned(message.id) {
+                            Style::default().fg(Color::Magenta)
+                        } else {
+                            Style::default()
+                        };
+                        let mut message_lines = textwrap::wrap(&full_message, options)
                             .iter()
-                            .map(|line| Line::from(line.to_string()))
+                            .map(|line| Line::styled(line.to_string(), text_style))
                             .collect::<Vec<Line>>();
 
+                        if let Some(parent_id) = message.reply_to_message_id {
+                            let quote = self.quote_parent(chat_messages, parent_id, width);
+                            message_lines.insert(
+                                0,
+                                Line::styled(quote, Style::default().fg(Color::DarkGray)),
+                            );
+                        }
+
+                        if message.deleted_at.is_none() {
+                            message_lines.extend(message.attachments.iter().map(|attachment| {
+                                Line::styled(
+                                    format!("📎 {}", format_attachment(attachment)),
+                                    Style::default().fg(Color::Cyan),
+                                )
+                            }));
+                        }
+
+                        if !message.reactions.is_empty() {
+                            message_lines.push(Line::styled(
+                                self.format_reactions(&message.reactions),
+                                Style::default().fg(Color::Yellow),
+                            ));
+                        }
+
                         ListItem::new(message_lines)
                     })
                     .collect();
@@ -60,14 +107,35 @@ impl View for MessagesViewImpl {
                     _ => Color::White,
                 };
 
+                let title = if state.reacting {
+                    let picker = REACTION_EMOJIS
+                        .iter()
+                        .enumerate()
+                        .map(|(index, emoji)| format!("{}: {}", index + 1, emoji))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    format!("Messages | React {} | Esc: Cancel", picker)
+                } else if selected_chat.description.is_empty() {
+                    "Messages".to_string()
+                } else {
+                    format!("Messages | {}", selected_chat.description)
+                };
+
+                let mut block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .style(Style::default().fg(color));
+
+                if let Some(typing) = self.format_typing(selected_chat, &users_lock) {
+                    block = block.title(
+                        Title::from(Line::styled(typing, Style::default().fg(Color::DarkGray)))
+                            .position(Position::Bottom),
+                    );
+                }
+
                 // Create and render the List widget
                 let messages_list = List::new(items)
-                    .block(
-                        Block::default()
-                            .borders(Borders::ALL)
-                            .title("Messages")
-                            .style(Style::default().fg(color)),
-                    )
+                    .block(block)
                     .style(Style::default().fg(Color::White))
                     .highlight_style(Style::default().bg(Color::Red));
 
@@ -79,6 +147,65 @@ impl View for MessagesViewImpl {
     }
 }
 
+impl MessagesViewImpl {
+    fn format_typing(&self, chat: &ClientChatState, users: &[User]) -> Option<String> {
+        let names: Vec<String> = chat
+            .typing_users
+            .iter()
+            .filter(|(_, since)| since.elapsed() < TYPING_TIMEOUT)
+            .map(|(user_id, _)| {
+                users
+                    .iter()
+                    .find(|user| &user.id == user_id)
+                    .map_or(user_id.clone(), display_name)
+            })
+            .collect();
+
+        match names.as_slice() {
+            [] => None,
+            [name] => Some(format!("{} is typing…", name)),
+            [first, second] => Some(format!("{} and {} are typing…", first, second)),
+            _ => Some("Several people are typing…".to_string()),
+        }
+    }
+
+    fn format_reactions(&self, reactions: &[Reaction]) -> String {
+        reactions
+            .iter()
+            .map(|reaction| format!("{} {}", reaction.emoji, reaction.count))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    fn quote_parent(&self, messages: &[Message], parent_id: i32, width: usize) -> String {
+        let snippet = match messages.iter().find(|message| message.id == parent_id) {
+            Some(parent) if parent.deleted_at.is_some() => "[message deleted]".to_string(),
+            Some(parent) => parent.text.clone(),
+            None => format!("message #{}", parent_id),
+        };
+
+        let quote = format!("> {}", snippet.replace('\n', " "));
+        match quote.char_indices().nth(width.saturating_sub(1)) {
+            Some((index, _)) => format!("{}…", &quote[..index]),
+            None => quote,
+        }
+    }
+}
+
+/// Name and size of an attachment, as shown in the chat.
+pub fn format_attachment(attachment: &Attachment) -> String {
+    let size = attachment.size as f64;
+    let size = if size < 1024.0 {
+        format!("{} B", attachment.size)
+    } else if size < 1024.0 * 1024.0 {
+        format!("{:.1} KB", size / 1024.0)
+    } else {
+        format!("{:.1} MB", size / (1024.0 * 1024.0))
+    };
+
+    format!("{} ({})", attachment.file_name, size)
+}
+
 module! {
     pub MessagesViewModule {
         components = [MessagesViewImpl],
@@ -0,0 +1,100 @@
+use crate::client::redux::state::client_chat::{ClientChatState, PINNED_BAR_MAX_ROWS};
+use crate::client::redux::state::State;
+use crate::client::view::app::users::display_name;
+use crate::client::view::View;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Color, Line, Style};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use shaku::{module, Component, Interface};
+use std::sync::Arc;
+
+pub trait PinnedView: View + Interface {}
+
+#[derive(Component)]
+#[shaku(interface = PinnedView)]
+pub struct PinnedViewImpl {}
+
+impl PinnedView for PinnedViewImpl {}
+
+/// Rows the pinned bar takes above the messages, none when the chat has no pins.
+pub fn pinned_bar_height(chat: &ClientChatState) -> u16 {
+    let rows = match (chat.pinned_messages.len(), chat.pinned_expanded) {
+        (0, _) => return 0,
+        (_, false) => 1,
+        (pinned_count, true) => pinned_count.min(PINNED_BAR_MAX_ROWS),
+    };
+
+    rows as u16 + 2
+}
+
+impl View for PinnedViewImpl {
+    fn draw(&self, f: &mut Frame, rect: Rect, state: State) -> anyhow::Result<()> {
+        let chats_lock = state.chats.read().unwrap();
+        let users_lock = state.users.read().unwrap();
+
+        let chat = match state.selected_chat.and_then(|i| chats_lock.get(i)) {
+            Some(chat) if !chat.pinned_messages.is_empty() => chat,
+            _ => return Ok(()),
+        };
+
+        let width = rect.width.saturating_sub(2) as usize;
+        let rows = if chat.pinned_expanded {
+            PINNED_BAR_MAX_ROWS
+        } else {
+            1
+        };
+
+        let lines: Vec<Line> = chat
+            .pinned_messages
+            .iter()
+            .filter_map(|pin| pin.message.as_ref())
+            .take(rows)
+            .map(|message| {
+                let user_name = users_lock
+                    .iter()
+                    .find(|user| user.id == message.user_id)
+                    .map_or("Unknown".to_string(), display_name);
+                let text = format!("📌 [{}]: {}", user_name, message.text.replace('\n', " "));
+
+                // Pins are one line each, the full message is in the chat.
+                let text = match text.char_indices().nth(width.saturating_sub(1)) {
+                    Some((index, _)) => format!("{}…", &text[..index]),
+                    None => text,
+                };
+                Line::from(text)
+            })
+            .collect();
+
+        let pinned_count = chat.pinned_messages.len();
+        let title = match (chat.pinned_expanded, pinned_count) {
+            (true, _) => format!("Pinned ({}) | P: Collapse", pinned_count),
+            (false, 1) => "Pinned".to_string(),
+            (false, _) => format!("Pinned ({}) | P: Show all", pinned_count),
+        };
+
+        let pinned = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .style(Style::default().fg(Color::Yellow)),
+            )
+            .style(Style::default().fg(Color::White));
+
+        f.render_widget(pinned, rect);
+
+        Ok(())
+    }
+}
+
+module! {
+    pub PinnedViewModule {
+        components = [PinnedViewImpl],
+        providers = [],
+    }
+}
+
+pub fn build_pinned_view_module() -> Arc<PinnedViewModule> {
+    Arc::new(PinnedViewModule::builder().build())
+}
@@ -0,0 +1,117 @@
+use crate::client::redux::state::State;
+use crate::client::view::app::users::display_name;
+use crate::client::view::View;
+use ratatui::layout::Rect;
+use ratatui::prelude::{Color, Line, Span, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
+use ratatui::Frame;
+use shaku::{module, Component, Interface};
+use std::sync::Arc;
+
+/// Marker the server wraps the matched words of a snippet in.
+const HIGHLIGHT_MARKER: &str = "**";
+
+pub trait SearchView: View + Interface {}
+
+#[derive(Component)]
+#[shaku(interface = SearchView)]
+pub struct SearchViewImpl {}
+
+impl SearchView for SearchViewImpl {}
+
+impl View for SearchViewImpl {
+    fn draw(&self, f: &mut Frame, rect: Rect, state: State) -> anyhow::Result<()> {
+        let chats_lock = state.chats.read().unwrap();
+        let users_lock = state.users.read().unwrap();
+        let search = &state.search;
+
+        let mut list_state = ListState::default();
+        list_state.select(search.selected_hit);
+
+        let width = rect.width.saturating_sub(2) as usize;
+        let items: Vec<ListItem> = search
+            .hits
+            .iter()
+            .filter_map(|hit| {
+                let message = hit.message.as_ref()?;
+                let chat_name = chats_lock
+                    .iter()
+                    .find(|chat| chat.id == message.chat_id)
+                    .map_or("Unknown chat".to_string(), |chat| chat.name.clone());
+                let user_name = users_lock
+                    .iter()
+                    .find(|user| user.id == message.user_id)
+                    .map_or("Unknown".to_string(), display_name);
+
+                let mut lines = vec![Line::styled(
+                    format!("{} [{}]", chat_name, user_name),
+                    Style::default().fg(Color::DarkGray),
+                )];
+                lines.extend(self.highlight_snippet(&hit.snippet, width));
+                Some(ListItem::new(lines))
+            })
+            .collect();
+
+        let status = if search.searching {
+            " | Searching…".to_string()
+        } else if !search.hits_query.is_empty() && search.hits.is_empty() {
+            " | No results".to_string()
+        } else {
+            String::new()
+        };
+
+        let hits_list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Search: {}▏{}", search.query, status))
+                    .style(Style::default().fg(Color::Red)),
+            )
+            .style(Style::default().fg(Color::White))
+            .highlight_style(Style::default().bg(Color::Red));
+
+        f.render_stateful_widget(hits_list, rect, &mut list_state);
+
+        Ok(())
+    }
+}
+
+impl SearchViewImpl {
+    /// Wraps a snippet and styles the words between the highlight markers.
+    fn highlight_snippet(&self, snippet: &str, width: usize) -> Vec<Line<'static>> {
+        let mut highlighted = false;
+
+        textwrap::wrap(&snippet.replace('\n', " "), width.max(1))
+            .iter()
+            .map(|line| {
+                let spans: Vec<Span> = line
+                    .split(HIGHLIGHT_MARKER)
+                    .enumerate()
+                    .map(|(index, part)| {
+                        if index > 0 {
+                            highlighted = !highlighted;
+                        }
+                        let style = if highlighted {
+                            Style::default().fg(Color::Yellow)
+                        } else {
+                            Style::default()
+                        };
+                        Span::styled(part.to_string(), style)
+                    })
+                    .collect();
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
+
+module! {
+    pub SearchViewModule {
+        components = [SearchViewImpl],
+        providers = [],
+    }
+}
+
+pub fn build_search_view_module() -> Arc<SearchViewModule> {
+    Arc::new(SearchViewModule::builder().build())
+}
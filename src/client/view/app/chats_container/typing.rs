@@ -1,5 +1,6 @@
 use crate::client::redux::state::client_chat::ChatsState;
 use crate::client::redux::state::State;
+use crate::client::view::app::chats_container::messages::format_attachment;
 use crate::client::view::View;
 use ratatui::layout::Rect;
 use ratatui::prelude::{Color, Style};
@@ -41,11 +42,27 @@ impl View for TypingViewImpl {
                 // Reconstruct the string to display from the character vector
                 let display_text: String = chat_chars[start_index..].iter().collect();
 
+                let mode = match (chat.editing_message, chat.replying_to) {
+                    (Some(_), _) => "Editing",
+                    (None, Some(_)) => "Replying",
+                    (None, None) => "Typing",
+                };
+                let title = if chat.pending_attachments.is_empty() {
+                    mode.to_string()
+                } else {
+                    let attachments: Vec<_> = chat
+                        .pending_attachments
+                        .iter()
+                        .map(format_attachment)
+                        .collect();
+                    format!("{} | 📎 {}", mode, attachments.join(", "))
+                };
+
                 let text = Paragraph::new(display_text)
                     .block(
                         Block::default()
                             .borders(Borders::ALL)
-                            .title("Typing")
+                            .title(title)
                             .style(Style::default().fg(color)),
                     )
                     .style(Style::default().fg(Color::White));
@@ -21,13 +21,24 @@ impl View for ControlsViewImpl {
     fn draw(&self, f: &mut Frame, rect: Rect, state: State) -> anyhow::Result<()> {
         let controls_text = match state.tab_state {
             TabState::Chats => match state.chats_state {
-                ChatsState::Chats => "| j/k: Select chat | h: Chat select | l: Messages",
-                ChatsState::Messages => "| j/k: Select message | i: Insert mode",
+                ChatsState::Chats => "| j/k: Select chat | h: Chat select | l: Messages | x: Leave chat | m: Mute/unmute | /: Search | @: Mentions",
+                ChatsState::Messages => {
+                    "| j/k: Select message | i: Insert mode | r: Reply | a: React | p: Pin/unpin | P: Expand pins | e: Edit message | d: Delete message | s: Save attachments | /: Search | @: Mentions"
+                }
                 ChatsState::Typing => {
-                    "| Type message | Enter: Send | Backspace: Delete | Esc: Exit Insert mode"
+                    "| Type message | Enter: Send | Tab: Attach file at typed path | Backspace: Delete | Esc: Exit Insert mode"
+                }
+                ChatsState::Search => {
+                    "| Type query | Enter: Search / Jump to message | Up/Down: Select result | Esc: Exit search"
+                }
+                ChatsState::Mentions => {
+                    "| j/k: Select mention | Enter: Jump to message | Esc: Exit mentions"
                 }
-                _ => "",
             },
+            TabState::Users if state.user_search.active => {
+                "| Type query | Enter: Search / Invite to selected chat | Up/Down: Select user | Esc: Exit search"
+            }
+            TabState::Users => "| j/k: Select user | Enter: Direct message | b: Block/unblock | /: Search users",
             _ => "",
         };
 
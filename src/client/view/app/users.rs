@@ -1,11 +1,14 @@
 use crate::client::redux::state::State;
 use crate::client::view::View;
-use ratatui::layout::{Alignment, Rect};
-use ratatui::prelude::{Color, Line, Style};
+use crate::utils::messenger::{Presence, PresenceStatus, User};
+use crate::utils::persistence::user_presence::effective_status;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::prelude::{Color, Line, Span, Style};
 use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Wrap};
 use ratatui::Frame;
 use shaku::{module, Component, Interface};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub trait UserView: Interface + View {}
 
@@ -15,13 +18,128 @@ pub struct UserViewImpl {}
 
 impl UserView for UserViewImpl {}
 
+/// The display name if the user set one, then their email, then their id.
+pub fn display_name(user: &User) -> String {
+    [&user.display_name, &user.email]
+        .into_iter()
+        .find(|name| !name.is_empty())
+        .unwrap_or(&user.id)
+        .clone()
+}
+
+/// Up to two initials of the display name, shown as the avatar of the user.
+fn initials(name: &str) -> String {
+    let words = name
+        .split(|c: char| c.is_whitespace() || c == '.' || c == '@' || c == '_')
+        .filter(|word| !word.is_empty());
+    let initials: String = words
+        .take(2)
+        .filter_map(|word| word.chars().next())
+        .flat_map(char::to_uppercase)
+        .collect();
+
+    if initials.is_empty() {
+        "?".to_string()
+    } else {
+        initials
+    }
+}
+
+fn avatar_color(user_id: &str) -> Color {
+    const COLORS: [Color; 6] = [
+        Color::Cyan,
+        Color::Magenta,
+        Color::Blue,
+        Color::LightGreen,
+        Color::LightRed,
+        Color::LightYellow,
+    ];
+    let hash = user_id.bytes().fold(0usize, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as usize)
+    });
+    COLORS[hash % COLORS.len()]
+}
+
+fn format_last_seen(presence: &Presence, now_secs: i64) -> String {
+    let last_seen = match &presence.last_seen_at {
+        Some(last_seen) => last_seen.seconds,
+        None => return "offline".to_string(),
+    };
+
+    let ago = (now_secs - last_seen).max(0);
+    match ago {
+        0..=59 => "last seen just now".to_string(),
+        60..=3599 => format!("last seen {}m ago", ago / 60),
+        3600..=86399 => format!("last seen {}h ago", ago / 3600),
+        _ => format!("last seen {}d ago", ago / 86400),
+    }
+}
+
+fn user_line(user: &User, presence: Option<&Presence>, now_secs: i64) -> Line<'static> {
+    let status = presence
+        .map(|presence| effective_status(presence, now_secs))
+        .unwrap_or(PresenceStatus::Offline);
+
+    let (color, description) = match (status, presence) {
+        (PresenceStatus::Online, _) => (Color::Green, "online".to_string()),
+        (PresenceStatus::Away, _) => (Color::Yellow, "away".to_string()),
+        (PresenceStatus::Offline, Some(presence)) => {
+            (Color::DarkGray, format_last_seen(presence, now_secs))
+        }
+        (PresenceStatus::Offline, None) => (Color::DarkGray, "offline".to_string()),
+    };
+
+    let name = display_name(user);
+    let mut spans = vec![
+        Span::styled("● ", Style::default().fg(color)),
+        Span::styled(
+            format!("[{}] ", initials(&name)),
+            Style::default().fg(avatar_color(&user.id)),
+        ),
+        Span::raw(name),
+        Span::styled(
+            format!(" ({})", description),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if !user.status_message.is_empty() {
+        spans.push(Span::styled(
+            format!(" - {}", user.status_message),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
+    Line::from(spans)
+}
+
 impl View for UserViewImpl {
     fn draw(&self, f: &mut Frame, rect: Rect, state: State) -> anyhow::Result<()> {
+        if state.user_search.active {
+            return self.draw_search(f, rect, &state);
+        }
+
         let users_lock = state.users.read().unwrap();
+        let presences_lock = state.presences.read().unwrap();
+        let blocked_lock = state.blocked_users.read().unwrap();
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
 
         let users = users_lock
             .iter()
-            .map(|user| Line::from(Line::from(user.email.clone())))
+            .enumerate()
+            .map(|(index, user)| {
+                let mut line = user_line(user, presences_lock.get(&user.id), now_secs);
+                if blocked_lock.contains(&user.id) {
+                    line.spans
+                        .push(Span::styled(" [blocked]", Style::default().fg(Color::Red)));
+                }
+                if state.selected_user == Some(index) {
+                    line.patch_style(Style::default().bg(Color::Red));
+                }
+                line
+            })
             .collect::<Vec<_>>();
 
         let p = Paragraph::new(users)
@@ -41,6 +159,87 @@ impl View for UserViewImpl {
     }
 }
 
+impl UserViewImpl {
+    /// The directory search box with its results, Enter invites the selected user
+    /// to the chat selected on the Chats tab.
+    fn draw_search(&self, f: &mut Frame, rect: Rect, state: &State) -> anyhow::Result<()> {
+        let user_search = &state.user_search;
+        let chats_lock = state.chats.read().unwrap();
+        let presences_lock = state.presences.read().unwrap();
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+            .split(rect);
+
+        let chat_name = |chat_id: i32| {
+            chats_lock
+                .iter()
+                .find(|chat| chat.id == chat_id)
+                .map_or(chat_id.to_string(), |chat| chat.name.clone())
+        };
+        let target = match state.selected_chat_id() {
+            Some(chat_id) => format!("Enter invites to {}", chat_name(chat_id)),
+            None => "Select a chat on the Chats tab to invite to".to_string(),
+        };
+
+        let search_box = Paragraph::new(format!("{}▏", user_search.query)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Search users | {}", target))
+                .style(Style::default().fg(Color::Red))
+                .border_type(BorderType::Plain),
+        );
+        f.render_widget(search_box, chunks[0]);
+
+        let hits = user_search
+            .hits
+            .iter()
+            .enumerate()
+            .map(|(index, user)| {
+                let mut line = user_line(user, presences_lock.get(&user.id), now_secs);
+                if user_search.selected_hit == Some(index) {
+                    line.patch_style(Style::default().bg(Color::Red));
+                }
+                line
+            })
+            .collect::<Vec<_>>();
+
+        let status = if user_search.searching {
+            "Searching…".to_string()
+        } else if let Some((user_id, chat_id)) = &user_search.last_invite {
+            let user_name = user_search
+                .hits
+                .iter()
+                .find(|user| &user.id == user_id)
+                .map_or(user_id.clone(), display_name);
+            format!("Invited {} to {}", user_name, chat_name(*chat_id))
+        } else if !user_search.hits_query.is_empty() && user_search.hits.is_empty() {
+            "No users found".to_string()
+        } else {
+            "Results".to_string()
+        };
+
+        let results = Paragraph::new(hits)
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Left)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(status)
+                    .style(Style::default().fg(Color::White))
+                    .border_type(BorderType::Plain),
+            );
+        f.render_widget(results, chunks[1]);
+
+        Ok(())
+    }
+}
+
 module! {
     pub ViewUserModule {
         components = [UserViewImpl],
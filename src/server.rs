@@ -4,18 +4,24 @@ use async_trait::async_trait;
 use shaku::{module, Component, Interface};
 use tonic::transport::Server as TonicServer;
 use tonic_async_interceptor::async_interceptor;
+use tower::util::MapRequestLayer;
 use tracing::info;
 
 use crate::server::auth_interceptor::{
-    build_auth_interceptor_module, AuthInterceptorFactory, AuthInterceptorModule,
+    build_auth_interceptor_module, tag_grpc_method, AuthInterceptorFactory, AuthInterceptorModule,
 };
 use crate::server::crab_messenger::{
-    build_crab_messenger_module, ChatResponseStream, CrabMessenger, CrabMessengerModule,
-    InviteResponseStream, MessengerAdapter,
+    build_crab_messenger_module, AttachmentResponseStream, ChatResponseStream, CrabMessenger,
+    CrabMessengerModule, InviteResponseStream, MessengerAdapter, PresenceResponseStream,
+};
+use crate::utils::db_connection_manager::{
+    build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
 };
 use crate::utils::messenger::messenger_server::MessengerServer;
+use crate::utils::persistence::user_presence::reset_connections;
 
 mod auth_interceptor;
+mod authenticated_user;
 mod crab_messenger;
 
 #[async_trait]
@@ -28,11 +34,19 @@ pub trait Server: Interface {
 pub struct ServerImpl {
     #[shaku(inject)]
     crab_messenger: Arc<
-        dyn CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream>,
+        dyn CrabMessenger<
+            ChatStream = ChatResponseStream,
+            InvitesStream = InviteResponseStream,
+            PresenceUpdatesStream = PresenceResponseStream,
+            DownloadAttachmentStream = AttachmentResponseStream,
+        >,
     >,
 
     #[shaku(inject)]
     auth_interceptor_factory: Arc<dyn AuthInterceptorFactory>,
+
+    #[shaku(inject)]
+    db_connection_manager: Arc<dyn DBConnectionManager>,
 }
 
 #[async_trait]
@@ -43,6 +57,12 @@ impl Server for ServerImpl {
 
         let addr = "[::1]:50051".parse().unwrap();
 
+        // No stream is open yet, whatever the last run left connected is stale.
+        let mut connection = self.db_connection_manager.get_connection()?;
+        let reset = reset_connections(&mut connection)?;
+        drop(connection);
+        info!("Reset the presence of {} users", reset);
+
         let messenger_adapter = MessengerAdapter::new(self.crab_messenger.clone());
         let auth_interceptor = self.auth_interceptor_factory.create();
         let interceptor_layer = async_interceptor(move |req| {
@@ -53,6 +73,7 @@ impl Server for ServerImpl {
         let messenger = MessengerServer::new(messenger_adapter);
 
         TonicServer::builder()
+            .layer(MapRequestLayer::new(tag_grpc_method))
             .layer(interceptor_layer)
             .add_service(messenger)
             .serve(addr)
@@ -67,13 +88,17 @@ module! {
         components = [ServerImpl],
         providers = [],
         use CrabMessengerModule {
-            components = [dyn CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream>],
+            components = [dyn CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream, PresenceUpdatesStream = PresenceResponseStream, DownloadAttachmentStream = AttachmentResponseStream>],
             providers = [],
         },
         use AuthInterceptorModule {
             components = [dyn AuthInterceptorFactory],
             providers = [],
         },
+        use DBConnectionManagerModule {
+            components = [dyn DBConnectionManager],
+            providers = [],
+        },
     }
 }
 
@@ -82,6 +107,7 @@ pub fn build_server_module() -> Arc<ServerModule> {
         ServerModule::builder(
             build_crab_messenger_module(),
             build_auth_interceptor_module(),
+            build_db_connection_manager_module(),
         )
         .build(),
     )
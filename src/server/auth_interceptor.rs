@@ -1,27 +1,82 @@
-use std::str::FromStr;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use diesel::prelude::*;
 use diesel::{PgConnection, RunQueryDsl};
-use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 use shaku::{module, Component, Interface};
-use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::codegen::http;
 use tonic::{Request, Status};
-use tracing::field::debug;
 use tracing::{debug, error, info, warn};
 
+use crate::server::authenticated_user::{AuthMethod, AuthenticatedUser};
 use crate::server::crab_messenger::user_manager::{
     build_user_manager_module, UserManager, UserManagerModule,
 };
-use crate::utils::auth::token::AccessToken;
 use crate::utils::db_connection_manager::{
     build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
 };
-use crate::utils::persistence::schema::users;
+use crate::utils::identity_provider::{
+    build_identity_provider_module, IdentityProvider, IdentityProviderModule,
+};
+use crate::utils::persistence::api_key::{
+    hash_api_key, ApiKey, SCOPE_MESSAGES_READ, SCOPE_MESSAGES_WRITE,
+};
+use crate::utils::persistence::schema::{api_keys, users};
 use crate::utils::persistence::user::User;
 
+/// How long a user found in the database is trusted to still be there.
+const KNOWN_USER_TTL: Duration = Duration::from_secs(10 * 60);
+/// Minimal interval between two updates of the last use of an api key.
+const API_KEY_USAGE_THROTTLE: Duration = Duration::from_secs(60);
+
+const API_KEY_SCHEME: &str = "ApiKey ";
+const BEARER_SCHEME: &str = "Bearer ";
+
+/// Path of the called method, e.g. `/messenger.Messenger/GetMessages`. The interceptor
+/// only sees the metadata, so the path is put in the extensions by [`tag_grpc_method`].
+#[derive(Clone, Debug)]
+pub struct GrpcMethod(pub String);
+
+pub fn tag_grpc_method<B>(mut req: http::Request<B>) -> http::Request<B> {
+    let method = GrpcMethod(req.uri().path().to_string());
+    req.extensions_mut().insert(method);
+    req
+}
+
+/// Scopes letting an api key call a method, any one of them is enough. Everything
+/// else, like managing chats or api keys, is left to users.
+fn method_scopes(method: &str) -> &'static [&'static str] {
+    match method.rsplit('/').next().unwrap_or_default() {
+        "Chat" | "GetMessages" | "SearchMessages" | "GetUserChats" | "MarkRead"
+        | "GetChatMembers" | "GetPinnedMessages" | "GetMentions" | "GetThread"
+        | "DownloadAttachment" => &[SCOPE_MESSAGES_READ],
+        "EditMessage" | "DeleteMessage" | "AddReaction" | "RemoveReaction" | "UploadAttachment" => {
+            &[SCOPE_MESSAGES_WRITE]
+        }
+        _ => &[],
+    }
+}
+
+/// What the `authorization` header of a request carries.
+#[derive(Debug, PartialEq, Eq)]
+enum Credentials<'a> {
+    ApiKey(&'a str),
+    Token(&'a str),
+}
+
+fn parse_authorization(authorization: &str) -> Credentials<'_> {
+    match authorization.strip_prefix(API_KEY_SCHEME) {
+        Some(key) => Credentials::ApiKey(key.trim()),
+        // Clients used to send the bare token, both forms are accepted.
+        None => Credentials::Token(
+            authorization
+                .strip_prefix(BEARER_SCHEME)
+                .unwrap_or(authorization),
+        ),
+    }
+}
+
 pub trait AuthInterceptorFactory: Interface {
     fn create(&self) -> AuthInterceptor;
 }
@@ -35,11 +90,8 @@ pub struct AuthInterceptorFactoryImpl {
     #[shaku(inject)]
     user_manager: Arc<dyn UserManager>,
 
-    client_id: String,
-    client_secret: String,
-    audience: String,
-    server_n: String,
-    server_e: String,
+    #[shaku(inject)]
+    identity_provider: Arc<dyn IdentityProvider>,
 }
 
 impl AuthInterceptorFactory for AuthInterceptorFactoryImpl {
@@ -47,18 +99,63 @@ impl AuthInterceptorFactory for AuthInterceptorFactoryImpl {
         AuthInterceptor::new(
             self.db_connection_manager.clone(),
             self.user_manager.clone(),
-            self.client_id.clone(),
-            self.client_secret.clone(),
-            self.audience.clone(),
-            self.server_n.clone(),
-            self.server_e.clone(),
+            self.identity_provider.clone(),
         )
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct UserInfo {
-    email: String,
+/// Users already checked, shared by all clones of the interceptor.
+#[derive(Default)]
+struct KnownUsers {
+    /// Email of each user and when they were checked.
+    verified: HashMap<String, (String, Instant)>,
+    /// First-time lookups in progress, other requests for the same user wait on them.
+    lookups: HashMap<String, Arc<tokio::sync::Mutex<()>>>,
+}
+
+/// Share of a user lookup, the lookup is forgotten when the last share is dropped. Until
+/// then new requests wait on the same lock, even if the lookup failed.
+struct UserLookup<'a> {
+    known_users: &'a Mutex<KnownUsers>,
+    user_id: &'a str,
+    lock: Option<Arc<tokio::sync::Mutex<()>>>,
+}
+
+impl<'a> UserLookup<'a> {
+    fn join(known_users: &'a Mutex<KnownUsers>, user_id: &'a str) -> Self {
+        let lock = known_users
+            .lock()
+            .unwrap()
+            .lookups
+            .entry(user_id.to_string())
+            .or_default()
+            .clone();
+
+        Self {
+            known_users,
+            user_id,
+            lock: Some(lock),
+        }
+    }
+
+    async fn lock(&self) -> tokio::sync::MutexGuard<'_, ()> {
+        self.lock.as_ref().unwrap().lock().await
+    }
+}
+
+impl Drop for UserLookup<'_> {
+    fn drop(&mut self) {
+        // Shares are only taken and released under this lock, so the count can be trusted.
+        let mut known_users = self.known_users.lock().unwrap();
+        self.lock.take();
+        if known_users
+            .lookups
+            .get(self.user_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            known_users.lookups.remove(self.user_id);
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -67,113 +164,32 @@ pub struct AuthInterceptor {
 
     user_manager: Arc<dyn UserManager>,
 
-    client_id: String,
-    client_secret: String,
-    audience: String,
-    server_n: String,
-    server_e: String,
-}
+    identity_provider: Arc<dyn IdentityProvider>,
 
-#[derive(Deserialize)]
-struct Auth0TokenResponse {
-    access_token: String,
+    known_users: Arc<Mutex<KnownUsers>>,
 }
 
 impl AuthInterceptor {
     pub fn new(
         db_connection_manager: Arc<dyn DBConnectionManager>,
         user_manager: Arc<dyn UserManager>,
-        client_id: String,
-        client_secret: String,
-        audience: String,
-        server_n: String,
-        server_e: String,
+        identity_provider: Arc<dyn IdentityProvider>,
     ) -> Self {
         Self {
             db_connection_manager,
             user_manager,
-            client_id,
-            client_secret,
-            audience,
-            server_n,
-            server_e,
+            identity_provider,
+            known_users: Default::default(),
         }
     }
 
-    async fn get_auth0_access_token(&self) -> anyhow::Result<String> {
-        let client = reqwest::Client::new();
-        let token_url = "https://crab-messenger.eu.auth0.com/oauth/token";
-        let request_body = json!({
-            "client_id": self.client_id,
-            "client_secret": self.client_secret,
-            "audience": self.audience,
-            "grant_type": "client_credentials"
-        });
-
-        let response = client
-            .post(token_url)
-            .json(&request_body)
-            .send()
-            .await
-            .map_err(|err| {
-                error!("Failed to retrieve Auth0 access token: {}", err);
-                err
-            })?;
-
-        let token_response: Auth0TokenResponse = response.json().await.map_err(|err| {
-            error!("Failed to parse Auth0 token response: {}", err);
-            anyhow::Error::new(err)
-        })?;
-
-        info!(
-            "Retrieved Auth0 access token {}",
-            token_response.access_token
-        );
-        Ok(token_response.access_token)
-    }
-
-    #[tracing::instrument(skip(self))]
-    async fn get_user_info(&self, user_id: &str) -> anyhow::Result<String> {
-        info!("Getting user info");
-        let access_token = self.get_auth0_access_token().await?;
-
-        let client = reqwest::Client::new();
-        let url = format!("{}users", self.audience);
-
-        let from_params = [
-            ("q", format!("{}", user_id)),
-            ("search_engine", "v3".to_string()),
-        ];
-
-        let response = client
-            .get(url)
-            .query(&from_params)
-            .bearer_auth(access_token)
-            .send()
-            .await
-            .map_err(|err| {
-                error!("Failed to get user info: {}", err);
-                err
-            })?;
-        debug!("Response: {:?}", response);
-        let response_body = response.text().await.map_err(|err| {
-            error!("Failed to get user info: {}", err);
-            err
-        })?;
-
-        debug!("Response body: {}", response_body);
-
-        let users: Vec<UserInfo> = serde_json::from_str(&response_body).map_err(|err| {
-            error!("Failed to parse user info: {}", err);
-            anyhow::Error::new(err)
-        })?;
-
-        users.get(0).map(|user| user.email.clone()).ok_or_else(|| {
-            anyhow::Error::new(std::io::Error::new(
-                std::io::ErrorKind::NotFound,
-                "User not found",
-            ))
-        })
+    fn known_email(&self, user_id: &str) -> Option<String> {
+        let known_users = self.known_users.lock().unwrap();
+        known_users
+            .verified
+            .get(user_id)
+            .filter(|(_, verified_at)| verified_at.elapsed() < KNOWN_USER_TTL)
+            .map(|(email, _)| email.clone())
     }
 
     async fn find_by_id(
@@ -191,34 +207,137 @@ impl AuthInterceptor {
             })
     }
 
+    /// Email of the user, who is created on their first request.
     #[tracing::instrument(skip(self))]
-    async fn check_user(&self, user_id: &str) -> anyhow::Result<()> {
-        let mut conn = self.db_connection_manager.get_connection()?;
+    async fn check_user(&self, user_id: &str) -> anyhow::Result<String> {
+        if let Some(email) = self.known_email(user_id) {
+            return Ok(email);
+        }
+
+        let lookup = UserLookup::join(&self.known_users, user_id);
+        let _lookup_guard = lookup.lock().await;
+
+        // Whoever held the lookup before may have done the work already.
+        if let Some(email) = self.known_email(user_id) {
+            return Ok(email);
+        }
+
+        let result = self.ensure_user(user_id).await;
+
+        let mut known_users = self.known_users.lock().unwrap();
+        if let Ok(email) = &result {
+            known_users
+                .verified
+                .retain(|_, (_, verified_at)| verified_at.elapsed() < KNOWN_USER_TTL);
+            known_users
+                .verified
+                .insert(user_id.to_string(), (email.clone(), Instant::now()));
+        }
 
-        if let Some(user) = self.find_by_id(&mut conn, user_id).await? {
-            Ok(())
+        result
+    }
+
+    /// Creates users signing in for the first time.
+    async fn ensure_user(&self, user_id: &str) -> anyhow::Result<String> {
+        // The connection isn't held while the identity provider is asked for the email.
+        let user = {
+            let mut conn = self.db_connection_manager.get_connection()?;
+            self.find_by_id(&mut conn, user_id).await?
+        };
+
+        if let Some(user) = user {
+            Ok(user.email)
         } else {
-            let email = self.get_user_info(user_id).await?;
+            let email = self.identity_provider.get_email(user_id).await?;
 
             debug!("Creating user with email: {}", &email);
             self.user_manager
                 .create_user(User {
                     id: user_id.to_string(),
-                    email,
+                    email: email.clone(),
                 })
                 .await?;
 
-            Ok(())
+            Ok(email)
         }
     }
 
+    /// Bot user of the key, if the key exists, isn't revoked and may call the method.
+    async fn check_api_key(
+        &self,
+        key: &str,
+        method: Option<&GrpcMethod>,
+    ) -> Result<AuthenticatedUser, Status> {
+        let mut conn = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let api_key = api_keys::table
+            .filter(api_keys::secret_hash.eq(hash_api_key(key)))
+            .filter(api_keys::revoked_at.is_null())
+            .first::<ApiKey>(&mut conn)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get api key: {}", e);
+                Status::internal("Failed to get api key")
+            })?
+            .ok_or_else(|| {
+                warn!("Unknown or revoked api key");
+                Status::unauthenticated("Invalid api key")
+            })?;
+
+        let allowed = method.is_some_and(|method| {
+            method_scopes(&method.0)
+                .iter()
+                .any(|scope| api_key.has_scope(scope))
+        });
+        if !allowed {
+            warn!("Api key {} can't call {:?}", api_key.id, method);
+            return Err(Status::permission_denied(
+                "The api key doesn't allow this request",
+            ));
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        if api_key
+            .last_used_at
+            .is_none_or(|at| (now - at).to_std().unwrap_or_default() >= API_KEY_USAGE_THROTTLE)
+        {
+            // Not being able to record the use doesn't make the key invalid.
+            if let Err(e) = diesel::update(api_keys::table)
+                .filter(api_keys::id.eq(api_key.id))
+                .set(api_keys::last_used_at.eq(now))
+                .execute(&mut conn)
+            {
+                error!("Failed to record api key use: {}", e);
+            }
+        }
+
+        let bot = self
+            .find_by_id(&mut conn, &api_key.bot_user_id)
+            .await
+            .map_err(|_| Status::internal("Failed to get bot user"))?
+            .ok_or_else(|| {
+                error!("Bot user of api key {} not found", api_key.id);
+                Status::internal("Failed to get bot user")
+            })?;
+
+        Ok(AuthenticatedUser {
+            id: bot.id,
+            email: bot.email,
+            scopes: api_key.scopes,
+            auth_method: AuthMethod::ApiKey,
+        })
+    }
+
     #[tracing::instrument(skip(self, req))]
     pub async fn intercept(&self, mut req: Request<()>) -> Result<Request<()>, Status> {
         info!("Intercepting request");
         let self_clone = self.clone();
 
         let headers = req.metadata();
-        let token = headers
+        let authorization = headers
             .get("authorization")
             .to_owned()
             .and_then(|val| val.to_str().ok())
@@ -227,41 +346,50 @@ impl AuthInterceptor {
                 Status::unauthenticated("No authorization token found")
             })?;
 
-        let mut validation = Validation::new(Algorithm::RS256);
-        validation.set_audience(&["crab-api", "https://crab-messenger.eu.auth0.com/userinfo"]);
-
-        let token_message = decode::<AccessToken>(
-            &token,
-            &DecodingKey::from_rsa_components(&self.server_n, &self.server_e)
-                .expect("Failed to decode public key"),
-            &validation,
-        )
-        .map_err(|e| {
-            error!("Failed to decode token: {}", e);
-            Status::unauthenticated("Failed to decode token")
-        })?;
-
-        let access_token = token_message.claims;
-        let user_id = &access_token.id;
-
-        match self_clone.check_user(user_id).await {
-            Ok(_) => {
-                info!("User verified or created successfully");
+        let authenticated_user = match parse_authorization(authorization) {
+            Credentials::ApiKey(key) => {
+                self.check_api_key(key, req.extensions().get::<GrpcMethod>())
+                    .await?
             }
-            Err(e) => {
-                error!("Failed to verify or create user: {}", e);
-                return Err(Status::internal("Failed to verify or create user"));
+            Credentials::Token(token) => {
+                let access_token =
+                    self.identity_provider
+                        .verify_token(token)
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to decode token: {}", e);
+                            Status::unauthenticated("Failed to decode token")
+                        })?;
+
+                let email = match self_clone.check_user(&access_token.id).await {
+                    Ok(email) => {
+                        info!("User verified or created successfully");
+                        email
+                    }
+                    Err(e) => {
+                        error!("Failed to verify or create user: {}", e);
+                        return Err(Status::internal("Failed to verify or create user"));
+                    }
+                };
+
+                AuthenticatedUser {
+                    id: access_token.id,
+                    email,
+                    scopes: Vec::new(),
+                    auth_method: AuthMethod::Token,
+                }
             }
-        }
+        };
 
-        let mut metadata_map = MetadataMap::new();
-        let user_id_meta = MetadataValue::from_str(user_id).map_err(|_| {
-            error!("Invalid user_id");
-            Status::invalid_argument("Invalid user_id")
-        })?;
+        info!(
+            "Authenticated {} ({}) with {:?}",
+            authenticated_user.id, authenticated_user.email, authenticated_user.auth_method
+        );
 
-        metadata_map.insert("user_id", user_id_meta);
-        *req.metadata_mut() = metadata_map;
+        // The rest of the metadata, like request ids, is left for the handlers, only
+        // the credentials don't go further.
+        req.metadata_mut().remove("authorization");
+        req.extensions_mut().insert(authenticated_user);
 
         Ok(req)
     }
@@ -278,6 +406,10 @@ module! {
         use UserManagerModule{
             components = [dyn UserManager],
             providers = []
+        },
+        use IdentityProviderModule{
+            components = [dyn IdentityProvider],
+            providers = []
         }
     }
 }
@@ -294,40 +426,7 @@ pub fn build_auth_interceptor_module() -> Arc<AuthInterceptorModule> {
         AuthInterceptorModule::builder(
             build_db_connection_manager_module(),
             build_user_manager_module(),
-        )
-        .with_component_parameters::<AuthInterceptorFactoryImpl>(
-            AuthInterceptorFactoryImplParameters {
-                client_id: dotenv::var("AUTH0_CLIENT_ID")
-                    .map_err(|err| {
-                        error!("AUTH0_CLIENT_ID must be set in .env file: {}", err);
-                        panic!("AUTH0_CLIENT_ID must be set in .env file");
-                    })
-                    .unwrap(),
-                client_secret: dotenv::var("AUTH0_CLIENT_SECRET")
-                    .map_err(|err| {
-                        error!("AUTH0_CLIENT_SECRET must be set in .env file: {}", err);
-                        panic!("AUTH0_CLIENT_SECRET must be set in .env file");
-                    })
-                    .unwrap(),
-                audience: dotenv::var("AUTH0_AUDIENCE")
-                    .map_err(|err| {
-                        error!("AUTH0_AUDIENCE must be set in .env file: {}", err);
-                        panic!("AUTH0_AUDIENCE must be set in .env file");
-                    })
-                    .unwrap(),
-                server_n: dotenv::var("AUTH0_SERVER_N")
-                    .map_err(|err| {
-                        error!("AUTH0_SERVER_N must be set in .env file: {}", err);
-                        panic!("AUTH0_SERVER_N must be set in .env file");
-                    })
-                    .unwrap(),
-                server_e: dotenv::var("AUTH0_SERVER_E")
-                    .map_err(|err| {
-                        error!("AUTH0_SERVER_E must be set in .env file: {}", err);
-                        panic!("AUTH0_SERVER_E must be set in .env file");
-                    })
-                    .unwrap(),
-            },
+            build_identity_provider_module(),
         )
         .build(),
     )
@@ -337,7 +436,46 @@ pub fn build_auth_interceptor_module() -> Arc<AuthInterceptorModule> {
 mod test {
     use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 
+    use super::{method_scopes, parse_authorization, Credentials};
     use crate::utils::auth::token::AccessToken;
+    use crate::utils::persistence::api_key::{SCOPE_MESSAGES_READ, SCOPE_MESSAGES_WRITE};
+
+    #[test]
+    fn test_parse_authorization() {
+        assert_eq!(
+            parse_authorization("ApiKey crab_abc"),
+            Credentials::ApiKey("crab_abc")
+        );
+        assert_eq!(
+            parse_authorization("Bearer eyJ.a.b"),
+            Credentials::Token("eyJ.a.b")
+        );
+        assert_eq!(
+            parse_authorization("eyJ.a.b"),
+            Credentials::Token("eyJ.a.b")
+        );
+    }
+
+    #[test]
+    fn test_method_scopes() {
+        assert_eq!(
+            method_scopes("/messenger.Messenger/Chat"),
+            &[SCOPE_MESSAGES_READ]
+        );
+        assert_eq!(
+            method_scopes("/messenger.Messenger/GetMessages"),
+            &[SCOPE_MESSAGES_READ]
+        );
+        assert_eq!(
+            method_scopes("/messenger.Messenger/EditMessage"),
+            &[SCOPE_MESSAGES_WRITE]
+        );
+        // Managing chats, invites and keys is left to users.
+        assert!(method_scopes("/messenger.Messenger/CreateChat").is_empty());
+        assert!(method_scopes("/messenger.Messenger/SendInvite").is_empty());
+        assert!(method_scopes("/messenger.Messenger/CreateApiKey").is_empty());
+        assert!(method_scopes("/messenger.Messenger/RevokeApiKey").is_empty());
+    }
 
     #[test]
     fn test_key() {
@@ -0,0 +1,47 @@
+use tonic::{Request, Status};
+use tracing::error;
+
+/// How the caller of a request proved who they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    /// Access token issued by the identity provider.
+    Token,
+    /// Api key, the request is made as the bot user of the key.
+    ApiKey,
+}
+
+/// Caller of a request, put in its extensions by the auth interceptor.
+#[derive(Clone, Debug)]
+pub struct AuthenticatedUser {
+    pub id: String,
+    pub email: String,
+    /// Scopes of the api key, empty for tokens since users can do anything.
+    pub scopes: Vec<String>,
+    pub auth_method: AuthMethod,
+}
+
+impl AuthenticatedUser {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        match self.auth_method {
+            AuthMethod::Token => true,
+            AuthMethod::ApiKey => self.scopes.iter().any(|s| s == scope),
+        }
+    }
+}
+
+pub trait AuthenticatedRequest {
+    /// Caller of the request. Requests that didn't go through the interceptor are
+    /// rejected rather than handled on behalf of nobody.
+    // Handlers return `Status` as is, boxing it here would only move the conversion there.
+    #[allow(clippy::result_large_err)]
+    fn authenticated_user(&self) -> Result<&AuthenticatedUser, Status>;
+}
+
+impl<T> AuthenticatedRequest for Request<T> {
+    fn authenticated_user(&self) -> Result<&AuthenticatedUser, Status> {
+        self.extensions().get::<AuthenticatedUser>().ok_or_else(|| {
+            error!("Request has no authenticated user");
+            Status::unauthenticated("Not authenticated")
+        })
+    }
+}
@@ -7,6 +7,12 @@ use futures_core::Stream;
 use shaku::{module, Component, Interface};
 use tonic::{Request, Response, Status, Streaming};
 
+use crate::server::crab_messenger::api_key_manager::{
+    build_api_key_manager_module, ApiKeyManager, ApiKeyManagerModule,
+};
+use crate::server::crab_messenger::attachment_manager::{
+    build_attachment_manager_module, AttachmentManager, AttachmentManagerModule,
+};
 use crate::server::crab_messenger::chat_manager::{
     build_chat_manager_module, ChatManager, ChatManagerModule,
 };
@@ -16,15 +22,21 @@ use crate::server::crab_messenger::invite_manager::{
 use crate::server::crab_messenger::message_manager::{
     build_message_manager_module, MessageManager, MessageManagerModule,
 };
+use crate::server::crab_messenger::presence_manager::{
+    build_presence_manager_module, PresenceManager, PresenceManagerModule,
+};
 use crate::server::crab_messenger::user_manager::{
     build_user_manager_module, UserManager, UserManagerModule,
 };
 use crate::utils::messenger::messenger_server::Messenger;
-use crate::utils::messenger::{AnswerInviteRequest, AnswerInviteResponse, Chats, CreateChatRequest, CreateChatResponse, GetInvitesRequest, GetInvitesResponse, GetMessagesRequest, GetRelatedUsersRequest, GetUserChatsRequest, Invite as ProtoInvite, InvitesRequest, Message as MMessage, Messages, SendInviteRequest, SendMessage, Users};
+use crate::utils::messenger::{AnswerInviteRequest, AnswerInviteResponse, ApiKeys, Attachment, AttachmentChunk, BlockUserRequest, BlockUserResponse, ChatEvent, ChatMembers, ChatRequest, Chats, CreateApiKeyRequest, CreateApiKeyResponse, CreateChatRequest, CreateChatResponse, CreateInviteLinkRequest, DeleteChatRequest, DeleteChatResponse, DeleteMessageRequest, DeleteMessageResponse, DownloadAttachmentRequest, EditMessageRequest, GetChatMembersRequest, GetInvitesRequest, GetInvitesResponse, GetMentionsRequest, GetMessagesRequest, GetPinnedMessagesRequest, GetPresenceRequest, GetProfileRequest, GetRelatedUsersRequest, GetSentInvitesRequest, GetSentInvitesResponse, GetThreadRequest, GetUserChatsRequest, InviteEvent, InviteLink, InvitesRequest, JoinByCodeRequest, JoinByCodeResponse, LeaveChatRequest, LeaveChatResponse, ListApiKeysRequest, ListBlockedRequest, MarkReadRequest, MarkReadResponse, Message as MMessage, Messages, MuteChatRequest, MuteChatResponse, OpenDirectChatRequest, OpenDirectChatResponse, PinMessageRequest, PinnedMessage, PinnedMessages, Presence, PresenceUpdatesRequest, Presences, Profile, ReactionRequest, ReactionResponse, RemoveMemberRequest, RemoveMemberResponse, RevokeApiKeyRequest, RevokeApiKeyResponse, RevokeInviteRequest, RevokeInviteResponse, SearchMessagesRequest, SearchMessagesResponse, SendInviteRequest, Thread, UnblockUserRequest, UnblockUserResponse, UnpinMessageRequest, UnpinMessageResponse, UpdateChatRequest, UpdateChatResponse, UpdateProfileRequest, UploadAttachmentRequest, Users};
 use crate::utils::messenger::{SearchUserQuery, SendInviteResponse};
 
+mod api_key_manager;
+mod attachment_manager;
 mod chat_manager;
 mod message_manager;
+mod presence_manager;
 pub mod user_manager;
 
 mod invite_manager;
@@ -32,7 +44,7 @@ mod invite_manager;
 pub trait CrabMessenger: Interface + Messenger {}
 
 #[derive(Component)]
-#[shaku(interface = CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream>)]
+#[shaku(interface = CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream, PresenceUpdatesStream = PresenceResponseStream, DownloadAttachmentStream = AttachmentResponseStream>)]
 pub struct CrabMessengerImpl {
     #[shaku(inject)]
     message_manager: Arc<dyn MessageManager<ChatStream = ChatResponseStream>>,
@@ -45,18 +57,31 @@ pub struct CrabMessengerImpl {
 
     #[shaku(inject)]
     chat_manager: Arc<dyn ChatManager>,
+
+    #[shaku(inject)]
+    presence_manager: Arc<dyn PresenceManager>,
+
+    #[shaku(inject)]
+    attachment_manager:
+        Arc<dyn AttachmentManager<DownloadAttachmentStream = AttachmentResponseStream>>,
+
+    #[shaku(inject)]
+    api_key_manager: Arc<dyn ApiKeyManager>,
 }
 
 impl CrabMessenger for CrabMessengerImpl {}
-pub type ChatResponseStream = Pin<Box<dyn Stream<Item = Result<MMessage, Status>> + Send>>;
-pub type InviteResponseStream = Pin<Box<dyn Stream<Item = Result<ProtoInvite, Status>> + Send>>;
+pub type ChatResponseStream = Pin<Box<dyn Stream<Item = Result<ChatEvent, Status>> + Send>>;
+pub type InviteResponseStream = Pin<Box<dyn Stream<Item = Result<InviteEvent, Status>> + Send>>;
+pub type PresenceResponseStream = Pin<Box<dyn Stream<Item = Result<Presence, Status>> + Send>>;
+pub type AttachmentResponseStream =
+    Pin<Box<dyn Stream<Item = Result<AttachmentChunk, Status>> + Send>>;
 
 #[async_trait]
 impl Messenger for CrabMessengerImpl {
     type ChatStream = ChatResponseStream;
     async fn chat(
         &self,
-        request: Request<Streaming<SendMessage>>,
+        request: Request<Streaming<ChatRequest>>,
     ) -> Result<Response<Self::ChatStream>, Status> {
         self.message_manager.chat(request).await
     }
@@ -68,6 +93,13 @@ impl Messenger for CrabMessengerImpl {
         self.message_manager.get_messages(request).await
     }
 
+    async fn search_messages(
+        &self,
+        request: Request<SearchMessagesRequest>,
+    ) -> Result<Response<SearchMessagesResponse>, Status> {
+        self.message_manager.search_messages(request).await
+    }
+
     async fn search_user(
         &self,
         request: Request<SearchUserQuery>,
@@ -82,6 +114,83 @@ impl Messenger for CrabMessengerImpl {
         self.chat_manager.get_user_chats(request).await
     }
 
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadResponse>, Status> {
+        self.chat_manager.mark_read(request).await
+    }
+
+    async fn open_direct_chat(
+        &self,
+        request: Request<OpenDirectChatRequest>,
+    ) -> Result<Response<OpenDirectChatResponse>, Status> {
+        self.chat_manager.open_direct_chat(request).await
+    }
+
+    async fn leave_chat(
+        &self,
+        request: Request<LeaveChatRequest>,
+    ) -> Result<Response<LeaveChatResponse>, Status> {
+        self.chat_manager.leave_chat(request).await
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberResponse>, Status> {
+        self.chat_manager.remove_member(request).await
+    }
+
+    async fn update_chat(
+        &self,
+        request: Request<UpdateChatRequest>,
+    ) -> Result<Response<UpdateChatResponse>, Status> {
+        self.chat_manager.update_chat(request).await
+    }
+
+    async fn get_chat_members(
+        &self,
+        request: Request<GetChatMembersRequest>,
+    ) -> Result<Response<ChatMembers>, Status> {
+        self.chat_manager.get_chat_members(request).await
+    }
+
+    async fn delete_chat(
+        &self,
+        request: Request<DeleteChatRequest>,
+    ) -> Result<Response<DeleteChatResponse>, Status> {
+        self.chat_manager.delete_chat(request).await
+    }
+
+    async fn mute_chat(
+        &self,
+        request: Request<MuteChatRequest>,
+    ) -> Result<Response<MuteChatResponse>, Status> {
+        self.chat_manager.mute_chat(request).await
+    }
+
+    async fn pin_message(
+        &self,
+        request: Request<PinMessageRequest>,
+    ) -> Result<Response<PinnedMessage>, Status> {
+        self.chat_manager.pin_message(request).await
+    }
+
+    async fn unpin_message(
+        &self,
+        request: Request<UnpinMessageRequest>,
+    ) -> Result<Response<UnpinMessageResponse>, Status> {
+        self.chat_manager.unpin_message(request).await
+    }
+
+    async fn get_pinned_messages(
+        &self,
+        request: Request<GetPinnedMessagesRequest>,
+    ) -> Result<Response<PinnedMessages>, Status> {
+        self.chat_manager.get_pinned_messages(request).await
+    }
+
     async fn create_chat(&self, request: Request<CreateChatRequest>) -> Result<Response<CreateChatResponse>, Status> {
        self.chat_manager.create_chat(request).await 
     }
@@ -93,6 +202,41 @@ impl Messenger for CrabMessengerImpl {
         self.user_manager.get_related_users(request).await
     }
 
+    async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        self.user_manager.get_profile(request).await
+    }
+
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        self.user_manager.update_profile(request).await
+    }
+
+    async fn block_user(
+        &self,
+        request: Request<BlockUserRequest>,
+    ) -> Result<Response<BlockUserResponse>, Status> {
+        self.user_manager.block_user(request).await
+    }
+
+    async fn unblock_user(
+        &self,
+        request: Request<UnblockUserRequest>,
+    ) -> Result<Response<UnblockUserResponse>, Status> {
+        self.user_manager.unblock_user(request).await
+    }
+
+    async fn list_blocked(
+        &self,
+        request: Request<ListBlockedRequest>,
+    ) -> Result<Response<Users>, Status> {
+        self.user_manager.list_blocked(request).await
+    }
+
     async fn send_invite(
         &self,
         request: Request<SendInviteRequest>,
@@ -122,11 +266,139 @@ impl Messenger for CrabMessengerImpl {
     ) -> Result<Response<GetInvitesResponse>, Status> {
         self.invite_manager.get_invites(request).await
     }
+
+    async fn get_sent_invites(
+        &self,
+        request: Request<GetSentInvitesRequest>,
+    ) -> Result<Response<GetSentInvitesResponse>, Status> {
+        self.invite_manager.get_sent_invites(request).await
+    }
+
+    async fn revoke_invite(
+        &self,
+        request: Request<RevokeInviteRequest>,
+    ) -> Result<Response<RevokeInviteResponse>, Status> {
+        self.invite_manager.revoke_invite(request).await
+    }
+
+    async fn create_invite_link(
+        &self,
+        request: Request<CreateInviteLinkRequest>,
+    ) -> Result<Response<InviteLink>, Status> {
+        self.invite_manager.create_invite_link(request).await
+    }
+
+    async fn join_by_code(
+        &self,
+        request: Request<JoinByCodeRequest>,
+    ) -> Result<Response<JoinByCodeResponse>, Status> {
+        self.invite_manager.join_by_code(request).await
+    }
+
+    async fn edit_message(
+        &self,
+        request: Request<EditMessageRequest>,
+    ) -> Result<Response<MMessage>, Status> {
+        self.message_manager.edit_message(request).await
+    }
+
+    async fn delete_message(
+        &self,
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<DeleteMessageResponse>, Status> {
+        self.message_manager.delete_message(request).await
+    }
+
+    async fn get_mentions(
+        &self,
+        request: Request<GetMentionsRequest>,
+    ) -> Result<Response<Messages>, Status> {
+        self.message_manager.get_mentions(request).await
+    }
+
+    async fn get_thread(
+        &self,
+        request: Request<GetThreadRequest>,
+    ) -> Result<Response<Thread>, Status> {
+        self.message_manager.get_thread(request).await
+    }
+
+    async fn add_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        self.message_manager.add_reaction(request).await
+    }
+
+    async fn remove_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        self.message_manager.remove_reaction(request).await
+    }
+
+    async fn get_presence(
+        &self,
+        request: Request<GetPresenceRequest>,
+    ) -> Result<Response<Presences>, Status> {
+        self.presence_manager.get_presence(request).await
+    }
+
+    type PresenceUpdatesStream = PresenceResponseStream;
+
+    async fn presence_updates(
+        &self,
+        request: Request<PresenceUpdatesRequest>,
+    ) -> Result<Response<Self::PresenceUpdatesStream>, Status> {
+        self.presence_manager.presence_updates(request).await
+    }
+
+    async fn upload_attachment(
+        &self,
+        request: Request<Streaming<UploadAttachmentRequest>>,
+    ) -> Result<Response<Attachment>, Status> {
+        self.attachment_manager.upload_attachment(request).await
+    }
+
+    type DownloadAttachmentStream = AttachmentResponseStream;
+
+    async fn download_attachment(
+        &self,
+        request: Request<DownloadAttachmentRequest>,
+    ) -> Result<Response<Self::DownloadAttachmentStream>, Status> {
+        self.attachment_manager.download_attachment(request).await
+    }
+
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        self.api_key_manager.create_api_key(request).await
+    }
+
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ApiKeys>, Status> {
+        self.api_key_manager.list_api_keys(request).await
+    }
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        self.api_key_manager.revoke_api_key(request).await
+    }
 }
 
 pub struct MessengerAdapter {
     messenger: Arc<
-        dyn CrabMessenger<ChatStream = ChatResponseStream, InvitesStream = InviteResponseStream>,
+        dyn CrabMessenger<
+            ChatStream = ChatResponseStream,
+            InvitesStream = InviteResponseStream,
+            PresenceUpdatesStream = PresenceResponseStream,
+            DownloadAttachmentStream = AttachmentResponseStream,
+        >,
     >,
 }
 
@@ -136,6 +408,8 @@ impl MessengerAdapter {
             dyn CrabMessenger<
                 ChatStream = ChatResponseStream,
                 InvitesStream = InviteResponseStream,
+                PresenceUpdatesStream = PresenceResponseStream,
+                DownloadAttachmentStream = AttachmentResponseStream,
             >,
         >,
     ) -> Self {
@@ -148,7 +422,7 @@ impl Messenger for MessengerAdapter {
     type ChatStream = ChatResponseStream;
     async fn chat(
         &self,
-        request: Request<Streaming<SendMessage>>,
+        request: Request<Streaming<ChatRequest>>,
     ) -> Result<Response<Self::ChatStream>, Status> {
         self.messenger.chat(request).await
     }
@@ -160,6 +434,13 @@ impl Messenger for MessengerAdapter {
         self.messenger.get_messages(request).await
     }
 
+    async fn search_messages(
+        &self,
+        request: Request<SearchMessagesRequest>,
+    ) -> Result<Response<SearchMessagesResponse>, Status> {
+        self.messenger.search_messages(request).await
+    }
+
     async fn search_user(
         &self,
         request: Request<SearchUserQuery>,
@@ -174,6 +455,83 @@ impl Messenger for MessengerAdapter {
         self.messenger.get_user_chats(request).await
     }
 
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadResponse>, Status> {
+        self.messenger.mark_read(request).await
+    }
+
+    async fn open_direct_chat(
+        &self,
+        request: Request<OpenDirectChatRequest>,
+    ) -> Result<Response<OpenDirectChatResponse>, Status> {
+        self.messenger.open_direct_chat(request).await
+    }
+
+    async fn leave_chat(
+        &self,
+        request: Request<LeaveChatRequest>,
+    ) -> Result<Response<LeaveChatResponse>, Status> {
+        self.messenger.leave_chat(request).await
+    }
+
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberResponse>, Status> {
+        self.messenger.remove_member(request).await
+    }
+
+    async fn update_chat(
+        &self,
+        request: Request<UpdateChatRequest>,
+    ) -> Result<Response<UpdateChatResponse>, Status> {
+        self.messenger.update_chat(request).await
+    }
+
+    async fn get_chat_members(
+        &self,
+        request: Request<GetChatMembersRequest>,
+    ) -> Result<Response<ChatMembers>, Status> {
+        self.messenger.get_chat_members(request).await
+    }
+
+    async fn delete_chat(
+        &self,
+        request: Request<DeleteChatRequest>,
+    ) -> Result<Response<DeleteChatResponse>, Status> {
+        self.messenger.delete_chat(request).await
+    }
+
+    async fn mute_chat(
+        &self,
+        request: Request<MuteChatRequest>,
+    ) -> Result<Response<MuteChatResponse>, Status> {
+        self.messenger.mute_chat(request).await
+    }
+
+    async fn pin_message(
+        &self,
+        request: Request<PinMessageRequest>,
+    ) -> Result<Response<PinnedMessage>, Status> {
+        self.messenger.pin_message(request).await
+    }
+
+    async fn unpin_message(
+        &self,
+        request: Request<UnpinMessageRequest>,
+    ) -> Result<Response<UnpinMessageResponse>, Status> {
+        self.messenger.unpin_message(request).await
+    }
+
+    async fn get_pinned_messages(
+        &self,
+        request: Request<GetPinnedMessagesRequest>,
+    ) -> Result<Response<PinnedMessages>, Status> {
+        self.messenger.get_pinned_messages(request).await
+    }
+
     async fn create_chat(&self, request: Request<CreateChatRequest>) -> Result<Response<CreateChatResponse>, Status> {
         self.messenger.create_chat(request).await
     }
@@ -185,6 +543,41 @@ impl Messenger for MessengerAdapter {
         self.messenger.get_related_users(request).await
     }
 
+    async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        self.messenger.get_profile(request).await
+    }
+
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<Profile>, Status> {
+        self.messenger.update_profile(request).await
+    }
+
+    async fn block_user(
+        &self,
+        request: Request<BlockUserRequest>,
+    ) -> Result<Response<BlockUserResponse>, Status> {
+        self.messenger.block_user(request).await
+    }
+
+    async fn unblock_user(
+        &self,
+        request: Request<UnblockUserRequest>,
+    ) -> Result<Response<UnblockUserResponse>, Status> {
+        self.messenger.unblock_user(request).await
+    }
+
+    async fn list_blocked(
+        &self,
+        request: Request<ListBlockedRequest>,
+    ) -> Result<Response<Users>, Status> {
+        self.messenger.list_blocked(request).await
+    }
+
     async fn send_invite(
         &self,
         request: Request<SendInviteRequest>,
@@ -214,6 +607,129 @@ impl Messenger for MessengerAdapter {
     ) -> Result<Response<GetInvitesResponse>, Status> {
         self.messenger.get_invites(request).await
     }
+
+    async fn get_sent_invites(
+        &self,
+        request: Request<GetSentInvitesRequest>,
+    ) -> Result<Response<GetSentInvitesResponse>, Status> {
+        self.messenger.get_sent_invites(request).await
+    }
+
+    async fn revoke_invite(
+        &self,
+        request: Request<RevokeInviteRequest>,
+    ) -> Result<Response<RevokeInviteResponse>, Status> {
+        self.messenger.revoke_invite(request).await
+    }
+
+    async fn create_invite_link(
+        &self,
+        request: Request<CreateInviteLinkRequest>,
+    ) -> Result<Response<InviteLink>, Status> {
+        self.messenger.create_invite_link(request).await
+    }
+
+    async fn join_by_code(
+        &self,
+        request: Request<JoinByCodeRequest>,
+    ) -> Result<Response<JoinByCodeResponse>, Status> {
+        self.messenger.join_by_code(request).await
+    }
+
+    async fn edit_message(
+        &self,
+        request: Request<EditMessageRequest>,
+    ) -> Result<Response<MMessage>, Status> {
+        self.messenger.edit_message(request).await
+    }
+
+    async fn delete_message(
+        &self,
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<DeleteMessageResponse>, Status> {
+        self.messenger.delete_message(request).await
+    }
+
+    async fn get_mentions(
+        &self,
+        request: Request<GetMentionsRequest>,
+    ) -> Result<Response<Messages>, Status> {
+        self.messenger.get_mentions(request).await
+    }
+
+    async fn get_thread(
+        &self,
+        request: Request<GetThreadRequest>,
+    ) -> Result<Response<Thread>, Status> {
+        self.messenger.get_thread(request).await
+    }
+
+    async fn add_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        self.messenger.add_reaction(request).await
+    }
+
+    async fn remove_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        self.messenger.remove_reaction(request).await
+    }
+
+    async fn get_presence(
+        &self,
+        request: Request<GetPresenceRequest>,
+    ) -> Result<Response<Presences>, Status> {
+        self.messenger.get_presence(request).await
+    }
+
+    type PresenceUpdatesStream = PresenceResponseStream;
+
+    async fn presence_updates(
+        &self,
+        request: Request<PresenceUpdatesRequest>,
+    ) -> Result<Response<Self::PresenceUpdatesStream>, Status> {
+        self.messenger.presence_updates(request).await
+    }
+
+    async fn upload_attachment(
+        &self,
+        request: Request<Streaming<UploadAttachmentRequest>>,
+    ) -> Result<Response<Attachment>, Status> {
+        self.messenger.upload_attachment(request).await
+    }
+
+    type DownloadAttachmentStream = AttachmentResponseStream;
+
+    async fn download_attachment(
+        &self,
+        request: Request<DownloadAttachmentRequest>,
+    ) -> Result<Response<Self::DownloadAttachmentStream>, Status> {
+        self.messenger.download_attachment(request).await
+    }
+
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        self.messenger.create_api_key(request).await
+    }
+
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ApiKeys>, Status> {
+        self.messenger.list_api_keys(request).await
+    }
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        self.messenger.revoke_api_key(request).await
+    }
 }
 
 module! {
@@ -236,6 +752,18 @@ module! {
             components = [dyn InviteManager],
             providers = [],
         },
+        use PresenceManagerModule {
+            components = [dyn PresenceManager],
+            providers = [],
+        },
+        use AttachmentManagerModule {
+            components = [dyn AttachmentManager<DownloadAttachmentStream = AttachmentResponseStream>],
+            providers = [],
+        },
+        use ApiKeyManagerModule {
+            components = [dyn ApiKeyManager],
+            providers = [],
+        },
     }
 }
 
@@ -246,6 +774,9 @@ pub fn build_crab_messenger_module() -> Arc<CrabMessengerModule> {
             build_user_manager_module(),
             build_chat_manager_module(),
             build_invite_manager_module(),
+            build_presence_manager_module(),
+            build_attachment_manager_module(),
+            build_api_key_manager_module(),
         )
         .build(),
     )
@@ -0,0 +1,402 @@
+use std::sync::Arc;
+
+use amqprs::channel::BasicPublishArguments;
+use amqprs::BasicProperties;
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use shaku::{module, Component, Interface};
+use tonic::{Request, Response, Status};
+use tracing::{error, info, warn};
+
+use crate::server::authenticated_user::AuthenticatedRequest;
+use crate::utils::db_connection_manager::{
+    build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
+};
+use crate::utils::generate_random_string;
+use crate::utils::messenger::{
+    ApiKey as ProtoApiKey, ApiKeys, ChatRole, CreateApiKeyRequest, CreateApiKeyResponse,
+    ListApiKeysRequest, RevokeApiKeyRequest, RevokeApiKeyResponse,
+};
+use crate::utils::persistence::api_key::{
+    generate_api_key, hash_api_key, ApiKey, InsertApiKey, API_KEY_SCOPES,
+};
+use crate::utils::persistence::profile::{Profile, VISIBILITY_NOBODY};
+use crate::utils::persistence::schema::{api_keys, profiles, users, users_chats};
+use crate::utils::persistence::user::User;
+use crate::utils::persistence::users_chats::UsersChats;
+use crate::utils::rabbit_channel_manager::{
+    build_channel_manager_module, ChannelManager, ChannelManagerModule,
+};
+use crate::utils::rabbit_declares::{chat_connect_exchange_name, declare_chat_connect_exchange};
+use crate::utils::rabbit_types::RabbitChatConnect;
+
+#[async_trait]
+pub trait ApiKeyManager: Interface {
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status>;
+
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ApiKeys>, Status>;
+
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status>;
+}
+
+/// Longest bot name accepted, in chars.
+const MAX_API_KEY_NAME_LENGTH: usize = 64;
+/// Most keys a user can have that aren't revoked.
+const MAX_ACTIVE_API_KEYS: i64 = 20;
+/// Most chats a single bot can be added to.
+const MAX_API_KEY_CHATS: usize = 50;
+const BOT_ID_LENGTH: usize = 24;
+
+#[derive(Component)]
+#[shaku(interface = ApiKeyManager)]
+pub struct ApiKeyManagerImpl {
+    #[shaku(inject)]
+    db_connection_manager: Arc<dyn DBConnectionManager>,
+
+    #[shaku(inject)]
+    channel_manager: Arc<dyn ChannelManager>,
+}
+
+impl ApiKeyManagerImpl {
+    async fn validate_scopes(&self, scopes: &[String]) -> Result<Vec<String>, Status> {
+        if scopes.is_empty() {
+            return Err(Status::invalid_argument(
+                "An api key needs at least one scope",
+            ));
+        }
+        if let Some(scope) = scopes
+            .iter()
+            .find(|scope| !API_KEY_SCOPES.contains(&scope.as_str()))
+        {
+            return Err(Status::invalid_argument(format!(
+                "Unknown scope: {}, expected one of {}",
+                scope,
+                API_KEY_SCOPES.join(", ")
+            )));
+        }
+
+        let mut scopes = scopes.to_vec();
+        scopes.sort();
+        scopes.dedup();
+        Ok(scopes)
+    }
+
+    /// Bots are added to the chats by their owner, who needs to be allowed to add members.
+    async fn check_admin_of(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_ids: &[i32],
+    ) -> Result<(), Status> {
+        for chat_id in chat_ids {
+            let membership = users_chats::table
+                .filter(users_chats::user_id.eq(user_id))
+                .filter(users_chats::chat_id.eq(chat_id))
+                .first::<UsersChats>(connection)
+                .optional()
+                .map_err(|e| {
+                    error!("Failed to get binding: {}", e);
+                    Status::internal("Failed to get binding")
+                })?;
+
+            if !membership.is_some_and(|membership| membership.is_admin()) {
+                return Err(Status::permission_denied(format!(
+                    "Only admins of chat {} can add a bot to it",
+                    chat_id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Detaches the bot's live streams from the chats it was removed from.
+    async fn publish_disconnects(
+        &self,
+        bot_user_id: &str,
+        chat_ids: &[i32],
+    ) -> Result<(), anyhow::Error> {
+        let channel = self.channel_manager.get_channel().await?;
+        declare_chat_connect_exchange(&channel, bot_user_id).await?;
+
+        for chat_id in chat_ids {
+            channel
+                .basic_publish(
+                    BasicProperties::default(),
+                    serde_json::to_string(&RabbitChatConnect::Disconnect(*chat_id))?.into_bytes(),
+                    BasicPublishArguments::new(&chat_connect_exchange_name(bot_user_id), "")
+                        .mandatory(false)
+                        .immediate(false)
+                        .finish(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ApiKeyManager for ApiKeyManagerImpl {
+    #[tracing::instrument(skip(self, request), err)]
+    async fn create_api_key(
+        &self,
+        request: Request<CreateApiKeyRequest>,
+    ) -> Result<Response<CreateApiKeyResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let create_request = request.into_inner();
+
+        let name = create_request.name.trim().to_string();
+        if name.is_empty() {
+            return Err(Status::invalid_argument("Api key name can't be empty"));
+        }
+        if name.chars().count() > MAX_API_KEY_NAME_LENGTH {
+            return Err(Status::invalid_argument(format!(
+                "Api key name can't be longer than {} characters",
+                MAX_API_KEY_NAME_LENGTH
+            )));
+        }
+
+        let scopes = self.validate_scopes(&create_request.scopes).await?;
+
+        let mut chat_ids = create_request.chat_ids;
+        chat_ids.sort();
+        chat_ids.dedup();
+        if chat_ids.is_empty() {
+            return Err(Status::invalid_argument(
+                "An api key needs at least one chat",
+            ));
+        }
+        if chat_ids.len() > MAX_API_KEY_CHATS {
+            return Err(Status::invalid_argument(format!(
+                "A bot can't be added to more than {} chats",
+                MAX_API_KEY_CHATS
+            )));
+        }
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        self.check_admin_of(&mut connection, &user_id, &chat_ids)
+            .await?;
+
+        let active_keys = api_keys::table
+            .filter(api_keys::owner_user_id.eq(&user_id))
+            .filter(api_keys::revoked_at.is_null())
+            .count()
+            .get_result::<i64>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to count api keys: {}", e);
+                Status::internal("Failed to count api keys")
+            })?;
+        if active_keys >= MAX_ACTIVE_API_KEYS {
+            return Err(Status::resource_exhausted(format!(
+                "You can't have more than {} active api keys",
+                MAX_ACTIVE_API_KEYS
+            )));
+        }
+
+        let (key, key_prefix) = generate_api_key();
+        let bot_id = format!("bot|{}", generate_random_string(BOT_ID_LENGTH));
+
+        // Bots have no mailbox, the address only fills the column and is never shown.
+        let bot = User {
+            id: bot_id.clone(),
+            email: format!("{}@bots.crab-messenger", &bot_id[4..]),
+        };
+        let mut bot_profile = Profile::new(bot_id.clone());
+        bot_profile.display_name = Some(name.clone());
+        bot_profile.email_visibility = VISIBILITY_NOBODY.to_string();
+
+        let insert_api_key = InsertApiKey {
+            owner_user_id: user_id,
+            bot_user_id: bot_id.clone(),
+            name,
+            key_prefix,
+            secret_hash: hash_api_key(&key),
+            scopes,
+        };
+
+        let api_key = connection
+            .transaction(|connection| {
+                diesel::insert_into(users::table)
+                    .values(&bot)
+                    .execute(connection)?;
+                diesel::insert_into(profiles::table)
+                    .values(&bot_profile)
+                    .execute(connection)?;
+                diesel::insert_into(users_chats::table)
+                    .values(
+                        chat_ids
+                            .iter()
+                            .map(|chat_id| {
+                                UsersChats::new(bot_id.clone(), *chat_id, ChatRole::Member)
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .execute(connection)?;
+                diesel::insert_into(api_keys::table)
+                    .values(&insert_api_key)
+                    .get_result::<ApiKey>(connection)
+            })
+            .map_err(|e| {
+                error!("Failed to create api key: {}", e);
+                Status::internal("Failed to create api key")
+            })?;
+
+        info!("Created api key {} for bot {}", api_key.id, bot_id);
+        Ok(Response::new(CreateApiKeyResponse {
+            api_key: Some(ProtoApiKey {
+                chat_ids,
+                ..api_key.into()
+            }),
+            key,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn list_api_keys(
+        &self,
+        request: Request<ListApiKeysRequest>,
+    ) -> Result<Response<ApiKeys>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let owned_keys = api_keys::table
+            .filter(api_keys::owner_user_id.eq(&user_id))
+            .order(api_keys::created_at.desc())
+            .load::<ApiKey>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get api keys: {}", e);
+                Status::internal("Failed to get api keys")
+            })?;
+
+        let bot_chats = users_chats::table
+            .filter(users_chats::user_id.eq_any(owned_keys.iter().map(|key| &key.bot_user_id)))
+            .order(users_chats::chat_id.asc())
+            .select((users_chats::user_id, users_chats::chat_id))
+            .load::<(String, i32)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get bot chats: {}", e);
+                Status::internal("Failed to get bot chats")
+            })?;
+
+        Ok(Response::new(ApiKeys {
+            api_keys: owned_keys
+                .into_iter()
+                .map(|api_key| ProtoApiKey {
+                    chat_ids: bot_chats
+                        .iter()
+                        .filter(|(bot_user_id, _)| *bot_user_id == api_key.bot_user_id)
+                        .map(|(_, chat_id)| *chat_id)
+                        .collect(),
+                    ..api_key.into()
+                })
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn revoke_api_key(
+        &self,
+        request: Request<RevokeApiKeyRequest>,
+    ) -> Result<Response<RevokeApiKeyResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let api_key_id = request.into_inner().api_key_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let api_key = api_keys::table
+            .filter(api_keys::id.eq(api_key_id))
+            .filter(api_keys::owner_user_id.eq(&user_id))
+            .first::<ApiKey>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get api key: {}", e);
+                Status::internal("Failed to get api key")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!("No api key found with id: {}", api_key_id))
+            })?;
+
+        // Revoking twice is fine, the first revocation time is kept. The bot leaves its
+        // chats along with the revocation.
+        if api_key.revoked_at.is_none() {
+            let chat_ids = connection
+                .transaction(|connection| {
+                    diesel::update(api_keys::table)
+                        .filter(api_keys::id.eq(api_key_id))
+                        .set(api_keys::revoked_at.eq(chrono::Utc::now().naive_utc()))
+                        .execute(connection)?;
+                    let chat_ids = users_chats::table
+                        .filter(users_chats::user_id.eq(&api_key.bot_user_id))
+                        .select(users_chats::chat_id)
+                        .load::<i32>(connection)?;
+                    diesel::delete(users_chats::table)
+                        .filter(users_chats::user_id.eq(&api_key.bot_user_id))
+                        .execute(connection)?;
+                    Ok::<_, diesel::result::Error>(chat_ids)
+                })
+                .map_err(|e| {
+                    error!("Failed to revoke api key: {}", e);
+                    Status::internal("Failed to revoke api key")
+                })?;
+            info!("Revoked api key {}", api_key_id);
+
+            // The key is revoked either way, a bot stream that misses this is cut off
+            // when it reconnects.
+            if let Err(e) = self
+                .publish_disconnects(&api_key.bot_user_id, &chat_ids)
+                .await
+            {
+                warn!("Failed to disconnect bot {}: {}", api_key.bot_user_id, e);
+            }
+        }
+
+        Ok(Response::new(RevokeApiKeyResponse { success: true }))
+    }
+}
+
+module! {
+    pub ApiKeyManagerModule {
+        components = [ApiKeyManagerImpl],
+        providers = [],
+        use DBConnectionManagerModule {
+            components = [dyn DBConnectionManager],
+            providers = [],
+        },
+        use ChannelManagerModule {
+            components = [dyn ChannelManager],
+            providers = [],
+        },
+    }
+}
+
+pub fn build_api_key_manager_module() -> Arc<ApiKeyManagerModule> {
+    Arc::new(
+        ApiKeyManagerModule::builder(
+            build_db_connection_manager_module(),
+            build_channel_manager_module(),
+        )
+        .build(),
+    )
+}
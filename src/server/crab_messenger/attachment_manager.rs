@@ -0,0 +1,367 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use shaku::{module, Component, Interface};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{debug, error, warn};
+
+use crate::server::authenticated_user::AuthenticatedRequest;
+use crate::server::crab_messenger::AttachmentResponseStream;
+use crate::utils::blob_store::{build_blob_store_module, BlobStore, BlobStoreModule};
+use crate::utils::db_connection_manager::{
+    build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
+};
+use crate::utils::generate_random_string;
+use crate::utils::messenger::upload_attachment_request::Payload;
+use crate::utils::messenger::{
+    Attachment as GAttachment, AttachmentChunk, DownloadAttachmentRequest, UploadAttachmentRequest,
+};
+use crate::utils::persistence::attachment::{Attachment, InsertAttachment};
+use crate::utils::persistence::schema::{attachments, messages, users_chats};
+use crate::utils::persistence::users_chats::UsersChats;
+
+/// Largest attachment accepted, in bytes.
+const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+/// Longest file name (in chars) an attachment can have.
+const MAX_FILE_NAME_LENGTH: usize = 255;
+/// Size of the chunks attachments are downloaded in.
+const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+const STORAGE_KEY_LENGTH: usize = 32;
+
+#[async_trait]
+pub trait AttachmentManager: Interface {
+    type DownloadAttachmentStream;
+    async fn upload_attachment(
+        &self,
+        request: Request<Streaming<UploadAttachmentRequest>>,
+    ) -> Result<Response<GAttachment>, Status>;
+    async fn download_attachment(
+        &self,
+        request: Request<DownloadAttachmentRequest>,
+    ) -> Result<Response<Self::DownloadAttachmentStream>, Status>;
+}
+
+#[derive(Component)]
+#[shaku(interface = AttachmentManager<DownloadAttachmentStream = AttachmentResponseStream>)]
+pub struct AttachmentManagerImpl {
+    #[shaku(inject)]
+    db_connection_manager: Arc<dyn DBConnectionManager>,
+    #[shaku(inject)]
+    blob_store: Arc<dyn BlobStore>,
+}
+
+impl AttachmentManagerImpl {
+    async fn check_membership(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_id: i32,
+    ) -> Result<(), Status> {
+        let binding = users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq(chat_id))
+            .first::<UsersChats>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get binding: {}", e);
+                Status::internal("Failed to get binding")
+            })?;
+
+        match binding {
+            Some(_) => Ok(()),
+            None => Err(Status::permission_denied(
+                "You are not a member of this chat",
+            )),
+        }
+    }
+
+    async fn receive_content(
+        &self,
+        stream: &mut Streaming<UploadAttachmentRequest>,
+    ) -> Result<Vec<u8>, Status> {
+        let mut data = Vec::new();
+
+        while let Some(request) = stream.message().await? {
+            match request.payload {
+                Some(Payload::Chunk(chunk)) => {
+                    if data.len() + chunk.len() > MAX_ATTACHMENT_SIZE {
+                        return Err(Status::invalid_argument(format!(
+                            "Attachments can't be larger than {} bytes",
+                            MAX_ATTACHMENT_SIZE
+                        )));
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                _ => {
+                    return Err(Status::invalid_argument(
+                        "Only content chunks can follow the attachment info",
+                    ));
+                }
+            }
+        }
+
+        if data.is_empty() {
+            return Err(Status::invalid_argument("Attachment can't be empty"));
+        }
+
+        Ok(data)
+    }
+
+    /// Keeps the last path component only, the name is shown to the other members
+    /// and used when they save the file.
+    async fn validate_file_name(&self, file_name: &str) -> Result<String, Status> {
+        let file_name = file_name
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .trim();
+
+        if file_name.is_empty() || file_name == "." || file_name == ".." {
+            return Err(Status::invalid_argument(
+                "Attachment file name can't be empty",
+            ));
+        }
+        if file_name.chars().count() > MAX_FILE_NAME_LENGTH {
+            return Err(Status::invalid_argument(format!(
+                "Attachment file name can't be longer than {} characters",
+                MAX_FILE_NAME_LENGTH
+            )));
+        }
+        if file_name.chars().any(char::is_control) {
+            return Err(Status::invalid_argument(
+                "Attachment file name can't contain control characters",
+            ));
+        }
+
+        Ok(file_name.to_string())
+    }
+
+    /// Unsent attachments are only visible to their uploader, sent ones to the chat
+    /// members as long as the message isn't deleted.
+    async fn check_downloadable(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        attachment: &Attachment,
+    ) -> Result<(), Status> {
+        let not_found =
+            || Status::not_found(format!("No attachment found with id: {}", attachment.id));
+
+        let message_id = match attachment.message_id {
+            Some(message_id) => message_id,
+            None if attachment.uploader_user_id == user_id => return Ok(()),
+            None => return Err(not_found()),
+        };
+
+        self.check_membership(connection, user_id, attachment.chat_id)
+            .await?;
+
+        let deleted_at = messages::table
+            .filter(messages::id.eq(message_id))
+            .select(messages::deleted_at)
+            .first::<Option<chrono::NaiveDateTime>>(connection)
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?;
+
+        match deleted_at {
+            Some(_) => Err(not_found()),
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl AttachmentManager for AttachmentManagerImpl {
+    type DownloadAttachmentStream = AttachmentResponseStream;
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn upload_attachment(
+        &self,
+        request: Request<Streaming<UploadAttachmentRequest>>,
+    ) -> Result<Response<GAttachment>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let mut stream = request.into_inner();
+
+        let info = match stream.message().await? {
+            Some(UploadAttachmentRequest {
+                payload: Some(Payload::Info(info)),
+            }) => info,
+            _ => {
+                return Err(Status::invalid_argument(
+                    "The first request must carry the attachment info",
+                ));
+            }
+        };
+        let file_name = self.validate_file_name(&info.file_name).await?;
+
+        // The connection goes back to the pool while the content is streamed in.
+        {
+            let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+                error!("Failed to get DB connection: {}", e);
+                Status::internal("Failed to get DB connection")
+            })?;
+            self.check_membership(&mut connection, &user_id, info.chat_id)
+                .await?;
+        }
+
+        let data = self.receive_content(&mut stream).await?;
+        let content_type = sniff_content_type(&data);
+        debug!(
+            "Received {} bytes of {} for chat {}",
+            data.len(),
+            content_type,
+            info.chat_id
+        );
+
+        let storage_key = generate_random_string(STORAGE_KEY_LENGTH);
+        self.blob_store
+            .put(&storage_key, &data)
+            .await
+            .map_err(|e| {
+                error!("Failed to store attachment: {}", e);
+                Status::internal("Failed to store attachment")
+            })?;
+
+        let insert_attachment = InsertAttachment {
+            uploader_user_id: user_id,
+            chat_id: info.chat_id,
+            file_name,
+            content_type: content_type.to_string(),
+            size: data.len() as i64,
+            storage_key: storage_key.clone(),
+        };
+
+        let attachment = self
+            .db_connection_manager
+            .get_connection()
+            .map_err(anyhow::Error::new)
+            .and_then(|mut connection| {
+                diesel::insert_into(attachments::table)
+                    .values(&insert_attachment)
+                    .get_result::<Attachment>(&mut connection)
+                    .map_err(anyhow::Error::new)
+            });
+
+        match attachment {
+            Ok(attachment) => Ok(Response::new(attachment.into())),
+            Err(e) => {
+                error!("Failed to save attachment: {}", e);
+                if let Err(e) = self.blob_store.delete(&storage_key).await {
+                    warn!("Failed to delete orphaned blob {}: {}", storage_key, e);
+                }
+                Err(Status::internal("Failed to save attachment"))
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn download_attachment(
+        &self,
+        request: Request<DownloadAttachmentRequest>,
+    ) -> Result<Response<Self::DownloadAttachmentStream>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let attachment_id = request.into_inner().attachment_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let attachment = attachments::table
+            .filter(attachments::id.eq(attachment_id))
+            .first::<Attachment>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get attachment: {}", e);
+                Status::internal("Failed to get attachment")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!("No attachment found with id: {}", attachment_id))
+            })?;
+
+        self.check_downloadable(&mut connection, &user_id, &attachment)
+            .await?;
+
+        let data = self
+            .blob_store
+            .get(&attachment.storage_key)
+            .await
+            .map_err(|e| {
+                error!("Failed to read attachment: {}", e);
+                Status::internal("Failed to read attachment")
+            })?;
+
+        let chunks: Vec<_> = data
+            .chunks(ATTACHMENT_CHUNK_SIZE)
+            .map(|chunk| AttachmentChunk {
+                data: chunk.to_vec(),
+            })
+            .collect();
+
+        debug!(
+            "Sending attachment {} in {} chunks",
+            attachment.id,
+            chunks.len()
+        );
+        Ok(Response::new(Box::pin(tokio_stream::iter(
+            chunks.into_iter().map(Ok),
+        ))))
+    }
+}
+
+/// Tells the content type from the first bytes of the file, whatever its name says.
+fn sniff_content_type(data: &[u8]) -> &'static str {
+    const SIGNATURES: [(&[u8], &str); 7] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+
+    if let Some((_, content_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return content_type;
+    }
+
+    if data.starts_with(b"RIFF") && data.get(8..12) == Some(&b"WEBP"[..]) {
+        "image/webp"
+    } else if std::str::from_utf8(data).is_ok() {
+        "text/plain; charset=utf-8"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+module! {
+    pub AttachmentManagerModule {
+        components = [AttachmentManagerImpl],
+        providers = [],
+        use DBConnectionManagerModule {
+            components = [dyn DBConnectionManager],
+            providers = [],
+        },
+        use BlobStoreModule {
+            components = [dyn BlobStore],
+            providers = [],
+        },
+    }
+}
+
+pub fn build_attachment_manager_module() -> Arc<AttachmentManagerModule> {
+    Arc::new(
+        AttachmentManagerModule::builder(
+            build_db_connection_manager_module(),
+            build_blob_store_module(),
+        )
+        .build(),
+    )
+}
@@ -1,25 +1,49 @@
+use crate::server::authenticated_user::AuthenticatedRequest;
+use crate::utils::blob_store::{build_blob_store_module, BlobStore, BlobStoreModule};
 use crate::utils::db_connection_manager::{
     build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
 };
 use crate::utils::messenger::{
-    Chat as GChat, Chats, CreateChatRequest, CreateChatResponse, GetUserChatsRequest,
+    Chat as GChat, ChatMember, ChatMembers, ChatRole, Chats, CreateChatRequest, CreateChatResponse,
+    DeleteChatRequest, DeleteChatResponse, GetChatMembersRequest, GetPinnedMessagesRequest,
+    GetUserChatsRequest, LeaveChatRequest, LeaveChatResponse, MarkReadRequest, MarkReadResponse,
+    Message as GMessage, MuteChatRequest, MuteChatResponse, OpenDirectChatRequest,
+    OpenDirectChatResponse, PinMessageRequest, PinnedMessage as GPinnedMessage, PinnedMessages,
+    RemoveMemberRequest, RemoveMemberResponse, UnpinMessageRequest, UnpinMessageResponse,
+    UpdateChatRequest, UpdateChatResponse,
 };
-use crate::utils::persistence::chat::{Chat, InsertChat};
-use crate::utils::persistence::schema::{chats, users_chats};
-use crate::utils::persistence::users_chats::UsersChats;
+use crate::utils::persistence::chat::{Chat, InsertChat, UpdateChat};
+use crate::utils::persistence::message::{to_timestamp, Message};
+use crate::utils::persistence::pinned_message::{
+    to_proto_pinned_message, InsertPinnedMessage, PinnedMessage,
+};
+use crate::utils::persistence::profile::{user_for_viewer, Profile};
+use crate::utils::persistence::schema::{
+    attachments, chat_invite_links, chats, invites, mentions, message_reactions, messages,
+    pinned_messages, profiles, users, users_chats,
+};
+use crate::utils::persistence::user::User;
+use crate::utils::persistence::user_block::{blocked_by, is_blocked_between};
+use crate::utils::persistence::users_chats::{parse_role, UsersChats, ROLE_ADMIN, ROLE_OWNER};
 use crate::utils::rabbit_channel_manager::{
     build_channel_manager_module, ChannelManager, ChannelManagerModule,
 };
-use crate::utils::rabbit_declares::{CHAT_CONNECT_EXCHANGE, chat_connect_exchange_name};
-use amqprs::channel::BasicPublishArguments;
+use crate::utils::rabbit_declares::{
+    chat_connect_exchange_name, declare_chat_connect_exchange, declare_messages_exchange,
+    delete_messages_exchange, messages_exchange_name,
+};
+use crate::utils::rabbit_types::{RabbitChatConnect, RabbitChatEvent};
+use amqprs::channel::{BasicPublishArguments, Channel};
 use amqprs::BasicProperties;
 use async_trait::async_trait;
 use diesel::prelude::*;
+use diesel::PgConnection;
 use diesel::QueryDsl;
 use shaku::{module, Component, Interface};
+use std::collections::HashMap;
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
-use tracing::{debug, error, instrument};
+use tracing::{debug, error, instrument, warn};
 
 #[async_trait]
 pub trait ChatManager: Interface {
@@ -32,8 +56,70 @@ pub trait ChatManager: Interface {
         &self,
         request: Request<CreateChatRequest>,
     ) -> Result<Response<CreateChatResponse>, Status>;
+
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadResponse>, Status>;
+
+    async fn open_direct_chat(
+        &self,
+        request: Request<OpenDirectChatRequest>,
+    ) -> Result<Response<OpenDirectChatResponse>, Status>;
+
+    async fn leave_chat(
+        &self,
+        request: Request<LeaveChatRequest>,
+    ) -> Result<Response<LeaveChatResponse>, Status>;
+
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberResponse>, Status>;
+
+    async fn update_chat(
+        &self,
+        request: Request<UpdateChatRequest>,
+    ) -> Result<Response<UpdateChatResponse>, Status>;
+
+    async fn get_chat_members(
+        &self,
+        request: Request<GetChatMembersRequest>,
+    ) -> Result<Response<ChatMembers>, Status>;
+
+    async fn delete_chat(
+        &self,
+        request: Request<DeleteChatRequest>,
+    ) -> Result<Response<DeleteChatResponse>, Status>;
+
+    async fn mute_chat(
+        &self,
+        request: Request<MuteChatRequest>,
+    ) -> Result<Response<MuteChatResponse>, Status>;
+
+    async fn pin_message(
+        &self,
+        request: Request<PinMessageRequest>,
+    ) -> Result<Response<GPinnedMessage>, Status>;
+
+    async fn unpin_message(
+        &self,
+        request: Request<UnpinMessageRequest>,
+    ) -> Result<Response<UnpinMessageResponse>, Status>;
+
+    async fn get_pinned_messages(
+        &self,
+        request: Request<GetPinnedMessagesRequest>,
+    ) -> Result<Response<PinnedMessages>, Status>;
 }
 
+/// Longest chat name accepted, in chars.
+const MAX_CHAT_NAME_LENGTH: usize = 64;
+/// Longest chat description accepted, in chars.
+const MAX_CHAT_DESCRIPTION_LENGTH: usize = 512;
+/// Most messages a chat can have pinned at once.
+const MAX_PINNED_MESSAGES: i64 = 50;
+
 #[derive(Component)]
 #[shaku(interface = ChatManager)]
 pub struct ChatManagerImpl {
@@ -42,6 +128,347 @@ pub struct ChatManagerImpl {
 
     #[shaku(inject)]
     channel_manager: Arc<dyn ChannelManager>,
+
+    #[shaku(inject)]
+    blob_store: Arc<dyn BlobStore>,
+}
+
+impl ChatManagerImpl {
+    /// Unread messages of the user in each of the chats, chats without any are left out.
+    async fn count_unread(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_ids: &[i32],
+    ) -> Result<HashMap<i32, i32>, Status> {
+        let unread_counts = messages::table
+            .inner_join(users_chats::table.on(users_chats::chat_id.eq(messages::chat_id)))
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq_any(chat_ids))
+            .filter(
+                users_chats::last_read_message_id.is_null().or(messages::id
+                    .nullable()
+                    .gt(users_chats::last_read_message_id)),
+            )
+            .filter(messages::user_id.ne(user_id))
+            .filter(messages::deleted_at.is_null())
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(user_id)),
+            ))
+            .group_by(messages::chat_id)
+            .select((messages::chat_id, diesel::dsl::count_star()))
+            .load::<(i32, i64)>(connection)
+            .map_err(|e| {
+                error!("Failed to count unread messages: {}", e);
+                Status::internal("Failed to count unread messages")
+            })?;
+
+        Ok(unread_counts
+            .into_iter()
+            .map(|(chat_id, unread_count)| (chat_id, unread_count as i32))
+            .collect())
+    }
+
+    /// Last message of each of the chats, chats without any are left out. Messages of users
+    /// the caller blocked don't make it into the preview.
+    async fn get_last_messages(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_ids: &[i32],
+    ) -> Result<HashMap<i32, GMessage>, Status> {
+        let last_messages = messages::table
+            .filter(messages::chat_id.eq_any(chat_ids))
+            .filter(messages::deleted_at.is_null())
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(user_id)),
+            ))
+            .distinct_on(messages::chat_id)
+            .order((
+                messages::chat_id,
+                messages::created_at.desc(),
+                messages::id.desc(),
+            ))
+            .load::<Message>(connection)
+            .map_err(|e| {
+                error!("Failed to get last messages: {}", e);
+                Status::internal("Failed to get last messages")
+            })?;
+
+        Ok(last_messages
+            .into_iter()
+            .map(|message| (message.chat_id, message.into()))
+            .collect())
+    }
+
+    /// Names of the chats by id, direct chats are shown under the name of the other
+    /// participant.
+    async fn display_names(
+        &self,
+        connection: &mut PgConnection,
+        chats: &[&Chat],
+        user_id: &str,
+    ) -> Result<HashMap<i32, String>, Status> {
+        let peer_ids: Vec<&str> = chats
+            .iter()
+            .filter_map(|chat| chat.direct_peer(user_id))
+            .collect();
+
+        let peers = users::table
+            .left_join(profiles::table)
+            .filter(users::id.eq_any(&peer_ids))
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .load::<(User, Option<Profile>)>(connection)
+            .map_err(|e| {
+                error!("Failed to get users: {}", e);
+                Status::internal("Failed to get users")
+            })?;
+
+        // Both participants share the chat, so the peer's email shows unless they hide it.
+        let mut peer_names: HashMap<String, String> = peers
+            .into_iter()
+            .map(|(peer, profile)| user_for_viewer(peer, profile, user_id, true))
+            .filter_map(|peer| {
+                let name = [peer.display_name, peer.email]
+                    .into_iter()
+                    .find(|name| !name.is_empty())?;
+                Some((peer.id, name))
+            })
+            .collect();
+
+        Ok(chats
+            .iter()
+            .map(|chat| {
+                let name = match chat.direct_peer(user_id) {
+                    Some(peer_id) => peer_names
+                        .remove(peer_id)
+                        .unwrap_or_else(|| peer_id.to_string()),
+                    None => chat.name.clone(),
+                };
+                (chat.id, name)
+            })
+            .collect())
+    }
+
+    async fn get_membership(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_id: i32,
+    ) -> Result<Option<UsersChats>, Status> {
+        users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq(chat_id))
+            .first::<UsersChats>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get binding: {}", e);
+                Status::internal("Failed to get binding")
+            })
+    }
+
+    /// Deletes the membership and hands the ownership over to the longest standing admin,
+    /// or member if there is none, when the owner goes.
+    async fn delete_membership(
+        &self,
+        connection: &mut PgConnection,
+        membership: &UsersChats,
+    ) -> Result<(), Status> {
+        connection
+            .transaction(|connection| {
+                diesel::delete(users_chats::table)
+                    .filter(users_chats::user_id.eq(&membership.user_id))
+                    .filter(users_chats::chat_id.eq(membership.chat_id))
+                    .execute(connection)?;
+
+                if membership.role() != ChatRole::Owner {
+                    return Ok(());
+                }
+
+                let successor = users_chats::table
+                    .filter(users_chats::chat_id.eq(membership.chat_id))
+                    .order((
+                        users_chats::role.eq(ROLE_ADMIN).desc(),
+                        users_chats::joined_at.asc(),
+                    ))
+                    .select(users_chats::user_id)
+                    .first::<String>(connection)
+                    .optional()?;
+
+                if let Some(successor) = successor {
+                    debug!("User {} now owns chat {}", successor, membership.chat_id);
+                    diesel::update(users_chats::table)
+                        .filter(users_chats::user_id.eq(successor))
+                        .filter(users_chats::chat_id.eq(membership.chat_id))
+                        .set(users_chats::role.eq(ROLE_OWNER))
+                        .execute(connection)?;
+                }
+
+                Ok::<_, diesel::result::Error>(())
+            })
+            .map_err(|e| {
+                error!("Failed to remove member: {}", e);
+                Status::internal("Failed to remove member")
+            })
+    }
+
+    /// Tells the chat about the removal, then detaches the removed user's live streams
+    /// from the chat exchange.
+    async fn publish_removal(
+        &self,
+        chat_id: i32,
+        user_id: &str,
+        removed_by: &str,
+    ) -> Result<(), anyhow::Error> {
+        let channel = self.channel_manager.get_channel().await?;
+
+        self.publish_chat_event(
+            &channel,
+            chat_id,
+            &RabbitChatEvent::MemberRemoved {
+                chat_id,
+                user_id: user_id.to_string(),
+                removed_by: removed_by.to_string(),
+            },
+        )
+        .await?;
+
+        self.publish_chat_connect(&channel, user_id, &RabbitChatConnect::Disconnect(chat_id))
+            .await
+    }
+
+    async fn publish_chat_event(
+        &self,
+        channel: &Channel,
+        chat_id: i32,
+        event: &RabbitChatEvent,
+    ) -> Result<(), anyhow::Error> {
+        declare_messages_exchange(channel, &chat_id.to_string()).await?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serde_json::to_string(event)?.into_bytes(),
+                BasicPublishArguments::new(&messages_exchange_name(&chat_id.to_string()), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Loads a message for pinning or unpinning, which only the chat's admins can do.
+    async fn get_pinnable_message(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        message_id: i32,
+    ) -> Result<Message, Status> {
+        let message = messages::table
+            .filter(messages::id.eq(message_id))
+            .first::<Message>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!("No message found with id: {}", message_id))
+            })?;
+
+        let actor = self
+            .get_membership(connection, user_id, message.chat_id)
+            .await?
+            .ok_or_else(|| Status::permission_denied("You are not a member of this chat"))?;
+
+        if !actor.is_admin() {
+            return Err(Status::permission_denied(
+                "Only admins can pin and unpin messages",
+            ));
+        }
+
+        Ok(message)
+    }
+
+    /// Deletes the chat with everything that belongs to it.
+    async fn delete_chat_cascade(
+        &self,
+        connection: &mut PgConnection,
+        chat_id: i32,
+    ) -> Result<(), Status> {
+        let storage_keys = connection
+            .transaction(|connection| {
+                let chat_messages = messages::table
+                    .filter(messages::chat_id.eq(chat_id))
+                    .select(messages::id);
+
+                diesel::delete(message_reactions::table)
+                    .filter(message_reactions::message_id.eq_any(chat_messages))
+                    .execute(connection)?;
+                diesel::delete(pinned_messages::table)
+                    .filter(pinned_messages::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                diesel::delete(mentions::table)
+                    .filter(mentions::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                let storage_keys = diesel::delete(attachments::table)
+                    .filter(attachments::chat_id.eq(chat_id))
+                    .returning(attachments::storage_key)
+                    .get_results::<String>(connection)?;
+                diesel::delete(users_chats::table)
+                    .filter(users_chats::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                diesel::delete(invites::table)
+                    .filter(invites::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                diesel::delete(chat_invite_links::table)
+                    .filter(chat_invite_links::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                diesel::delete(messages::table)
+                    .filter(messages::chat_id.eq(chat_id))
+                    .execute(connection)?;
+                diesel::delete(chats::table)
+                    .filter(chats::id.eq(chat_id))
+                    .execute(connection)?;
+
+                Ok::<_, diesel::result::Error>(storage_keys)
+            })
+            .map_err(|e| {
+                error!("Failed to delete chat: {}", e);
+                Status::internal("Failed to delete chat")
+            })?;
+
+        // The chat is gone already, a blob left behind only takes up space.
+        for storage_key in storage_keys {
+            if let Err(e) = self.blob_store.delete(&storage_key).await {
+                warn!("Failed to delete blob {}: {}", storage_key, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn publish_chat_connect(
+        &self,
+        channel: &Channel,
+        user_id: &str,
+        command: &RabbitChatConnect,
+    ) -> Result<(), anyhow::Error> {
+        declare_chat_connect_exchange(channel, user_id).await?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serde_json::to_string(command)?.into_bytes(),
+                BasicPublishArguments::new(&chat_connect_exchange_name(user_id), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -56,23 +483,48 @@ impl ChatManager for ChatManagerImpl {
             Status::internal("Failed to get DB connection")
         })?;
 
-        let metadata = request.metadata();
-        let user_id = metadata.get("user_id").unwrap().to_str().unwrap();
+        let user_id = request.authenticated_user()?.id.as_str();
         debug!("User_id: {:?}", user_id);
 
         let chats = users_chats::table
             .filter(users_chats::user_id.eq(user_id))
             .inner_join(chats::table.on(users_chats::chat_id.eq(chats::id)))
-            .select(chats::all_columns)
-            .load::<Chat>(&mut connection)
+            .select((chats::all_columns, users_chats::role, users_chats::muted))
+            .load::<(Chat, String, bool)>(&mut connection)
             .map_err(|e| {
                 error!("Failed to get chats_container: {}", e);
                 Status::internal("Failed to get chats_container")
             })?;
 
-        let chats: Vec<GChat> = chats.into_iter().map(|c| c.into()).collect();
+        let chat_ids: Vec<i32> = chats.iter().map(|(chat, _, _)| chat.id).collect();
+        let mut unread_counts = self
+            .count_unread(&mut connection, user_id, &chat_ids)
+            .await?;
+        let mut last_messages = self
+            .get_last_messages(&mut connection, user_id, &chat_ids)
+            .await?;
 
-        Ok(Response::new(Chats { chats }))
+        let mut names = self
+            .display_names(
+                &mut connection,
+                &chats.iter().map(|(chat, _, _)| chat).collect::<Vec<_>>(),
+                user_id,
+            )
+            .await?;
+
+        let proto_chats: Vec<GChat> = chats
+            .into_iter()
+            .map(|(chat, role, muted)| GChat {
+                name: names.remove(&chat.id).unwrap_or_default(),
+                unread_count: unread_counts.remove(&chat.id).unwrap_or(0),
+                last_message: last_messages.remove(&chat.id),
+                role: parse_role(&role) as i32,
+                muted,
+                ..chat.into()
+            })
+            .collect();
+
+        Ok(Response::new(Chats { chats: proto_chats }))
     }
 
     #[instrument(skip(self, request), err)]
@@ -85,17 +537,11 @@ impl ChatManager for ChatManagerImpl {
             Status::internal("Failed to get DB connection")
         })?;
 
-        let metadata = request.metadata();
-        let user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let user_id = request.authenticated_user()?.id.clone();
         debug!("User_id: {:?}", user_id);
         let chat_name = request.into_inner().name;
 
-        let insert_chat = InsertChat { name: chat_name };
+        let insert_chat = InsertChat::group(chat_name);
         let chat = diesel::insert_into(chats::table)
             .values(insert_chat)
             .get_result::<Chat>(&mut connection)
@@ -104,10 +550,7 @@ impl ChatManager for ChatManagerImpl {
                 Status::internal("Failed to create chat")
             })?;
 
-        let user_chat = UsersChats {
-            user_id: user_id.to_string(),
-            chat_id: chat.id,
-        };
+        let user_chat = UsersChats::new(user_id.to_string(), chat.id, ChatRole::Owner);
 
         diesel::insert_into(users_chats::table)
             .values(user_chat)
@@ -122,15 +565,7 @@ impl ChatManager for ChatManagerImpl {
             Status::internal("Failed to get channel")
         })?;
 
-        channel
-            .basic_publish(
-                BasicProperties::default(),
-                chat.id.to_string().into_bytes(),
-                BasicPublishArguments::new(&chat_connect_exchange_name(&user_id), "")
-                    .mandatory(false)
-                    .immediate(false)
-                    .finish(),
-            )
+        self.publish_chat_connect(&channel, &user_id, &RabbitChatConnect::Connect(chat.id))
             .await
             .map_err(|e| {
                 error!("Failed to publish message: {}", e);
@@ -138,7 +573,710 @@ impl ChatManager for ChatManagerImpl {
             })?;
 
         Ok(Response::new(CreateChatResponse {
-            chat: Some(chat.into()),
+            chat: Some(GChat {
+                role: ChatRole::Owner as i32,
+                ..chat.into()
+            }),
+        }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn mark_read(
+        &self,
+        request: Request<MarkReadRequest>,
+    ) -> Result<Response<MarkReadResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let mark_read_request = request.into_inner();
+
+        let message_chat_id = messages::table
+            .filter(messages::id.eq(mark_read_request.message_id))
+            .select(messages::chat_id)
+            .first::<i32>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?;
+
+        if message_chat_id != Some(mark_read_request.chat_id) {
+            return Err(Status::not_found(format!(
+                "No message {} found in chat {}",
+                mark_read_request.message_id, mark_read_request.chat_id
+            )));
+        }
+
+        // The marker only moves forward, so a late request can't mark read messages unread.
+        let updated = diesel::update(users_chats::table)
+            .filter(users_chats::user_id.eq(&user_id))
+            .filter(users_chats::chat_id.eq(mark_read_request.chat_id))
+            .filter(
+                users_chats::last_read_message_id
+                    .is_null()
+                    .or(users_chats::last_read_message_id.lt(mark_read_request.message_id)),
+            )
+            .set(users_chats::last_read_message_id.eq(mark_read_request.message_id))
+            .execute(&mut connection)
+            .map_err(|e| {
+                error!("Failed to update read marker: {}", e);
+                Status::internal("Failed to update read marker")
+            })?;
+
+        if updated == 0 {
+            let binding = users_chats::table
+                .filter(users_chats::user_id.eq(&user_id))
+                .filter(users_chats::chat_id.eq(mark_read_request.chat_id))
+                .first::<UsersChats>(&mut connection)
+                .optional()
+                .map_err(|e| {
+                    error!("Failed to get binding: {}", e);
+                    Status::internal("Failed to get binding")
+                })?;
+
+            if binding.is_none() {
+                return Err(Status::permission_denied(
+                    "You are not a member of this chat",
+                ));
+            }
+        }
+
+        debug!(
+            "User {} read chat {} up to message {}",
+            user_id, mark_read_request.chat_id, mark_read_request.message_id
+        );
+        Ok(Response::new(MarkReadResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn open_direct_chat(
+        &self,
+        request: Request<OpenDirectChatRequest>,
+    ) -> Result<Response<OpenDirectChatResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let peer_id = request.into_inner().user_id;
+
+        if peer_id == user_id {
+            return Err(Status::invalid_argument(
+                "Can't open a direct chat with yourself",
+            ));
+        }
+
+        let peer = users::table
+            .filter(users::id.eq(&peer_id))
+            .first::<User>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get user: {}", e);
+                Status::internal("Failed to get user")
+            })?;
+
+        if peer.is_none() {
+            return Err(Status::not_found(format!("No user {} found", peer_id)));
+        }
+
+        // Either side having blocked the other keeps the chat closed.
+        let blocked = is_blocked_between(&mut connection, &user_id, &peer_id).map_err(|e| {
+            error!("Failed to get blocks: {}", e);
+            Status::internal("Failed to get blocks")
+        })?;
+
+        if blocked {
+            return Err(Status::permission_denied(
+                "Can't open a direct chat with this user",
+            ));
+        }
+
+        // Both sides may open the chat at once, the unique key makes them end up in the same one.
+        let (chat, membership, participants) = connection
+            .transaction(|connection| {
+                let insert_chat = InsertChat::direct(&user_id, &peer_id);
+                let created = diesel::insert_into(chats::table)
+                    .values(&insert_chat)
+                    .on_conflict(chats::direct_key)
+                    .do_nothing()
+                    .execute(connection)?
+                    == 1;
+
+                let chat = chats::table
+                    .filter(chats::direct_key.eq(&insert_chat.direct_key))
+                    .first::<Chat>(connection)?;
+
+                // A new chat gets both users, an existing one only takes the caller back
+                // in if they had left it. A peer who left stays out.
+                let participants = if created {
+                    vec![user_id.clone(), peer_id.clone()]
+                } else {
+                    vec![user_id.clone()]
+                };
+                diesel::insert_into(users_chats::table)
+                    .values(
+                        participants
+                            .iter()
+                            .map(|participant| {
+                                UsersChats::new(participant.clone(), chat.id, ChatRole::Member)
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .on_conflict((users_chats::user_id, users_chats::chat_id))
+                    .do_nothing()
+                    .execute(connection)?;
+
+                let membership = users_chats::table
+                    .filter(users_chats::user_id.eq(&user_id))
+                    .filter(users_chats::chat_id.eq(chat.id))
+                    .first::<UsersChats>(connection)?;
+
+                Ok::<_, diesel::result::Error>((chat, membership, participants))
+            })
+            .map_err(|e| {
+                error!("Failed to open direct chat: {}", e);
+                Status::internal("Failed to open direct chat")
+            })?;
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        for participant in &participants {
+            self.publish_chat_connect(&channel, participant, &RabbitChatConnect::Connect(chat.id))
+                .await
+                .map_err(|e| {
+                    error!("Failed to publish chat connect: {}", e);
+                    Status::internal("Failed to publish chat connect")
+                })?;
+        }
+
+        let unread_count = self
+            .count_unread(&mut connection, &user_id, &[chat.id])
+            .await?
+            .remove(&chat.id)
+            .unwrap_or(0);
+        let last_message = self
+            .get_last_messages(&mut connection, &user_id, &[chat.id])
+            .await?
+            .remove(&chat.id);
+        let name = self
+            .display_names(&mut connection, &[&chat], &user_id)
+            .await?
+            .remove(&chat.id)
+            .unwrap_or_default();
+
+        debug!(
+            "User {} opened direct chat {} with {}",
+            user_id, chat.id, peer_id
+        );
+        Ok(Response::new(OpenDirectChatResponse {
+            chat: Some(GChat {
+                name,
+                unread_count,
+                last_message,
+                role: membership.role() as i32,
+                muted: membership.muted,
+                ..chat.into()
+            }),
+        }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn leave_chat(
+        &self,
+        request: Request<LeaveChatRequest>,
+    ) -> Result<Response<LeaveChatResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let chat_id = request.into_inner().chat_id;
+
+        let membership = self
+            .get_membership(&mut connection, &user_id, chat_id)
+            .await?
+            .ok_or_else(|| Status::not_found("You are not a member of this chat"))?;
+
+        self.delete_membership(&mut connection, &membership).await?;
+        self.publish_removal(chat_id, &user_id, &user_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to publish removal: {}", e);
+                Status::internal("Failed to publish removal")
+            })?;
+
+        debug!("User {} left chat {}", user_id, chat_id);
+        Ok(Response::new(LeaveChatResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn remove_member(
+        &self,
+        request: Request<RemoveMemberRequest>,
+    ) -> Result<Response<RemoveMemberResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let remove_request = request.into_inner();
+
+        let actor = self
+            .get_membership(&mut connection, &user_id, remove_request.chat_id)
+            .await?
+            .ok_or_else(|| Status::permission_denied("You are not a member of this chat"))?;
+
+        if !actor.is_admin() {
+            return Err(Status::permission_denied("Only admins can remove members"));
+        }
+
+        let membership = self
+            .get_membership(
+                &mut connection,
+                &remove_request.user_id,
+                remove_request.chat_id,
+            )
+            .await?
+            .ok_or_else(|| Status::not_found("User is not a member of this chat"))?;
+
+        // Admins manage members, only the owner manages admins, and nobody removes the owner.
+        if membership.role() >= actor.role() {
+            return Err(Status::permission_denied(
+                "You can't remove a member with the same or a higher role",
+            ));
+        }
+
+        self.delete_membership(&mut connection, &membership).await?;
+        self.publish_removal(remove_request.chat_id, &remove_request.user_id, &user_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to publish removal: {}", e);
+                Status::internal("Failed to publish removal")
+            })?;
+
+        debug!(
+            "User {} removed {} from chat {}",
+            user_id, remove_request.user_id, remove_request.chat_id
+        );
+        Ok(Response::new(RemoveMemberResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn update_chat(
+        &self,
+        request: Request<UpdateChatRequest>,
+    ) -> Result<Response<UpdateChatResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let update_request = request.into_inner();
+
+        let update_chat = UpdateChat {
+            name: update_request.name.map(|name| name.trim().to_string()),
+            description: update_request
+                .description
+                .map(|description| description.trim().to_string()),
+        };
+
+        if update_chat.name.is_none() && update_chat.description.is_none() {
+            return Err(Status::invalid_argument("Nothing to update"));
+        }
+        if update_chat
+            .name
+            .as_ref()
+            .is_some_and(|name| name.is_empty() || name.chars().count() > MAX_CHAT_NAME_LENGTH)
+        {
+            return Err(Status::invalid_argument(format!(
+                "Chat name must be between 1 and {} characters",
+                MAX_CHAT_NAME_LENGTH
+            )));
+        }
+        if update_chat
+            .description
+            .as_ref()
+            .is_some_and(|description| description.chars().count() > MAX_CHAT_DESCRIPTION_LENGTH)
+        {
+            return Err(Status::invalid_argument(format!(
+                "Chat description must be at most {} characters",
+                MAX_CHAT_DESCRIPTION_LENGTH
+            )));
+        }
+
+        let actor = self
+            .get_membership(&mut connection, &user_id, update_request.chat_id)
+            .await?
+            .ok_or_else(|| Status::permission_denied("You are not a member of this chat"))?;
+
+        if !actor.is_admin() {
+            return Err(Status::permission_denied("Only admins can update the chat"));
+        }
+
+        let chat = diesel::update(chats::table)
+            .filter(chats::id.eq(update_request.chat_id))
+            .set(&update_chat)
+            .get_result::<Chat>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to update chat: {}", e);
+                Status::internal("Failed to update chat")
+            })?;
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_event(
+            &channel,
+            chat.id,
+            &RabbitChatEvent::ChatUpdated(chat.clone()),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to publish chat update: {}", e);
+            Status::internal("Failed to publish chat update")
+        })?;
+
+        Ok(Response::new(UpdateChatResponse {
+            chat: Some(GChat {
+                role: actor.role() as i32,
+                ..chat.into()
+            }),
+        }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn get_chat_members(
+        &self,
+        request: Request<GetChatMembersRequest>,
+    ) -> Result<Response<ChatMembers>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let chat_id = request.into_inner().chat_id;
+
+        if self
+            .get_membership(&mut connection, &user_id, chat_id)
+            .await?
+            .is_none()
+        {
+            return Err(Status::permission_denied(
+                "You are not a member of this chat",
+            ));
+        }
+
+        let members = users_chats::table
+            .filter(users_chats::chat_id.eq(chat_id))
+            .inner_join(users::table.on(users_chats::user_id.eq(users::id)))
+            .left_join(profiles::table.on(profiles::user_id.eq(users::id)))
+            .order((users_chats::joined_at.asc(), users_chats::user_id.asc()))
+            .select((
+                users::all_columns,
+                profiles::all_columns.nullable(),
+                users_chats::role,
+                users_chats::joined_at,
+            ))
+            .load::<(User, Option<Profile>, String, chrono::NaiveDateTime)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get chat members: {}", e);
+                Status::internal("Failed to get chat members")
+            })?;
+
+        let members = members
+            .into_iter()
+            // Members share a chat, so they see each other's emails as contacts do.
+            .map(|(user, profile, role, joined_at)| ChatMember {
+                user: Some(user_for_viewer(user, profile, &user_id, true)),
+                role: parse_role(&role) as i32,
+                joined_at: Some(to_timestamp(joined_at)),
+            })
+            .collect();
+
+        Ok(Response::new(ChatMembers { members }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn delete_chat(
+        &self,
+        request: Request<DeleteChatRequest>,
+    ) -> Result<Response<DeleteChatResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let chat_id = request.into_inner().chat_id;
+
+        let actor = self
+            .get_membership(&mut connection, &user_id, chat_id)
+            .await?
+            .ok_or_else(|| Status::permission_denied("You are not a member of this chat"))?;
+
+        if actor.role() != ChatRole::Owner {
+            return Err(Status::permission_denied(
+                "Only the owner can delete the chat",
+            ));
+        }
+
+        self.delete_chat_cascade(&mut connection, chat_id).await?;
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_event(&channel, chat_id, &RabbitChatEvent::ChatDeleted { chat_id })
+            .await
+            .map_err(|e| {
+                error!("Failed to publish chat deletion: {}", e);
+                Status::internal("Failed to publish chat deletion")
+            })?;
+
+        // Deleting the exchange drops the bindings of every live stream of the members.
+        delete_messages_exchange(&channel, &chat_id.to_string())
+            .await
+            .map_err(|e| {
+                error!("Failed to delete chat exchange: {}", e);
+                Status::internal("Failed to delete chat exchange")
+            })?;
+
+        debug!("User {} deleted chat {}", user_id, chat_id);
+        Ok(Response::new(DeleteChatResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn mute_chat(
+        &self,
+        request: Request<MuteChatRequest>,
+    ) -> Result<Response<MuteChatResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let mute_request = request.into_inner();
+
+        let updated = diesel::update(users_chats::table)
+            .filter(users_chats::user_id.eq(&user_id))
+            .filter(users_chats::chat_id.eq(mute_request.chat_id))
+            .set(users_chats::muted.eq(mute_request.muted))
+            .execute(&mut connection)
+            .map_err(|e| {
+                error!("Failed to mute chat: {}", e);
+                Status::internal("Failed to mute chat")
+            })?;
+
+        if updated == 0 {
+            return Err(Status::permission_denied(
+                "You are not a member of this chat",
+            ));
+        }
+
+        debug!(
+            "User {} set chat {} muted: {}",
+            user_id, mute_request.chat_id, mute_request.muted
+        );
+        Ok(Response::new(MuteChatResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn pin_message(
+        &self,
+        request: Request<PinMessageRequest>,
+    ) -> Result<Response<GPinnedMessage>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let message_id = request.into_inner().message_id;
+
+        let message = self
+            .get_pinnable_message(&mut connection, &user_id, message_id)
+            .await?;
+
+        if message.deleted_at.is_some() {
+            return Err(Status::failed_precondition("Message has been deleted"));
+        }
+
+        let existing_pin = pinned_messages::table
+            .filter(pinned_messages::message_id.eq(message_id))
+            .first::<PinnedMessage>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get pinned message: {}", e);
+                Status::internal("Failed to get pinned message")
+            })?;
+
+        // Pinning twice is a no-op, the chat isn't told again.
+        if let Some(pin) = existing_pin {
+            return Ok(Response::new(to_proto_pinned_message(pin, message)));
+        }
+
+        let pinned_count = pinned_messages::table
+            .filter(pinned_messages::chat_id.eq(message.chat_id))
+            .count()
+            .get_result::<i64>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to count pinned messages: {}", e);
+                Status::internal("Failed to count pinned messages")
+            })?;
+
+        if pinned_count >= MAX_PINNED_MESSAGES {
+            return Err(Status::failed_precondition(format!(
+                "A chat can't have more than {} pinned messages",
+                MAX_PINNED_MESSAGES
+            )));
+        }
+
+        let pin = diesel::insert_into(pinned_messages::table)
+            .values(&InsertPinnedMessage {
+                message_id,
+                chat_id: message.chat_id,
+                pinned_by_user_id: user_id.clone(),
+            })
+            .get_result::<PinnedMessage>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to pin message: {}", e);
+                Status::internal("Failed to pin message")
+            })?;
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_event(
+            &channel,
+            message.chat_id,
+            &RabbitChatEvent::MessagePinned(pin.clone(), message.clone()),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to publish pin: {}", e);
+            Status::internal("Failed to publish pin")
+        })?;
+
+        debug!("User {} pinned message {}", user_id, message_id);
+        Ok(Response::new(to_proto_pinned_message(pin, message)))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn unpin_message(
+        &self,
+        request: Request<UnpinMessageRequest>,
+    ) -> Result<Response<UnpinMessageResponse>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let message_id = request.into_inner().message_id;
+
+        let message = self
+            .get_pinnable_message(&mut connection, &user_id, message_id)
+            .await?;
+
+        let deleted = diesel::delete(pinned_messages::table)
+            .filter(pinned_messages::message_id.eq(message_id))
+            .execute(&mut connection)
+            .map_err(|e| {
+                error!("Failed to unpin message: {}", e);
+                Status::internal("Failed to unpin message")
+            })?;
+
+        if deleted == 0 {
+            return Err(Status::not_found(format!(
+                "Message {} is not pinned",
+                message_id
+            )));
+        }
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_event(
+            &channel,
+            message.chat_id,
+            &RabbitChatEvent::MessageUnpinned {
+                chat_id: message.chat_id,
+                message_id,
+                unpinned_by: user_id.clone(),
+            },
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to publish unpin: {}", e);
+            Status::internal("Failed to publish unpin")
+        })?;
+
+        debug!("User {} unpinned message {}", user_id, message_id);
+        Ok(Response::new(UnpinMessageResponse { success: true }))
+    }
+
+    #[instrument(skip(self, request), err)]
+    async fn get_pinned_messages(
+        &self,
+        request: Request<GetPinnedMessagesRequest>,
+    ) -> Result<Response<PinnedMessages>, Status> {
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let user_id = request.authenticated_user()?.id.clone();
+        let chat_id = request.into_inner().chat_id;
+
+        if self
+            .get_membership(&mut connection, &user_id, chat_id)
+            .await?
+            .is_none()
+        {
+            return Err(Status::permission_denied(
+                "You are not a member of this chat",
+            ));
+        }
+
+        let pins = pinned_messages::table
+            .inner_join(messages::table)
+            .filter(pinned_messages::chat_id.eq(chat_id))
+            .filter(messages::deleted_at.is_null())
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(&user_id)),
+            ))
+            .order((pinned_messages::pinned_at.desc(), messages::id.desc()))
+            .select((PinnedMessage::as_select(), Message::as_select()))
+            .load::<(PinnedMessage, Message)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get pinned messages: {}", e);
+                Status::internal("Failed to get pinned messages")
+            })?;
+
+        Ok(Response::new(PinnedMessages {
+            pinned_messages: pins
+                .into_iter()
+                .map(|(pin, message)| to_proto_pinned_message(pin, message))
+                .collect(),
         }))
     }
 }
@@ -155,6 +1293,10 @@ module! {
             components = [dyn ChannelManager],
             providers = [],
         },
+        use BlobStoreModule{
+            components = [dyn BlobStore],
+            providers = [],
+        },
     }
 }
 pub fn build_chat_manager_module() -> Arc<ChatManagerModule> {
@@ -162,6 +1304,7 @@ pub fn build_chat_manager_module() -> Arc<ChatManagerModule> {
         ChatManagerModule::builder(
             build_db_connection_manager_module(),
             build_channel_manager_module(),
+            build_blob_store_module(),
         )
         .build(),
     )
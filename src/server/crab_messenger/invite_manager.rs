@@ -18,6 +18,7 @@ use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 use tracing::{debug, error, info};
 
+use crate::server::authenticated_user::AuthenticatedRequest;
 use crate::server::crab_messenger::invite_manager::invite_consumer::RabbitConsumer;
 use crate::server::crab_messenger::InviteResponseStream;
 use crate::utils::db_connection_manager::{
@@ -25,11 +26,15 @@ use crate::utils::db_connection_manager::{
 };
 use crate::utils::generate_random_string;
 use crate::utils::messenger::{
-    AnswerInviteRequest, AnswerInviteResponse, GetInvitesRequest, GetInvitesResponse,
-    InvitesRequest, SendInviteRequest, SendInviteResponse,
+    AnswerInviteRequest, AnswerInviteResponse, CreateInviteLinkRequest, GetInvitesRequest,
+    GetInvitesResponse, GetSentInvitesRequest, GetSentInvitesResponse, InviteLink, InvitesRequest,
+    JoinByCodeRequest, JoinByCodeResponse, RevokeInviteRequest, RevokeInviteResponse,
+    SendInviteRequest, SendInviteResponse,
 };
+use crate::utils::persistence::chat_invite_link::{ChatInviteLink, InsertChatInviteLink};
 use crate::utils::persistence::invite::Invite;
-use crate::utils::persistence::schema::{invites, users_chats};
+use crate::utils::persistence::message::to_naive;
+use crate::utils::persistence::schema::{chat_invite_links, invites, users_chats};
 use crate::utils::persistence::users_chats::UsersChats;
 use crate::utils::rabbit_channel_manager::{
     build_channel_manager_module, ChannelManager, ChannelManagerModule,
@@ -38,10 +43,15 @@ use crate::utils::rabbit_declares::{
     declare_accept_invites_exchange, declare_invites_exchange, declare_send_invite_exchange,
     invites_exchange_name, ACCEPT_INVITES_EXCHANGE, INVITES_EXCHANGE, SEND_INVITE_EXCHANGE,
 };
-use crate::utils::rabbit_types::RabbitInviteAccept;
+use crate::utils::rabbit_types::{
+    RabbitChatJoin, RabbitInviteAccept, RabbitInviteEvent, RabbitJoinByCode,
+};
 
 mod invite_consumer;
 
+/// Length of the code of an invite link.
+const INVITE_CODE_LENGTH: usize = 12;
+
 #[async_trait]
 pub trait InviteManager: Interface {
     async fn send_invite(
@@ -63,6 +73,26 @@ pub trait InviteManager: Interface {
         &self,
         request: Request<AnswerInviteRequest>,
     ) -> Result<Response<AnswerInviteResponse>, Status>;
+
+    async fn get_sent_invites(
+        &self,
+        request: Request<GetSentInvitesRequest>,
+    ) -> Result<Response<GetSentInvitesResponse>, Status>;
+
+    async fn revoke_invite(
+        &self,
+        request: Request<RevokeInviteRequest>,
+    ) -> Result<Response<RevokeInviteResponse>, Status>;
+
+    async fn create_invite_link(
+        &self,
+        request: Request<CreateInviteLinkRequest>,
+    ) -> Result<Response<InviteLink>, Status>;
+
+    async fn join_by_code(
+        &self,
+        request: Request<JoinByCodeRequest>,
+    ) -> Result<Response<JoinByCodeResponse>, Status>;
 }
 
 #[derive(Component, Clone)]
@@ -90,37 +120,40 @@ impl InviteManager for InviteManagerImpl {
     ) -> Result<Response<SendInviteResponse>, Status> {
         info!("Sending invite");
 
-        let metadata = request.metadata();
-
-        let inviter_user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let inviter_user_id = request.authenticated_user()?.id.clone();
         let invite_request = request.into_inner();
 
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get connection: {:?}", e);
+            Status::internal("Failed to get connection")
+        })?;
+
         let binding = users_chats::table
             .filter(users_chats::user_id.eq(&inviter_user_id))
             .filter(users_chats::chat_id.eq(invite_request.chat_id))
-            .first::<UsersChats>(
-                &mut self.db_connection_manager.get_connection().map_err(|e| {
-                    error!("Failed to get connection: {:?}", e);
-                    Status::internal("Failed to get connection")
-                })?,
-            )
+            .first::<UsersChats>(&mut connection)
             .optional()
             .map_err(|e| {
                 error!("Failed to get binding: {:?}", e);
                 Status::internal("Failed to get binding")
             })?;
 
-        if binding.is_none() {
-            return Err(Status::permission_denied(
-                "You are not a member of this chat",
-            ));
+        match binding {
+            None => {
+                return Err(Status::permission_denied(
+                    "You are not a member of this chat",
+                ));
+            }
+            Some(binding) if !binding.is_admin() => {
+                return Err(Status::permission_denied("Only admins can send invites"));
+            }
+            Some(_) => {}
         }
 
+        // The worker skips these too, checking here lets the inviter know.
+        self.check_invitable(&mut connection, &invite_request)
+            .await?;
+
         let rabbit_invite = RabbitCreateInvite {
             inviter_user_id,
             invitee_user_id: invite_request.user_id,
@@ -166,13 +199,7 @@ impl InviteManager for InviteManagerImpl {
         request: Request<InvitesRequest>,
     ) -> Result<Response<InviteResponseStream>, Status> {
         info!("Starting invites");
-        let metadata = request.metadata();
-        let listener_user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let listener_user_id = request.authenticated_user()?.id.clone();
         let (tx, rx) = mpsc::channel(16);
         let channel = self.setup_invite_channel(&listener_user_id).await?;
 
@@ -206,13 +233,7 @@ impl InviteManager for InviteManagerImpl {
         &self,
         request: Request<GetInvitesRequest>,
     ) -> Result<Response<GetInvitesResponse>, Status> {
-        let metadata = request.metadata();
-        let user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let user_id = request.authenticated_user()?.id.clone();
 
         let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
             error!("Failed to get connection: {:?}", e);
@@ -221,6 +242,7 @@ impl InviteManager for InviteManagerImpl {
 
         let invites = invites::table
             .filter(invites::invitee_user_id.eq(user_id))
+            .filter(invites::expires_at.gt(diesel::dsl::now))
             .select(invites::all_columns)
             .load::<Invite>(&mut connection)
             .map_err(|e| {
@@ -237,13 +259,7 @@ impl InviteManager for InviteManagerImpl {
         &self,
         request: Request<AnswerInviteRequest>,
     ) -> Result<Response<AnswerInviteResponse>, Status> {
-        let metadata = request.metadata();
-        let user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let user_id = request.authenticated_user()?.id.clone();
 
         let answer_invite_request = request.into_inner();
 
@@ -264,6 +280,10 @@ impl InviteManager for InviteManagerImpl {
             return Err(Status::permission_denied("You can't answer this invite"));
         }
 
+        if db_invite.is_expired() {
+            return Err(Status::failed_precondition("Invite has expired"));
+        }
+
         match answer_invite_request.accept {
             false => self
                 .answer_nay(
@@ -297,9 +317,317 @@ impl InviteManager for InviteManagerImpl {
 
         Ok(Response::new(AnswerInviteResponse { success: true }))
     }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn get_sent_invites(
+        &self,
+        request: Request<GetSentInvitesRequest>,
+    ) -> Result<Response<GetSentInvitesResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let chat_id = request.into_inner().chat_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get connection: {:?}", e);
+            Status::internal("Failed to get connection")
+        })?;
+
+        let mut query = invites::table
+            .filter(invites::inviter_user_id.eq(user_id))
+            .filter(invites::expires_at.gt(diesel::dsl::now))
+            .into_boxed();
+
+        if let Some(chat_id) = chat_id {
+            query = query.filter(invites::chat_id.eq(chat_id));
+        }
+
+        let invites = query
+            .order(invites::created_at.desc())
+            .load::<Invite>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get sent invites: {}", e);
+                Status::internal("Failed to get sent invites")
+            })?;
+
+        Ok(Response::new(GetSentInvitesResponse {
+            invites: invites.into_iter().map(|i| i.into()).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn revoke_invite(
+        &self,
+        request: Request<RevokeInviteRequest>,
+    ) -> Result<Response<RevokeInviteResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let invite_id = request.into_inner().invite_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get connection: {:?}", e);
+            Status::internal("Failed to get connection")
+        })?;
+
+        let invite = invites::table
+            .filter(invites::id.eq(invite_id))
+            .first::<Invite>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get invite: {:?}", e);
+                Status::internal("Failed to get invite")
+            })?
+            .ok_or_else(|| Status::not_found(format!("No invite {} found", invite_id)))?;
+
+        if invite.inviter_user_id != user_id {
+            let binding = users_chats::table
+                .filter(users_chats::user_id.eq(&user_id))
+                .filter(users_chats::chat_id.eq(invite.chat_id))
+                .first::<UsersChats>(&mut connection)
+                .optional()
+                .map_err(|e| {
+                    error!("Failed to get binding: {:?}", e);
+                    Status::internal("Failed to get binding")
+                })?;
+
+            if !binding.is_some_and(|binding| binding.is_admin()) {
+                return Err(Status::permission_denied("You can't revoke this invite"));
+            }
+        }
+
+        diesel::delete(invites::table)
+            .filter(invites::id.eq(invite_id))
+            .execute(&mut connection)
+            .map_err(|e| {
+                error!("Failed to delete invite: {:?}", e);
+                Status::internal("Failed to delete invite")
+            })?;
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {:?}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_invite_event(
+            &channel,
+            &invite.invitee_user_id,
+            &RabbitInviteEvent::InviteRevoked {
+                invite_id,
+                chat_id: invite.chat_id,
+            },
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to publish revocation: {:?}", e);
+            Status::internal("Failed to publish revocation")
+        })?;
+
+        debug!("User {} revoked invite {}", user_id, invite_id);
+        Ok(Response::new(RevokeInviteResponse { success: true }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn create_invite_link(
+        &self,
+        request: Request<CreateInviteLinkRequest>,
+    ) -> Result<Response<InviteLink>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let link_request = request.into_inner();
+
+        if link_request.max_uses.is_some_and(|max_uses| max_uses <= 0) {
+            return Err(Status::invalid_argument("Max uses must be positive"));
+        }
+
+        let expires_at = match link_request.expires_at {
+            Some(expires_at) => Some(
+                to_naive(expires_at).ok_or_else(|| Status::invalid_argument("Invalid expiry"))?,
+            ),
+            None => None,
+        };
+        if expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now().naive_utc()) {
+            return Err(Status::invalid_argument("Expiry must be in the future"));
+        }
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get connection: {:?}", e);
+            Status::internal("Failed to get connection")
+        })?;
+
+        let binding = self
+            .get_binding(&mut connection, &user_id, link_request.chat_id)
+            .await?;
+
+        match binding {
+            None => {
+                return Err(Status::permission_denied(
+                    "You are not a member of this chat",
+                ));
+            }
+            Some(binding) if !binding.is_admin() => {
+                return Err(Status::permission_denied(
+                    "Only admins can create invite links",
+                ));
+            }
+            Some(_) => {}
+        }
+
+        let link = diesel::insert_into(chat_invite_links::table)
+            .values(InsertChatInviteLink {
+                code: generate_random_string(INVITE_CODE_LENGTH),
+                chat_id: link_request.chat_id,
+                creator_user_id: user_id,
+                max_uses: link_request.max_uses,
+                expires_at,
+            })
+            .get_result::<ChatInviteLink>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to create invite link: {:?}", e);
+                Status::internal("Failed to create invite link")
+            })?;
+
+        debug!("Invite link created for chat {}", link.chat_id);
+        Ok(Response::new(link.into()))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn join_by_code(
+        &self,
+        request: Request<JoinByCodeRequest>,
+    ) -> Result<Response<JoinByCodeResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let code = request.into_inner().code;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get connection: {:?}", e);
+            Status::internal("Failed to get connection")
+        })?;
+
+        let link = chat_invite_links::table
+            .find(&code)
+            .first::<ChatInviteLink>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get invite link: {:?}", e);
+                Status::internal("Failed to get invite link")
+            })?
+            .ok_or_else(|| Status::not_found("No such invite link"))?;
+
+        if link.is_expired() {
+            return Err(Status::failed_precondition("Invite link has expired"));
+        }
+        if link.is_used_up() {
+            return Err(Status::failed_precondition("Invite link has no uses left"));
+        }
+
+        if self
+            .get_binding(&mut connection, &user_id, link.chat_id)
+            .await?
+            .is_some()
+        {
+            return Err(Status::already_exists(
+                "You are already a member of this chat",
+            ));
+        }
+
+        // The worker checks the link again, as it may be used up meanwhile.
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {:?}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_join(
+            &channel,
+            &RabbitChatJoin::Code(RabbitJoinByCode { code, user_id }),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to join chat: {:?}", e);
+            Status::internal("Failed to join chat")
+        })?;
+
+        Ok(Response::new(JoinByCodeResponse {
+            chat_id: link.chat_id,
+        }))
+    }
 }
 
 impl InviteManagerImpl {
+    async fn get_binding(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_id: i32,
+    ) -> Result<Option<UsersChats>, Status> {
+        users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq(chat_id))
+            .first::<UsersChats>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get binding: {:?}", e);
+                Status::internal("Failed to get binding")
+            })
+    }
+
+    async fn check_invitable(
+        &self,
+        connection: &mut PgConnection,
+        invite_request: &SendInviteRequest,
+    ) -> Result<(), Status> {
+        let membership = users_chats::table
+            .filter(users_chats::user_id.eq(&invite_request.user_id))
+            .filter(users_chats::chat_id.eq(invite_request.chat_id))
+            .first::<UsersChats>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get binding: {:?}", e);
+                Status::internal("Failed to get binding")
+            })?;
+
+        if membership.is_some() {
+            return Err(Status::already_exists(
+                "User is already a member of this chat",
+            ));
+        }
+
+        let pending_invite = invites::table
+            .filter(invites::invitee_user_id.eq(&invite_request.user_id))
+            .filter(invites::chat_id.eq(invite_request.chat_id))
+            .filter(invites::expires_at.gt(diesel::dsl::now))
+            .first::<Invite>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get invite: {:?}", e);
+                Status::internal("Failed to get invite")
+            })?;
+
+        if pending_invite.is_some() {
+            return Err(Status::already_exists(
+                "User is already invited to this chat",
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn publish_invite_event(
+        &self,
+        channel: &Channel,
+        user_id: &str,
+        event: &RabbitInviteEvent,
+    ) -> anyhow::Result<()> {
+        declare_invites_exchange(channel, user_id).await?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serde_json::to_string(event)?.into_bytes(),
+                BasicPublishArguments::new(&invites_exchange_name(user_id), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     async fn setup_invite_channel(&self, user_id: &str) -> Result<Channel, Status> {
         let channel = self.channel_manager.get_channel().await.map_err(|e| {
@@ -379,18 +707,29 @@ impl InviteManagerImpl {
         user_id: &str,
     ) -> anyhow::Result<()> {
         info!("Answering yay to invite {}", invite_id);
-        declare_accept_invites_exchange(&channel)
+        let rabbit_invite_accept = RabbitInviteAccept {
+            invite_id,
+            user_id: user_id.to_string(),
+        };
+
+        self.publish_chat_join(channel, &RabbitChatJoin::Invite(rabbit_invite_accept))
+            .await
+    }
+
+    /// Joins go through the worker, which binds the joiner's live Chat streams to the chat.
+    async fn publish_chat_join(
+        &self,
+        channel: &Channel,
+        chat_join: &RabbitChatJoin,
+    ) -> anyhow::Result<()> {
+        declare_accept_invites_exchange(channel)
             .await
             .map_err(|e| {
                 error!("Failed to declare exchange: {:?}", e);
                 Status::internal("Failed to declare exchange")
             })?;
-        let rabbit_invite_accept = RabbitInviteAccept {
-            invite_id,
-            user_id: user_id.to_string(),
-        };
 
-        let serialized_message = serde_json::to_string(&rabbit_invite_accept).map_err(|e| {
+        let serialized_message = serde_json::to_string(chat_join).map_err(|e| {
             error!("Failed to serialize message: {:?}", e);
             Status::internal("Failed to serialize message")
         })?;
@@ -1,9 +1,14 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
-use amqprs::channel::{BasicConsumeArguments, Channel, QueueBindArguments, QueueDeclareArguments};
+use amqprs::channel::{
+    BasicConsumeArguments, BasicPublishArguments, Channel, QueueBindArguments,
+    QueueDeclareArguments,
+};
 use amqprs::consumer::AsyncConsumer;
+use amqprs::BasicProperties;
 use async_trait::async_trait;
 use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Int4, Nullable, Text};
 use diesel::RunQueryDsl;
 use shaku::{module, Component, Interface};
 use tokio::sync::mpsc;
@@ -12,6 +17,7 @@ use tonic::codegen::Body;
 use tonic::{Request, Response, Status, Streaming};
 use tracing::{debug, error, info};
 
+use crate::server::authenticated_user::AuthenticatedRequest;
 use crate::server::crab_messenger::message_manager::connect_consumer::ConnectConsumer;
 use crate::server::crab_messenger::message_manager::message_consumer::RabbitConsumer;
 use crate::server::crab_messenger::message_manager::message_stream_handler::{
@@ -22,17 +28,30 @@ use crate::utils::db_connection_manager::{
     build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
 };
 use crate::utils::generate_random_string;
-use crate::utils::messenger::{GetMessagesRequest, Messages, SendMessage};
-use crate::utils::persistence::message::Message;
-use crate::utils::persistence::schema::{messages, users_chats};
+use crate::utils::messenger::{
+    ChatRequest, DeleteMessageRequest, DeleteMessageResponse, EditMessageRequest,
+    GetMentionsRequest, GetMessagesRequest, GetThreadRequest, Message as GMessage, Messages,
+    ReactionRequest, ReactionResponse, SearchHit, SearchMessagesRequest, SearchMessagesResponse,
+    Thread,
+};
+use crate::utils::persistence::api_key::{SCOPE_MESSAGES_READ, SCOPE_MESSAGES_WRITE};
+use crate::utils::persistence::attachment::{attachments_of, Attachment};
+use crate::utils::persistence::message::{to_naive, Message, MessageSearchHit};
+use crate::utils::persistence::message_reaction::{aggregate_reactions, MessageReaction};
+use crate::utils::persistence::schema::{
+    attachments, mentions, message_reactions, messages, pinned_messages, users_chats,
+};
+use crate::utils::persistence::user_block::{blocked_by, blocked_user_ids};
 use crate::utils::persistence::users_chats::UsersChats;
 use crate::utils::rabbit_channel_manager::{
     build_channel_manager_module, ChannelManager, ChannelManagerModule,
 };
 use crate::utils::rabbit_declares::{
     chat_connect_exchange_name, declare_chat_connect_exchange, declare_messages_exchange,
-    declare_new_message_exchange, messages_exchange_name, CHAT_CONNECT_EXCHANGE, MESSAGES_EXCHANGE,
+    declare_new_message_exchange, declare_reaction_exchange, messages_exchange_name,
+    REACTION_EXCHANGE,
 };
+use crate::utils::rabbit_types::{RabbitChatEvent, RabbitReactionChange};
 
 mod connect_consumer;
 mod message_consumer;
@@ -43,12 +62,40 @@ pub trait MessageManager: Interface {
     type ChatStream;
     async fn chat(
         &self,
-        request: Request<Streaming<SendMessage>>,
+        request: Request<Streaming<ChatRequest>>,
     ) -> Result<Response<Self::ChatStream>, Status>;
     async fn get_messages(
         &self,
         request: Request<GetMessagesRequest>,
     ) -> Result<Response<Messages>, Status>;
+    async fn search_messages(
+        &self,
+        request: Request<SearchMessagesRequest>,
+    ) -> Result<Response<SearchMessagesResponse>, Status>;
+    async fn get_mentions(
+        &self,
+        request: Request<GetMentionsRequest>,
+    ) -> Result<Response<Messages>, Status>;
+    async fn edit_message(
+        &self,
+        request: Request<EditMessageRequest>,
+    ) -> Result<Response<GMessage>, Status>;
+    async fn delete_message(
+        &self,
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<DeleteMessageResponse>, Status>;
+    async fn get_thread(
+        &self,
+        request: Request<GetThreadRequest>,
+    ) -> Result<Response<Thread>, Status>;
+    async fn add_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status>;
+    async fn remove_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status>;
 }
 
 #[derive(Component)]
@@ -172,8 +219,228 @@ impl MessageManagerImpl {
                 Status::internal("Failed to consume messages")
             })
     }
+
+    async fn check_membership(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+        chat_id: i32,
+    ) -> Result<(), Status> {
+        let binding = users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq(chat_id))
+            .first::<UsersChats>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get binding: {}", e);
+                Status::internal("Failed to get binding")
+            })?;
+
+        match binding {
+            Some(_) => Ok(()),
+            None => Err(Status::permission_denied(
+                "You are not a member of this chat",
+            )),
+        }
+    }
+
+    async fn get_own_message(
+        &self,
+        connection: &mut PgConnection,
+        message_id: i32,
+        user_id: &str,
+    ) -> Result<Message, Status> {
+        let message = messages::table
+            .filter(messages::id.eq(message_id))
+            .first::<Message>(connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!("No message found with id: {}", message_id))
+            })?;
+
+        if message.user_id != user_id {
+            return Err(Status::permission_denied(
+                "You can only change your own messages",
+            ));
+        }
+
+        if message.deleted_at.is_some() {
+            return Err(Status::failed_precondition("Message has been deleted"));
+        }
+
+        Ok(message)
+    }
+
+    async fn publish_chat_event(
+        &self,
+        chat_id: i32,
+        event: &RabbitChatEvent,
+    ) -> Result<(), Status> {
+        let serialized_event = serde_json::to_string(event).map_err(|e| {
+            error!("Failed to serialize chat event: {:?}", e);
+            Status::internal("Failed to serialize chat event")
+        })?;
+
+        let channel = self.setup_chat_channel().await?;
+        let chat_id = chat_id.to_string();
+        declare_messages_exchange(&channel, &chat_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to declare exchange: {:?}", e);
+                Status::internal("Failed to declare exchange")
+            })?;
+
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serialized_event.into_bytes(),
+                BasicPublishArguments::new(&messages_exchange_name(&chat_id), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to publish chat event: {:?}", e);
+                Status::internal("Failed to publish chat event")
+            })
+    }
+
+    async fn publish_reaction_change(
+        &self,
+        reaction_change: &RabbitReactionChange,
+    ) -> Result<(), Status> {
+        let emoji = reaction_change.emoji.trim();
+        if emoji.is_empty() || emoji.chars().count() > MAX_EMOJI_LENGTH {
+            return Err(Status::invalid_argument("Invalid emoji"));
+        }
+
+        let serialized_change = serde_json::to_string(reaction_change).map_err(|e| {
+            error!("Failed to serialize reaction: {:?}", e);
+            Status::internal("Failed to serialize reaction")
+        })?;
+
+        let channel = self.setup_chat_channel().await?;
+        declare_reaction_exchange(&channel).await.map_err(|e| {
+            error!("Failed to declare exchange: {:?}", e);
+            Status::internal("Failed to declare exchange")
+        })?;
+
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serialized_change.into_bytes(),
+                BasicPublishArguments::new(REACTION_EXCHANGE, "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to publish reaction: {:?}", e);
+                Status::internal("Failed to publish reaction")
+            })
+    }
+
+    async fn load_reactions(
+        &self,
+        connection: &mut PgConnection,
+        message_ids: Vec<i32>,
+    ) -> Result<Vec<MessageReaction>, Status> {
+        message_reactions::table
+            .filter(message_reactions::message_id.eq_any(message_ids))
+            .order(message_reactions::created_at.asc())
+            .load::<MessageReaction>(connection)
+            .map_err(|e| {
+                error!("Failed to get reactions: {}", e);
+                Status::internal("Failed to get reactions")
+            })
+    }
+
+    async fn load_attachments(
+        &self,
+        connection: &mut PgConnection,
+        message_ids: Vec<i32>,
+    ) -> Result<Vec<Attachment>, Status> {
+        attachments::table
+            .filter(attachments::message_id.eq_any(message_ids))
+            .order(attachments::id.asc())
+            .load::<Attachment>(connection)
+            .map_err(|e| {
+                error!("Failed to get attachments: {}", e);
+                Status::internal("Failed to get attachments")
+            })
+    }
+
+    /// Converts the messages along with their reactions and attachments.
+    async fn to_proto_messages(
+        &self,
+        connection: &mut PgConnection,
+        messages: Vec<Message>,
+    ) -> Result<Vec<GMessage>, Status> {
+        let message_ids: Vec<i32> = messages.iter().map(|message| message.id).collect();
+        let reactions = self.load_reactions(connection, message_ids.clone()).await?;
+        let attachments = self.load_attachments(connection, message_ids).await?;
+
+        Ok(messages
+            .into_iter()
+            .map(|message| {
+                let message_reactions: Vec<_> = reactions
+                    .iter()
+                    .filter(|reaction| reaction.message_id == message.id)
+                    .cloned()
+                    .collect();
+                GMessage {
+                    reactions: aggregate_reactions(&message_reactions),
+                    attachments: attachments_of(&attachments, message.id),
+                    ..message.into()
+                }
+            })
+            .collect())
+    }
 }
 
+/// Longest emoji sequence (in chars) accepted as a reaction.
+const MAX_EMOJI_LENGTH: usize = 16;
+
+/// Page size used by GetMessages when the request does not set a limit.
+const DEFAULT_MESSAGES_PAGE_SIZE: u32 = 50;
+/// Largest page GetMessages returns, whatever the requested limit.
+const MAX_MESSAGES_PAGE_SIZE: u32 = 200;
+
+/// Page size used by SearchMessages when the request does not set a limit.
+const DEFAULT_SEARCH_PAGE_SIZE: u32 = 20;
+/// Largest page SearchMessages returns, whatever the requested limit.
+const MAX_SEARCH_PAGE_SIZE: u32 = 100;
+
+/// Page size used by GetMentions when the request does not set a limit.
+const DEFAULT_MENTIONS_PAGE_SIZE: u32 = 20;
+/// Largest page GetMentions returns, whatever the requested limit.
+const MAX_MENTIONS_PAGE_SIZE: u32 = 100;
+
+/// The `search_vector` column is generated by Postgres and has no diesel type,
+/// so it stays out of the schema and the search is written as plain SQL.
+const SEARCH_MESSAGES_QUERY: &str = "
+select messages.id, messages.text, messages.created_at, messages.user_id, messages.chat_id,
+       messages.edited_at, messages.deleted_at, messages.reply_to_message_id,
+       ts_headline('english', messages.text, query,
+                   'StartSel=**, StopSel=**, MaxFragments=2, MaxWords=20, MinWords=5') as snippet,
+       ts_rank(messages.search_vector, query) as rank
+from messages
+         join users_chats on users_chats.chat_id = messages.chat_id,
+     websearch_to_tsquery('english', $1) query
+where users_chats.user_id = $2
+  and ($3::int is null or messages.chat_id = $3)
+  and messages.deleted_at is null
+  and messages.user_id not in (select blocked_user_id from user_blocks where blocker_user_id = $2)
+  and messages.search_vector @@ query
+order by rank desc, messages.id desc
+limit $4 offset $5";
+
 #[async_trait]
 impl MessageManager for MessageManagerImpl {
     type ChatStream = ChatResponseStream;
@@ -181,16 +448,16 @@ impl MessageManager for MessageManagerImpl {
     #[tracing::instrument(skip(self, request))]
     async fn chat(
         &self,
-        request: Request<Streaming<SendMessage>>,
+        request: Request<Streaming<ChatRequest>>,
     ) -> Result<Response<Self::ChatStream>, Status> {
         info!("Starting chat");
-        let metadata = request.metadata();
-        let user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let authenticated_user = request.authenticated_user()?;
+        let user_id = authenticated_user.id.clone();
+        // The stream is how events are read, keys that can't read have no use for it.
+        if !authenticated_user.has_scope(SCOPE_MESSAGES_READ) {
+            return Err(Status::permission_denied("The api key can't read messages"));
+        }
+        let can_write = authenticated_user.has_scope(SCOPE_MESSAGES_WRITE);
         let user_id_clone = user_id.clone();
         let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
             error!("Failed to get DB connection: {}", e);
@@ -212,13 +479,20 @@ impl MessageManager for MessageManagerImpl {
                 Status::internal("Failed to setup connect queue")
             })?;
 
-        let connect_consumer = ConnectConsumer::new(queue_name.clone());
-        let connect_consumer_tag = self
+        let blocked_user_ids = blocked_user_ids(&mut connection, &user_id).map_err(|e| {
+            error!("Failed to get blocked users: {}", e);
+            Status::internal("Failed to get blocked users")
+        })?;
+        let blocked_user_ids = Arc::new(RwLock::new(blocked_user_ids.into_iter().collect()));
+
+        let connect_consumer =
+            ConnectConsumer::new(tx.clone(), queue_name.clone(), blocked_user_ids.clone());
+        let _connect_consumer_tag = self
             .consume_messages(&channel, connect_consumer, &connect_queue_name)
             .await?;
 
         let my_chats = users_chats::table
-            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::user_id.eq(&user_id))
             .select(users_chats::all_columns)
             .load::<UsersChats>(&mut connection)
             .map_err(|e| {
@@ -232,10 +506,10 @@ impl MessageManager for MessageManagerImpl {
                 .await?;
         }
 
-        let cunsumer_tag = self
+        let _cunsumer_tag = self
             .consume_messages(
                 &channel,
-                RabbitConsumer::new(tx, queue_name.clone()),
+                RabbitConsumer::new(tx.clone(), queue_name.clone(), user_id, blocked_user_ids),
                 &queue_name,
             )
             .await?;
@@ -243,7 +517,7 @@ impl MessageManager for MessageManagerImpl {
         let message_stream_handler = self.message_stream_handler.clone();
         tokio::spawn(async move {
             if let Err(e) = message_stream_handler
-                .handle_stream(request.into_inner(), &channel, user_id_clone)
+                .handle_stream(request.into_inner(), &channel, tx, user_id_clone, can_write)
                 .await
             {
                 error!("Error handling stream: {:?}", e);
@@ -258,13 +532,7 @@ impl MessageManager for MessageManagerImpl {
         request: Request<GetMessagesRequest>,
     ) -> Result<Response<Messages>, Status> {
         info!("Received request to get messages");
-        let metadata = request.metadata();
-        let user_id = metadata
-            .get("user_id")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+        let user_id = request.authenticated_user()?.id.clone();
 
         let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
             error!("Failed to get DB connection from pool: {}", e);
@@ -275,7 +543,7 @@ impl MessageManager for MessageManagerImpl {
         let chat_id_filter = get_messages_req.chat_id;
 
         let binding = users_chats::table
-            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::user_id.eq(&user_id))
             .filter(users_chats::chat_id.eq(chat_id_filter))
             .first::<UsersChats>(&mut connection)
             .optional()
@@ -293,20 +561,48 @@ impl MessageManager for MessageManagerImpl {
             )));
         }
 
-        let created_before_filter = get_messages_req.created_before.unwrap(); // assuming it's present
-        let created_before_naive = chrono::NaiveDateTime::from_timestamp_opt(
-            created_before_filter.seconds,
-            created_before_filter.nanos as u32,
-        )
-        .unwrap();
+        let limit = match get_messages_req.limit {
+            0 => DEFAULT_MESSAGES_PAGE_SIZE,
+            limit => limit.min(MAX_MESSAGES_PAGE_SIZE),
+        } as usize;
         debug!(
-            "Fetching messages for chat_id: {} created before: {:?}",
-            chat_id_filter, created_before_naive
+            "Fetching {} messages for chat_id: {} before: {:?} after: {:?}",
+            limit, chat_id_filter, get_messages_req.before_id, get_messages_req.after_id
         );
 
-        let message_results = match messages::table
+        // Messages of users the caller blocked are left out.
+        let mut query = messages::table
             .filter(messages::chat_id.eq(chat_id_filter))
-            .filter(messages::created_at.lt(created_before_naive))
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(&user_id)),
+            ))
+            .into_boxed();
+
+        if let Some(created_before) = get_messages_req.created_before {
+            let created_before = to_naive(created_before)
+                .ok_or_else(|| Status::invalid_argument("Invalid created_before timestamp"))?;
+            query = query.filter(messages::created_at.lt(created_before));
+        }
+
+        // Ids only grow, so they make a stable cursor even for messages sharing a timestamp.
+        // One extra row is fetched to find out whether there is more past this page.
+        let query = match (get_messages_req.before_id, get_messages_req.after_id) {
+            (Some(_), Some(_)) => {
+                return Err(Status::invalid_argument(
+                    "Only one of before_id and after_id can be set",
+                ));
+            }
+            (None, Some(after_id)) => query
+                .filter(messages::id.gt(after_id))
+                .order(messages::id.asc()),
+            (Some(before_id), None) => query
+                .filter(messages::id.lt(before_id))
+                .order(messages::id.desc()),
+            (None, None) => query.order(messages::id.desc()),
+        };
+
+        let mut message_results = match query
+            .limit(limit as i64 + 1)
             .load::<Message>(&mut connection)
         {
             Ok(results) => {
@@ -319,16 +615,369 @@ impl MessageManager for MessageManagerImpl {
             }
         };
 
-        let proto_messages: Vec<_> = message_results.into_iter().map(Into::into).collect();
+        let has_more = message_results.len() > limit;
+        message_results.truncate(limit);
+        message_results.sort_by_key(|message| message.id);
+
+        let proto_messages = self
+            .to_proto_messages(&mut connection, message_results)
+            .await?;
         debug!("Total messages fetched: {}", proto_messages.len());
 
         let response = Messages {
             messages: proto_messages,
+            has_more,
         };
 
         info!("Successfully processed get_messages request");
         Ok(Response::new(response))
     }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn search_messages(
+        &self,
+        request: Request<SearchMessagesRequest>,
+    ) -> Result<Response<SearchMessagesResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let search_request = request.into_inner();
+
+        let query = search_request.query.trim();
+        if query.is_empty() {
+            return Err(Status::invalid_argument("Search query can't be empty"));
+        }
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        if let Some(chat_id) = search_request.chat_id {
+            let binding = users_chats::table
+                .filter(users_chats::user_id.eq(&user_id))
+                .filter(users_chats::chat_id.eq(chat_id))
+                .first::<UsersChats>(&mut connection)
+                .optional()
+                .map_err(|e| {
+                    error!("Failed to get binding: {}", e);
+                    Status::internal("Failed to get binding")
+                })?;
+
+            if binding.is_none() {
+                return Err(Status::not_found(format!(
+                    "No binding found for chat_id: {}",
+                    chat_id
+                )));
+            }
+        }
+
+        let limit = match search_request.limit {
+            0 => DEFAULT_SEARCH_PAGE_SIZE,
+            limit => limit.min(MAX_SEARCH_PAGE_SIZE),
+        } as usize;
+        debug!(
+            "Searching {} messages for {:?} in chat_id: {:?} from offset {}",
+            limit, query, search_request.chat_id, search_request.offset
+        );
+
+        // One extra row is fetched to find out whether there is more past this page.
+        let mut hits = diesel::sql_query(SEARCH_MESSAGES_QUERY)
+            .bind::<Text, _>(query)
+            .bind::<Text, _>(&user_id)
+            .bind::<Nullable<Int4>, _>(search_request.chat_id)
+            .bind::<BigInt, _>(limit as i64 + 1)
+            .bind::<BigInt, _>(search_request.offset as i64)
+            .load::<MessageSearchHit>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to search messages: {}", e);
+                Status::internal("Failed to search messages")
+            })?;
+
+        let has_more = hits.len() > limit;
+        hits.truncate(limit);
+
+        let reactions = self
+            .load_reactions(
+                &mut connection,
+                hits.iter().map(|hit| hit.message.id).collect(),
+            )
+            .await?;
+        let attachments = self
+            .load_attachments(
+                &mut connection,
+                hits.iter().map(|hit| hit.message.id).collect(),
+            )
+            .await?;
+
+        let hits = hits
+            .into_iter()
+            .map(|hit| {
+                let message_reactions: Vec<_> = reactions
+                    .iter()
+                    .filter(|reaction| reaction.message_id == hit.message.id)
+                    .cloned()
+                    .collect();
+                SearchHit {
+                    message: Some(GMessage {
+                        reactions: aggregate_reactions(&message_reactions),
+                        attachments: attachments_of(&attachments, hit.message.id),
+                        ..hit.message.into()
+                    }),
+                    snippet: hit.snippet,
+                    rank: hit.rank,
+                }
+            })
+            .collect();
+
+        Ok(Response::new(SearchMessagesResponse { hits, has_more }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn get_mentions(
+        &self,
+        request: Request<GetMentionsRequest>,
+    ) -> Result<Response<Messages>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let mentions_request = request.into_inner();
+
+        let limit = match mentions_request.limit {
+            0 => DEFAULT_MENTIONS_PAGE_SIZE,
+            limit => limit.min(MAX_MENTIONS_PAGE_SIZE),
+        } as usize;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        // Mentions stay in the feed while the user is in the chat and the message is there.
+        let my_chats = users_chats::table
+            .filter(users_chats::user_id.eq(&user_id))
+            .select(users_chats::chat_id);
+        let mut query = mentions::table
+            .inner_join(messages::table)
+            .filter(mentions::mentioned_user_id.eq(&user_id))
+            .filter(mentions::chat_id.eq_any(my_chats))
+            .filter(messages::deleted_at.is_null())
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(&user_id)),
+            ))
+            .select(Message::as_select())
+            .order(mentions::message_id.desc())
+            .limit(limit as i64 + 1)
+            .into_boxed();
+
+        if let Some(before_id) = mentions_request.before_id {
+            query = query.filter(mentions::message_id.lt(before_id));
+        }
+
+        let mut mentioned_messages = query.load::<Message>(&mut connection).map_err(|e| {
+            error!("Failed to get mentions: {}", e);
+            Status::internal("Failed to get mentions")
+        })?;
+
+        let has_more = mentioned_messages.len() > limit;
+        mentioned_messages.truncate(limit);
+        mentioned_messages.reverse();
+
+        Ok(Response::new(Messages {
+            messages: self
+                .to_proto_messages(&mut connection, mentioned_messages)
+                .await?,
+            has_more,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn edit_message(
+        &self,
+        request: Request<EditMessageRequest>,
+    ) -> Result<Response<GMessage>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let edit_request = request.into_inner();
+
+        if edit_request.text.trim().is_empty() {
+            return Err(Status::invalid_argument("Message text can't be empty"));
+        }
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        self.get_own_message(&mut connection, edit_request.message_id, &user_id)
+            .await?;
+
+        let message = diesel::update(messages::table)
+            .filter(messages::id.eq(edit_request.message_id))
+            .set((
+                messages::text.eq(edit_request.text),
+                messages::edited_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .get_result::<Message>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to edit message: {}", e);
+                Status::internal("Failed to edit message")
+            })?;
+
+        debug!("Message {} edited", message.id);
+        self.publish_chat_event(
+            message.chat_id,
+            &RabbitChatEvent::MessageEdited(message.clone()),
+        )
+        .await?;
+
+        Ok(Response::new(message.into()))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn delete_message(
+        &self,
+        request: Request<DeleteMessageRequest>,
+    ) -> Result<Response<DeleteMessageResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let message_id = request.into_inner().message_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        self.get_own_message(&mut connection, message_id, &user_id)
+            .await?;
+
+        let message = connection
+            .transaction(|connection| {
+                // A deleted message doesn't stay pinned, clients drop the pin on the delete event.
+                diesel::delete(pinned_messages::table)
+                    .filter(pinned_messages::message_id.eq(message_id))
+                    .execute(connection)?;
+
+                diesel::update(messages::table)
+                    .filter(messages::id.eq(message_id))
+                    .set((
+                        messages::text.eq(""),
+                        messages::deleted_at.eq(chrono::Utc::now().naive_utc()),
+                    ))
+                    .get_result::<Message>(connection)
+            })
+            .map_err(|e| {
+                error!("Failed to delete message: {}", e);
+                Status::internal("Failed to delete message")
+            })?;
+
+        debug!("Message {} deleted", message.id);
+        self.publish_chat_event(message.chat_id, &RabbitChatEvent::MessageDeleted(message))
+            .await?;
+
+        Ok(Response::new(DeleteMessageResponse { success: true }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn get_thread(
+        &self,
+        request: Request<GetThreadRequest>,
+    ) -> Result<Response<Thread>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let message_id = request.into_inner().message_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let message = messages::table
+            .filter(messages::id.eq(message_id))
+            .first::<Message>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?
+            .ok_or_else(|| {
+                Status::not_found(format!("No message found with id: {}", message_id))
+            })?;
+
+        self.check_membership(&mut connection, &user_id, message.chat_id)
+            .await?;
+
+        let parent = match message.reply_to_message_id {
+            Some(parent_id) => messages::table
+                .filter(messages::id.eq(parent_id))
+                .first::<Message>(&mut connection)
+                .map_err(|e| {
+                    error!("Failed to get parent message: {}", e);
+                    Status::internal("Failed to get parent message")
+                })?,
+            None => message,
+        };
+
+        let replies = messages::table
+            .filter(messages::reply_to_message_id.eq(parent.id))
+            .filter(diesel::dsl::not(
+                messages::user_id.eq_any(blocked_by(&user_id)),
+            ))
+            .order((messages::created_at.asc(), messages::id.asc()))
+            .load::<Message>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get replies: {}", e);
+                Status::internal("Failed to get replies")
+            })?;
+
+        debug!(
+            "Thread of message {} has {} replies",
+            parent.id,
+            replies.len()
+        );
+        let mut thread_messages = self
+            .to_proto_messages(
+                &mut connection,
+                std::iter::once(parent).chain(replies).collect(),
+            )
+            .await?;
+        let replies = thread_messages.split_off(1);
+        Ok(Response::new(Thread {
+            parent: thread_messages.pop(),
+            replies,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn add_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let reaction_request = request.into_inner();
+
+        self.publish_reaction_change(&RabbitReactionChange {
+            message_id: reaction_request.message_id,
+            user_id,
+            emoji: reaction_request.emoji,
+            added: true,
+        })
+        .await?;
+
+        Ok(Response::new(ReactionResponse { success: true }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn remove_reaction(
+        &self,
+        request: Request<ReactionRequest>,
+    ) -> Result<Response<ReactionResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let reaction_request = request.into_inner();
+
+        self.publish_reaction_change(&RabbitReactionChange {
+            message_id: reaction_request.message_id,
+            user_id,
+            emoji: reaction_request.emoji,
+            added: false,
+        })
+        .await?;
+
+        Ok(Response::new(ReactionResponse { success: true }))
+    }
 }
 
 module! {
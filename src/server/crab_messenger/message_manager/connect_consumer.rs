@@ -1,17 +1,84 @@
-use crate::utils::rabbit_declares::{declare_messages_exchange, MESSAGES_EXCHANGE, messages_exchange_name};
-use amqprs::channel::{BasicAckArguments, BasicRejectArguments, Channel, QueueBindArguments};
+use crate::utils::messenger::chat_event::Event;
+use crate::utils::messenger::ChatEvent;
+use crate::utils::persistence::message::Message;
+use crate::utils::rabbit_declares::{declare_messages_exchange, messages_exchange_name};
+use crate::utils::rabbit_types::RabbitChatConnect;
+use amqprs::channel::{
+    BasicAckArguments, BasicRejectArguments, Channel, QueueBindArguments, QueueUnbindArguments,
+};
 use amqprs::consumer::AsyncConsumer;
 use amqprs::{BasicProperties, Deliver};
 use async_trait::async_trait;
-use tracing::error;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use tonic::Status;
+use tracing::{debug, error};
 
 pub struct ConnectConsumer {
+    tx: mpsc::Sender<Result<ChatEvent, Status>>,
     queue_name: String,
+    /// Shared with the stream's message consumer, which skips the events of these users.
+    blocked_user_ids: Arc<RwLock<HashSet<String>>>,
 }
 
 impl ConnectConsumer {
-    pub fn new(queue_name: String) -> Self {
-        Self { queue_name }
+    pub fn new(
+        tx: mpsc::Sender<Result<ChatEvent, Status>>,
+        queue_name: String,
+        blocked_user_ids: Arc<RwLock<HashSet<String>>>,
+    ) -> Self {
+        Self {
+            tx,
+            queue_name,
+            blocked_user_ids,
+        }
+    }
+
+    fn set_blocked(&self, user_id: String, blocked: bool) {
+        let mut blocked_user_ids = self.blocked_user_ids.write().unwrap();
+        if blocked {
+            blocked_user_ids.insert(user_id);
+        } else {
+            blocked_user_ids.remove(&user_id);
+        }
+    }
+
+    async fn forward_mention(&self, message: Message) -> Result<(), amqprs::error::Error> {
+        let chat_event = ChatEvent {
+            event: Some(Event::Mentioned(message.into())),
+        };
+
+        // A closed stream is cleaned up by the message consumer.
+        if let Err(e) = self.tx.send(Ok(chat_event)).await {
+            debug!("Failed to forward mention: {:?}", e);
+        }
+        Ok(())
+    }
+
+    async fn connect(&self, channel: &Channel, chat_id: &str) -> Result<(), amqprs::error::Error> {
+        declare_messages_exchange(channel, chat_id).await?;
+        channel
+            .queue_bind(
+                QueueBindArguments::new(&self.queue_name, &messages_exchange_name(chat_id), "")
+                    .finish(),
+            )
+            .await
+    }
+
+    async fn disconnect(
+        &self,
+        channel: &Channel,
+        chat_id: &str,
+    ) -> Result<(), amqprs::error::Error> {
+        debug!("Unbinding {} from chat {}", self.queue_name, chat_id);
+        channel
+            .queue_unbind(QueueUnbindArguments::new(
+                &self.queue_name,
+                &messages_exchange_name(chat_id),
+                "",
+            ))
+            .await
     }
 }
 
@@ -24,14 +91,36 @@ impl AsyncConsumer for ConnectConsumer {
         basic_properties: BasicProperties,
         content: Vec<u8>,
     ) {
-        let user_id = String::from_utf8(content).unwrap();
+        let result = match serde_json::from_slice::<RabbitChatConnect>(&content) {
+            Ok(RabbitChatConnect::Connect(chat_id)) => {
+                self.connect(channel, &chat_id.to_string()).await
+            }
+            Ok(RabbitChatConnect::Disconnect(chat_id)) => {
+                self.disconnect(channel, &chat_id.to_string()).await
+            }
+            Ok(RabbitChatConnect::Block(user_id)) => {
+                self.set_blocked(user_id, true);
+                Ok(())
+            }
+            Ok(RabbitChatConnect::Unblock(user_id)) => {
+                self.set_blocked(user_id, false);
+                Ok(())
+            }
+            Ok(RabbitChatConnect::Mention(message)) => self.forward_mention(message).await,
+            Err(e) => {
+                error!("Failed to deserialize connect command: {:?}", e);
+                let _ = channel
+                    .basic_reject(BasicRejectArguments::new(deliver.delivery_tag(), false))
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to reject message: {:?}", e);
+                    });
+                return;
+            }
+        };
 
-        if let Err(e) = declare_messages_exchange(channel, &user_id)
-            .await
-            .map_err(|e| {
-                error!("Failed to declare exchange: {:?}", e);
-            })
-        {
+        if let Err(e) = result {
+            error!("Failed to apply connect command: {:?}", e);
             let _ = channel
                 .basic_reject(BasicRejectArguments::new(deliver.delivery_tag(), false))
                 .await
@@ -40,20 +129,6 @@ impl AsyncConsumer for ConnectConsumer {
                 });
             return;
         }
-        if let Err(e) = channel
-            .queue_bind(QueueBindArguments::new(&self.queue_name, &messages_exchange_name(&user_id), "").finish())
-            .await
-            .map_err(|e| {
-                error!("Failed to bind queue: {:?}", e);
-            })
-        {
-            let _ = channel
-                .basic_reject(BasicRejectArguments::new(deliver.delivery_tag(), false))
-                .await
-                .map_err(|e| {
-                    error!("Failed to reject message: {:?}", e);
-                });
-        }
 
         if let Err(e) = channel
             .basic_ack(BasicAckArguments::new(deliver.delivery_tag(), false))
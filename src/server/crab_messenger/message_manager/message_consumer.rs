@@ -3,21 +3,40 @@ use amqprs::consumer::AsyncConsumer;
 use amqprs::{BasicProperties, Deliver};
 use async_trait::async_trait;
 use scopeguard::defer;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
 use tokio::sync::mpsc;
 use tonic::Status;
 use tracing::{debug, error, info};
 
-use crate::utils::messenger::Message as GMessage;
-use crate::utils::persistence::message::Message as DBMessage;
+use crate::utils::messenger::ChatEvent;
+use crate::utils::rabbit_types::RabbitChatEvent;
 
 pub struct RabbitConsumer {
-    tx: mpsc::Sender<Result<GMessage, Status>>,
+    tx: mpsc::Sender<Result<ChatEvent, Status>>,
     queue_name: String,
+    user_id: String,
+    /// Users blocked by the stream's user, kept up to date by the connect consumer.
+    blocked_user_ids: Arc<RwLock<HashSet<String>>>,
 }
 
 impl RabbitConsumer {
-    pub fn new(tx: mpsc::Sender<Result<GMessage, Status>>, queue_name: String) -> Self {
-        Self { tx, queue_name }
+    pub fn new(
+        tx: mpsc::Sender<Result<ChatEvent, Status>>,
+        queue_name: String,
+        user_id: String,
+        blocked_user_ids: Arc<RwLock<HashSet<String>>>,
+    ) -> Self {
+        Self {
+            tx,
+            queue_name,
+            user_id,
+            blocked_user_ids,
+        }
+    }
+
+    fn is_blocked(&self, user_id: &str) -> bool {
+        self.blocked_user_ids.read().unwrap().contains(user_id)
     }
 }
 
@@ -32,18 +51,27 @@ impl AsyncConsumer for RabbitConsumer {
         content: Vec<u8>,
     ) {
         debug!("Sending message to user");
-        let db_message: DBMessage = match serde_json::from_slice(&content) {
-            Ok(msg) => msg,
+        let rabbit_event: RabbitChatEvent = match serde_json::from_slice(&content) {
+            Ok(event) => event,
             Err(e) => {
-                error!("Failed to deserialize message: {:?}", e);
+                error!("Failed to deserialize chat event: {:?}", e);
                 return;
             }
         };
 
-        let grpc_message = db_message.into();
+        if rabbit_event.typing_user_id() == Some(self.user_id.as_str()) {
+            debug!("Skipping own typing event");
+        } else if rabbit_event
+            .author_user_id()
+            .is_some_and(|author| self.is_blocked(author))
+        {
+            debug!("Skipping event of a blocked user");
+        } else {
+            let chat_event = rabbit_event.into();
 
-        let send_result = self.tx.send(Ok(grpc_message)).await;
-        debug!("Send result: {:?}", send_result);
+            let send_result = self.tx.send(Ok(chat_event)).await;
+            debug!("Send result: {:?}", send_result);
+        }
 
         if let Err(e) = channel
             .basic_ack(BasicAckArguments::new(deliver.delivery_tag(), false))
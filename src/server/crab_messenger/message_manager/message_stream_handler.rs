@@ -1,29 +1,73 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use amqprs::channel::{BasicPublishArguments, Channel};
 use amqprs::BasicProperties;
 use async_trait::async_trait;
+use diesel::prelude::*;
 use shaku::{module, Component, Interface};
-use tonic::Streaming;
-use tracing::{debug, error, info};
+use tokio::sync::mpsc;
+use tonic::{Status, Streaming};
+use tracing::{debug, error, info, warn};
 
-use crate::utils::messenger::SendMessage;
-use crate::utils::persistence::message::InsertMessage;
-use crate::utils::rabbit_declares::NEW_MESSAGE_EXCHANGE;
+use crate::server::crab_messenger::presence_manager::{
+    build_presence_manager_module, PresenceManager, PresenceManagerModule,
+};
+use crate::utils::db_connection_manager::{
+    build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
+};
+use crate::utils::messenger::chat_event::Event;
+use crate::utils::messenger::chat_request::Request as ChatRequestKind;
+use crate::utils::messenger::{ChatEvent, ChatRequest, Message as GMessage, SendMessage};
+use crate::utils::persistence::attachment::{attachments_of, Attachment};
+use crate::utils::persistence::message::{InsertMessage, Message};
+use crate::utils::persistence::message_reaction::{aggregate_reactions, MessageReaction};
+use crate::utils::persistence::schema::{attachments, message_reactions, messages, users_chats};
+use crate::utils::persistence::user_block::blocked_by;
+use crate::utils::persistence::users_chats::UsersChats;
+use crate::utils::rabbit_declares::{
+    declare_messages_exchange, messages_exchange_name, NEW_MESSAGE_EXCHANGE,
+};
+use crate::utils::rabbit_types::{RabbitChatEvent, RabbitNewMessage};
+
+/// Minimal interval between two activity updates of the same stream.
+const ACTIVITY_THROTTLE: Duration = Duration::from_secs(60);
+
+/// Messages loaded per query on resume, batches are replayed until the chat is caught up.
+const REPLAY_BATCH_SIZE: i64 = 500;
 
 #[async_trait]
 pub trait MessageStreamHandler: Interface {
     async fn handle_stream(
         &self,
-        stream: Streaming<SendMessage>,
+        stream: Streaming<ChatRequest>,
         channel: &Channel,
+        tx: mpsc::Sender<Result<ChatEvent, Status>>,
         user_id: String,
+        can_write: bool,
     ) -> Result<(), anyhow::Error>;
 }
 
 #[derive(Component)]
 #[shaku(interface = MessageStreamHandler)]
-pub struct MessageStreamHandlerImpl;
+pub struct MessageStreamHandlerImpl {
+    #[shaku(inject)]
+    db_connection_manager: Arc<dyn DBConnectionManager>,
+    #[shaku(inject)]
+    presence_manager: Arc<dyn PresenceManager>,
+}
+
+/// Per stream bookkeeping of typing notifications.
+#[derive(Default)]
+struct TypingState {
+    /// Chats whose exchange was declared on the stream channel.
+    declared_chats: HashSet<i32>,
+    /// Chats the user is currently typing in.
+    typing_in: HashSet<i32>,
+    /// Last time the user activity was recorded.
+    last_activity: Option<Instant>,
+}
 
 impl MessageStreamHandlerImpl {
     async fn publish_message(
@@ -48,44 +92,317 @@ impl MessageStreamHandlerImpl {
 
         Ok(())
     }
+
+    async fn send_message(
+        &self,
+        channel: &Channel,
+        send_msg: SendMessage,
+        user_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        let new_message = RabbitNewMessage {
+            message: InsertMessage {
+                user_id: user_id.to_string(),
+                text: send_msg.text,
+                chat_id: send_msg.chat_id,
+                reply_to_message_id: send_msg.reply_to_message_id,
+            },
+            attachment_ids: send_msg.attachment_ids,
+        };
+
+        let serialized_message = serde_json::to_string(&new_message).map_err(anyhow::Error::new)?;
+
+        self.publish_message(channel, serialized_message).await?;
+        debug!("Message published successfully");
+        Ok(())
+    }
+
+    /// Typing notifications skip the worker and go straight to the chat exchange, so
+    /// membership is checked here, on every event since the user can leave or be removed
+    /// while the stream is open.
+    async fn verify_chat(
+        &self,
+        channel: &Channel,
+        typing_state: &mut TypingState,
+        chat_id: i32,
+        user_id: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let mut connection = self.db_connection_manager.get_connection()?;
+        let binding = users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .filter(users_chats::chat_id.eq(chat_id))
+            .first::<UsersChats>(&mut connection)
+            .optional()?;
+
+        if binding.is_none() {
+            warn!("User {} is not a member of chat {}", user_id, chat_id);
+            typing_state.typing_in.remove(&chat_id);
+            return Ok(false);
+        }
+
+        if !typing_state.declared_chats.contains(&chat_id) {
+            declare_messages_exchange(channel, &chat_id.to_string()).await?;
+            typing_state.declared_chats.insert(chat_id);
+        }
+        Ok(true)
+    }
+
+    async fn publish_typing(
+        &self,
+        channel: &Channel,
+        typing_state: &mut TypingState,
+        chat_id: i32,
+        user_id: &str,
+        started: bool,
+    ) -> Result<(), anyhow::Error> {
+        if !self
+            .verify_chat(channel, typing_state, chat_id, user_id)
+            .await?
+        {
+            return Ok(());
+        }
+
+        let event = if started {
+            typing_state.typing_in.insert(chat_id);
+            RabbitChatEvent::TypingStarted {
+                chat_id,
+                user_id: user_id.to_string(),
+            }
+        } else {
+            typing_state.typing_in.remove(&chat_id);
+            RabbitChatEvent::TypingStopped {
+                chat_id,
+                user_id: user_id.to_string(),
+            }
+        };
+
+        let serialized_event = serde_json::to_string(&event)?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serialized_event.into_bytes(),
+                BasicPublishArguments::new(&messages_exchange_name(&chat_id.to_string()), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to publish typing event: {:?}", e);
+                anyhow::Error::new(e)
+            })?;
+
+        debug!("Typing event published successfully");
+        Ok(())
+    }
+
+    /// Sends the messages the user missed in each chat since its watermark, batch by batch
+    /// until none are left. Live events keep flowing meanwhile, the client orders and
+    /// deduplicates messages by id.
+    async fn replay_missed(
+        &self,
+        channel: &Channel,
+        typing_state: &mut TypingState,
+        tx: &mpsc::Sender<Result<ChatEvent, Status>>,
+        resume_from: HashMap<i32, i32>,
+        user_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        for (chat_id, last_seen_id) in resume_from {
+            if !self
+                .verify_chat(channel, typing_state, chat_id, user_id)
+                .await?
+            {
+                continue;
+            }
+
+            let mut last_replayed_id = last_seen_id;
+            loop {
+                let mut connection = self.db_connection_manager.get_connection()?;
+                let missed = messages::table
+                    .filter(messages::chat_id.eq(chat_id))
+                    .filter(messages::id.gt(last_replayed_id))
+                    .filter(diesel::dsl::not(
+                        messages::user_id.eq_any(blocked_by(user_id)),
+                    ))
+                    .order(messages::id.asc())
+                    .limit(REPLAY_BATCH_SIZE)
+                    .load::<Message>(&mut connection)?;
+                let caught_up = (missed.len() as i64) < REPLAY_BATCH_SIZE;
+                let reactions = message_reactions::table
+                    .filter(message_reactions::message_id.eq_any(missed.iter().map(|m| m.id)))
+                    .order(message_reactions::created_at.asc())
+                    .load::<MessageReaction>(&mut connection)?;
+                let missed_attachments = attachments::table
+                    .filter(attachments::message_id.eq_any(missed.iter().map(|m| m.id)))
+                    .order(attachments::id.asc())
+                    .load::<Attachment>(&mut connection)?;
+                // The connection goes back to the pool while the batch is sent to the client.
+                drop(connection);
+
+                debug!("Replaying {} messages of chat {}", missed.len(), chat_id);
+                for message in missed {
+                    last_replayed_id = message.id;
+                    let message_reactions: Vec<_> = reactions
+                        .iter()
+                        .filter(|reaction| reaction.message_id == message.id)
+                        .cloned()
+                        .collect();
+                    let event = ChatEvent {
+                        event: Some(Event::NewMessage(GMessage {
+                            reactions: aggregate_reactions(&message_reactions),
+                            attachments: attachments_of(&missed_attachments, message.id),
+                            ..message.into()
+                        })),
+                    };
+
+                    tx.send(Ok(event)).await?;
+                }
+
+                if caught_up {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn handle_request(
+        &self,
+        channel: &Channel,
+        typing_state: &mut TypingState,
+        tx: &mpsc::Sender<Result<ChatEvent, Status>>,
+        chat_request: ChatRequest,
+        user_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        match chat_request.request {
+            Some(ChatRequestKind::Message(send_msg)) => {
+                // Sending a message ends the typing notification in that chat.
+                if typing_state.typing_in.contains(&send_msg.chat_id) {
+                    self.publish_typing(channel, typing_state, send_msg.chat_id, user_id, false)
+                        .await?;
+                }
+                self.send_message(channel, send_msg, user_id).await
+            }
+            Some(ChatRequestKind::TypingStart(typing)) => {
+                self.publish_typing(channel, typing_state, typing.chat_id, user_id, true)
+                    .await
+            }
+            Some(ChatRequestKind::TypingStop(typing)) => {
+                self.publish_typing(channel, typing_state, typing.chat_id, user_id, false)
+                    .await
+            }
+            Some(ChatRequestKind::Resume(resume)) => {
+                self.replay_missed(channel, typing_state, tx, resume.resume_from, user_id)
+                    .await
+            }
+            None => {
+                warn!("Received empty chat request");
+                Ok(())
+            }
+        }
+    }
+
+    async fn record_activity(&self, typing_state: &mut TypingState, user_id: &str) {
+        if typing_state
+            .last_activity
+            .is_some_and(|at| at.elapsed() < ACTIVITY_THROTTLE)
+        {
+            return;
+        }
+
+        typing_state.last_activity = Some(Instant::now());
+        if let Err(e) = self.presence_manager.user_active(user_id).await {
+            error!("Failed to record activity: {:?}", e);
+        }
+    }
+
+    async fn stop_typing_everywhere(
+        &self,
+        channel: &Channel,
+        typing_state: &mut TypingState,
+        user_id: &str,
+    ) {
+        let chats: Vec<i32> = typing_state.typing_in.iter().copied().collect();
+        for chat_id in chats {
+            if let Err(e) = self
+                .publish_typing(channel, typing_state, chat_id, user_id, false)
+                .await
+            {
+                error!("Failed to stop typing in chat {}: {:?}", chat_id, e);
+            }
+        }
+    }
 }
 
 #[async_trait]
 impl MessageStreamHandler for MessageStreamHandlerImpl {
-    #[tracing::instrument(skip(self, stream, channel))]
+    #[tracing::instrument(skip(self, stream, channel, tx))]
     async fn handle_stream(
         &self,
-        mut stream: Streaming<SendMessage>,
+        mut stream: Streaming<ChatRequest>,
         channel: &Channel,
+        tx: mpsc::Sender<Result<ChatEvent, Status>>,
         user_id: String,
+        can_write: bool,
     ) -> Result<(), anyhow::Error> {
-        loop {
-            let message_result = stream.message().await;
-            match message_result {
-                Ok(Some(send_msg)) => {
-                    let insert_message = InsertMessage {
-                        user_id: user_id.clone(),
-                        text: send_msg.text,
-                        chat_id: send_msg.chat_id,
-                    };
+        let mut typing_state = TypingState {
+            last_activity: Some(Instant::now()),
+            ..Default::default()
+        };
 
-                    let serialized_message =
-                        serde_json::to_string(&insert_message).map_err(anyhow::Error::new)?;
+        if let Err(e) = self.presence_manager.user_connected(&user_id).await {
+            error!("Failed to record connection: {:?}", e);
+        }
+
+        let result = loop {
+            let request_result = stream.message().await;
+            match request_result {
+                Ok(Some(chat_request)) => {
+                    // Resuming only replays events, everything else acts on behalf of the user.
+                    let permitted = can_write
+                        || matches!(
+                            chat_request.request,
+                            Some(ChatRequestKind::Resume(_)) | None
+                        );
+                    if !permitted {
+                        warn!("Request not allowed by the api key scopes");
+                        // The client may be gone already, the stream is cleaned up either way.
+                        if let Err(e) = tx
+                            .send(Err(Status::permission_denied(
+                                "The api key doesn't allow this request",
+                            )))
+                            .await
+                        {
+                            warn!("Failed to reject request: {:?}", e);
+                        }
+                        break Ok(());
+                    }
 
-                    self.publish_message(channel, serialized_message).await?;
-                    debug!("Message published successfully");
+                    self.record_activity(&mut typing_state, &user_id).await;
+                    if let Err(e) = self
+                        .handle_request(channel, &mut typing_state, &tx, chat_request, &user_id)
+                        .await
+                    {
+                        break Err(e);
+                    }
                 }
                 Ok(None) => {
                     info!("Stream closed by sender");
-                    break;
+                    break Ok(());
                 }
                 Err(e) => {
                     error!("Stream error: {:?}", e);
-                    return Err(anyhow::Error::new(e));
+                    break Err(anyhow::Error::new(e));
                 }
             }
+        };
+
+        self.stop_typing_everywhere(channel, &mut typing_state, &user_id)
+            .await;
+        if let Err(e) = self.presence_manager.user_disconnected(&user_id).await {
+            error!("Failed to record disconnection: {:?}", e);
         }
-        Ok(())
+        result
     }
 }
 
@@ -93,9 +410,23 @@ module! {
     pub MessageStreamHandlerModule {
         components = [MessageStreamHandlerImpl],
         providers = [],
+        use DBConnectionManagerModule{
+            components = [dyn DBConnectionManager],
+            providers = [],
+        },
+        use PresenceManagerModule{
+            components = [dyn PresenceManager],
+            providers = [],
+        },
     }
 }
 
 pub fn build_message_stream_handler_module() -> Arc<MessageStreamHandlerModule> {
-    Arc::new(MessageStreamHandlerModule::builder().build())
+    Arc::new(
+        MessageStreamHandlerModule::builder(
+            build_db_connection_manager_module(),
+            build_presence_manager_module(),
+        )
+        .build(),
+    )
 }
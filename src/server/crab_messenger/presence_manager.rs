@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use amqprs::channel::{
+    BasicConsumeArguments, BasicPublishArguments, Channel, QueueBindArguments,
+    QueueDeclareArguments,
+};
+use amqprs::BasicProperties;
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use shaku::{module, Component, Interface};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{debug, error, info};
+
+use crate::server::authenticated_user::AuthenticatedRequest;
+use crate::server::crab_messenger::presence_manager::presence_consumer::PresenceConsumer;
+use crate::server::crab_messenger::PresenceResponseStream;
+use crate::utils::db_connection_manager::{
+    build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
+};
+use crate::utils::generate_random_string;
+use crate::utils::messenger::{
+    GetPresenceRequest, Presence as GPresence, PresenceStatus, PresenceUpdatesRequest, Presences,
+};
+use crate::utils::persistence::schema::{user_presence, users_chats};
+use crate::utils::persistence::user_presence::UserPresence;
+use crate::utils::rabbit_channel_manager::{
+    build_channel_manager_module, ChannelManager, ChannelManagerModule,
+};
+use crate::utils::rabbit_declares::{declare_presence_exchange, presence_exchange_name};
+
+mod presence_consumer;
+
+#[async_trait]
+pub trait PresenceManager: Interface {
+    async fn get_presence(
+        &self,
+        request: Request<GetPresenceRequest>,
+    ) -> Result<Response<Presences>, Status>;
+
+    async fn presence_updates(
+        &self,
+        request: Request<PresenceUpdatesRequest>,
+    ) -> Result<Response<PresenceResponseStream>, Status>;
+
+    async fn user_connected(&self, user_id: &str) -> Result<(), anyhow::Error>;
+
+    async fn user_disconnected(&self, user_id: &str) -> Result<(), anyhow::Error>;
+
+    async fn user_active(&self, user_id: &str) -> Result<(), anyhow::Error>;
+}
+
+#[derive(Component)]
+#[shaku(interface = PresenceManager)]
+pub struct PresenceManagerImpl {
+    #[shaku(inject)]
+    db_connection_manager: Arc<dyn DBConnectionManager>,
+    #[shaku(inject)]
+    channel_manager: Arc<dyn ChannelManager>,
+}
+
+impl PresenceManagerImpl {
+    /// Users sharing at least one chat with the given user, the user included.
+    fn related_user_ids(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+    ) -> QueryResult<Vec<String>> {
+        let my_chats = users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .select(users_chats::chat_id)
+            .load::<i32>(connection)?;
+
+        let mut user_ids = users_chats::table
+            .filter(users_chats::chat_id.eq_any(my_chats))
+            .select(users_chats::user_id)
+            .distinct()
+            .load::<String>(connection)?;
+
+        if !user_ids.iter().any(|id| id == user_id) {
+            user_ids.push(user_id.to_string());
+        }
+
+        Ok(user_ids)
+    }
+
+    async fn publish_presence(
+        &self,
+        connection: &mut PgConnection,
+        presence: &UserPresence,
+    ) -> Result<(), anyhow::Error> {
+        let serialized_presence = serde_json::to_string(presence)?;
+        let user_ids = self.related_user_ids(connection, &presence.user_id)?;
+        let channel = self.channel_manager.get_channel().await?;
+
+        for user_id in user_ids {
+            declare_presence_exchange(&channel, &user_id).await?;
+            channel
+                .basic_publish(
+                    BasicProperties::default(),
+                    serialized_presence.clone().into_bytes(),
+                    BasicPublishArguments::new(&presence_exchange_name(&user_id), "")
+                        .mandatory(false)
+                        .immediate(false)
+                        .finish(),
+                )
+                .await?;
+        }
+
+        debug!("Presence of {} published", presence.user_id);
+        Ok(())
+    }
+
+    async fn setup_queue(
+        &self,
+        channel: &Channel,
+        queue_name: &str,
+        user_id: &str,
+    ) -> Result<(), Status> {
+        declare_presence_exchange(channel, user_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to declare exchange: {:?}", e);
+                Status::internal("Failed to declare exchange")
+            })?;
+
+        channel
+            .queue_declare(
+                QueueDeclareArguments::new(queue_name)
+                    .auto_delete(true)
+                    .durable(false)
+                    .finish(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to declare queue: {:?}", e);
+                Status::internal("Failed to declare queue")
+            })?;
+
+        channel
+            .queue_bind(
+                QueueBindArguments::new(queue_name, &presence_exchange_name(user_id), "").finish(),
+            )
+            .await
+            .map_err(|e| {
+                error!("Failed to bind queue: {:?}", e);
+                Status::internal("Failed to bind queue")
+            })?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PresenceManager for PresenceManagerImpl {
+    #[tracing::instrument(skip(self, request), err)]
+    async fn get_presence(
+        &self,
+        request: Request<GetPresenceRequest>,
+    ) -> Result<Response<Presences>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let requested_ids = request.into_inner().user_ids;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let related_ids = self
+            .related_user_ids(&mut connection, &user_id)
+            .map_err(|e| {
+                error!("Failed to get related users: {}", e);
+                Status::internal("Failed to get related users")
+            })?;
+
+        // Presence is only visible to users sharing a chat.
+        let user_ids: Vec<String> = if requested_ids.is_empty() {
+            related_ids
+        } else {
+            requested_ids
+                .into_iter()
+                .filter(|id| related_ids.contains(id))
+                .collect()
+        };
+
+        let mut stored = user_presence::table
+            .filter(user_presence::user_id.eq_any(&user_ids))
+            .load::<UserPresence>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get presence: {}", e);
+                Status::internal("Failed to get presence")
+            })?;
+
+        let presences = user_ids
+            .into_iter()
+            .map(|id| match stored.iter().position(|p| p.user_id == id) {
+                Some(index) => stored.swap_remove(index).into(),
+                None => GPresence {
+                    user_id: id,
+                    status: PresenceStatus::Offline as i32,
+                    last_seen_at: None,
+                    last_active_at: None,
+                },
+            })
+            .collect();
+
+        Ok(Response::new(Presences { presences }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn presence_updates(
+        &self,
+        request: Request<PresenceUpdatesRequest>,
+    ) -> Result<Response<PresenceResponseStream>, Status> {
+        info!("Starting presence updates");
+        let user_id = request.authenticated_user()?.id.clone();
+        let (tx, rx) = mpsc::channel(16);
+
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {:?}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        let queue_name = generate_random_string(16);
+        self.setup_queue(&channel, &queue_name, &user_id).await?;
+
+        let (tag, message_rx) = channel
+            .basic_consume_rx(BasicConsumeArguments::new(&queue_name, "").finish())
+            .await
+            .map_err(|e| {
+                error!("Failed to consume: {:?}", e);
+                Status::internal("Failed to consume presence updates")
+            })?;
+
+        let mut consumer = PresenceConsumer::new(tx);
+        tokio::spawn(async move {
+            consumer.consume(&channel, tag, message_rx).await;
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn user_connected(&self, user_id: &str) -> Result<(), anyhow::Error> {
+        let mut connection = self.db_connection_manager.get_connection()?;
+        let now = chrono::Utc::now().naive_utc();
+
+        let presence = diesel::insert_into(user_presence::table)
+            .values(UserPresence {
+                user_id: user_id.to_string(),
+                connections: 1,
+                last_seen_at: now,
+                last_active_at: now,
+            })
+            .on_conflict(user_presence::user_id)
+            .do_update()
+            .set((
+                user_presence::connections.eq(user_presence::connections + 1),
+                user_presence::last_seen_at.eq(now),
+                user_presence::last_active_at.eq(now),
+            ))
+            .get_result::<UserPresence>(&mut connection)?;
+
+        debug!("User {} has {} connections", user_id, presence.connections);
+        self.publish_presence(&mut connection, &presence).await
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn user_disconnected(&self, user_id: &str) -> Result<(), anyhow::Error> {
+        let mut connection = self.db_connection_manager.get_connection()?;
+        let now = chrono::Utc::now().naive_utc();
+
+        let presence = diesel::update(user_presence::table)
+            .filter(user_presence::user_id.eq(user_id))
+            .filter(user_presence::connections.gt(0))
+            .set((
+                user_presence::connections.eq(user_presence::connections - 1),
+                user_presence::last_seen_at.eq(now),
+            ))
+            .get_result::<UserPresence>(&mut connection)
+            .optional()?;
+
+        match presence {
+            // Other streams of the same user keep it online.
+            Some(presence) if presence.connections == 0 => {
+                self.publish_presence(&mut connection, &presence).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    async fn user_active(&self, user_id: &str) -> Result<(), anyhow::Error> {
+        let mut connection = self.db_connection_manager.get_connection()?;
+        let now = chrono::Utc::now().naive_utc();
+
+        let previous = user_presence::table
+            .filter(user_presence::user_id.eq(user_id))
+            .first::<UserPresence>(&mut connection)
+            .optional()?;
+
+        let presence = diesel::update(user_presence::table)
+            .filter(user_presence::user_id.eq(user_id))
+            .set((
+                user_presence::last_seen_at.eq(now),
+                user_presence::last_active_at.eq(now),
+            ))
+            .get_result::<UserPresence>(&mut connection)
+            .optional()?;
+
+        match (previous, presence) {
+            (Some(previous), Some(presence)) if previous.status(now) != presence.status(now) => {
+                self.publish_presence(&mut connection, &presence).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+module! {
+    pub PresenceManagerModule {
+        components = [PresenceManagerImpl],
+        providers = [],
+        use DBConnectionManagerModule {
+            components = [dyn DBConnectionManager],
+            providers = [],
+        },
+        use ChannelManagerModule {
+            components = [dyn ChannelManager],
+            providers = [],
+        },
+    }
+}
+
+pub fn build_presence_manager_module() -> Arc<PresenceManagerModule> {
+    Arc::new(
+        PresenceManagerModule::builder(
+            build_db_connection_manager_module(),
+            build_channel_manager_module(),
+        )
+        .build(),
+    )
+}
@@ -0,0 +1,66 @@
+use amqprs::channel::{BasicAckArguments, BasicCancelArguments, Channel, ConsumerMessage};
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tonic::Status;
+use tracing::{debug, error, info, instrument};
+
+use crate::utils::messenger::Presence;
+use crate::utils::persistence::user_presence::UserPresence;
+
+pub struct PresenceConsumer {
+    tx: mpsc::Sender<Result<Presence, Status>>,
+}
+
+impl PresenceConsumer {
+    pub fn new(tx: mpsc::Sender<Result<Presence, Status>>) -> Self {
+        Self { tx }
+    }
+
+    #[instrument(skip(self, channel, message_rx))]
+    pub async fn consume(
+        &mut self,
+        channel: &Channel,
+        tag: String,
+        mut message_rx: UnboundedReceiver<ConsumerMessage>,
+    ) {
+        loop {
+            select! {
+                Some(message) = message_rx.recv() => {
+                    let deliver = message.deliver.unwrap();
+                    let content = message.content.unwrap();
+
+                    if let Err(e) = channel
+                        .basic_ack(BasicAckArguments::new(deliver.delivery_tag(), false))
+                        .await
+                    {
+                        error!("Failed to acknowledge message: {:?}", e);
+                    }
+
+                    let presence: UserPresence = match serde_json::from_slice(&content) {
+                        Ok(presence) => presence,
+                        Err(e) => {
+                            error!("Failed to deserialize presence: {:?}", e);
+                            continue;
+                        }
+                    };
+
+                    debug!("Sending presence of {} to user", presence.user_id);
+                    if let Err(e) = self.tx.send(Ok(presence.into())).await {
+                        error!("Failed to send presence: {:?}", e);
+                        break;
+                    }
+                }
+
+                _ = self.tx.closed() => {
+                    info!("Client likely disconnected, stopping presence updates.");
+                    break;
+                }
+            }
+        }
+
+        if let Err(e) = channel.basic_cancel(BasicCancelArguments::new(&tag)).await {
+            error!("Failed to cancel consumer: {:?}", e);
+        }
+    }
+}
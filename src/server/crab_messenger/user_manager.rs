@@ -1,24 +1,37 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+use amqprs::channel::{BasicPublishArguments, Channel};
+use amqprs::BasicProperties;
 use anyhow::Result;
 use async_trait::async_trait;
 use diesel::prelude::*;
-use diesel::{QueryDsl, RunQueryDsl};
+use diesel::sql_types::{BigInt, Text};
+use diesel::{PgConnection, QueryDsl, RunQueryDsl};
 use shaku::{module, Component, Interface};
 use tonic::{Request, Response, Status};
 use tracing::{debug, error, info};
 
+use crate::server::authenticated_user::AuthenticatedRequest;
 use crate::utils::db_connection_manager::{
     build_db_connection_manager_module, DBConnectionManager, DBConnectionManagerModule,
 };
-use crate::utils::messenger::{GetRelatedUsersRequest, SearchUserQuery, User as GUser, Users};
-use crate::utils::persistence::chat::Chat;
-use crate::utils::persistence::schema::{chats, users, users_chats};
-use crate::utils::persistence::user::User as DBUser;
-use crate::utils::persistence::users_chats::UsersChats;
+use crate::utils::messenger::{
+    BlockUserRequest, BlockUserResponse, EmailVisibility, GetProfileRequest,
+    GetRelatedUsersRequest, ListBlockedRequest, Profile as GProfile, SearchUserQuery,
+    UnblockUserRequest, UnblockUserResponse, UpdateProfileRequest, Users,
+};
+use crate::utils::persistence::profile::{
+    email_visibility_name, profile_for_viewer, user_for_viewer, Profile,
+};
+use crate::utils::persistence::schema::{invites, profiles, user_blocks, users, users_chats};
+use crate::utils::persistence::user::{User as DBUser, UserSearchHit};
+use crate::utils::persistence::user_block::{blocked_by, blockers_of, InsertUserBlock};
 use crate::utils::rabbit_channel_manager::{
     build_channel_manager_module, ChannelManager, ChannelManagerModule,
 };
+use crate::utils::rabbit_declares::{chat_connect_exchange_name, declare_chat_connect_exchange};
+use crate::utils::rabbit_types::RabbitChatConnect;
 
 #[async_trait]
 pub trait UserManager: Interface {
@@ -32,8 +45,72 @@ pub trait UserManager: Interface {
         &self,
         request: Request<GetRelatedUsersRequest>,
     ) -> Result<Response<Users>, Status>;
+
+    async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<GProfile>, Status>;
+
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<GProfile>, Status>;
+
+    async fn block_user(
+        &self,
+        request: Request<BlockUserRequest>,
+    ) -> Result<Response<BlockUserResponse>, Status>;
+
+    async fn unblock_user(
+        &self,
+        request: Request<UnblockUserRequest>,
+    ) -> Result<Response<UnblockUserResponse>, Status>;
+
+    async fn list_blocked(
+        &self,
+        request: Request<ListBlockedRequest>,
+    ) -> Result<Response<Users>, Status>;
 }
 
+const MAX_DISPLAY_NAME_LENGTH: usize = 64;
+const MAX_STATUS_MESSAGE_LENGTH: usize = 140;
+const MAX_TIMEZONE_LENGTH: usize = 64;
+
+/// Page size used by the user search when the request does not set a limit.
+const DEFAULT_USER_SEARCH_PAGE_SIZE: u32 = 20;
+/// Largest page the user search returns, whatever the requested limit.
+const MAX_USER_SEARCH_PAGE_SIZE: u32 = 50;
+
+/// Emails only take part in the search when their owner lets the caller see them,
+/// otherwise the prefix match would give hidden addresses away. Users blocked by or
+/// blocking the caller are left out. Prefix matches rank above fuzzy ones.
+const SEARCH_USERS_QUERY: &str = "
+with contacts as (select distinct other.user_id
+                  from users_chats mine
+                           join users_chats other on other.chat_id = mine.chat_id
+                  where mine.user_id = $1)
+select users.id, contacts.user_id is not null as is_contact
+from users
+         left join profiles on profiles.user_id = users.id
+         left join contacts on contacts.user_id = users.id
+         cross join lateral (select coalesce(profiles.email_visibility, 'contacts') = 'everyone'
+                                        or (coalesce(profiles.email_visibility, 'contacts') = 'contacts'
+                                            and contacts.user_id is not null) as email_visible) visibility
+where users.id <> $1
+  and not exists(select
+                 from user_blocks
+                 where (user_blocks.blocker_user_id = $1 and user_blocks.blocked_user_id = users.id)
+                    or (user_blocks.blocker_user_id = users.id and user_blocks.blocked_user_id = $1))
+  and (profiles.display_name ilike $2
+    or $3 <% profiles.display_name
+    or (visibility.email_visible and (users.email ilike $2 or $3 <% users.email)))
+order by coalesce(profiles.display_name ilike $2, false)
+             or (visibility.email_visible and users.email ilike $2) desc,
+         greatest(word_similarity($3, coalesce(profiles.display_name, '')),
+                  case when visibility.email_visible then word_similarity($3, users.email) else 0 end) desc,
+         users.id
+limit $4 offset $5";
+
 #[derive(Component)]
 #[shaku(interface = UserManager)]
 pub struct UserManagerImpl {
@@ -43,6 +120,206 @@ pub struct UserManagerImpl {
     channel_manager: Arc<dyn ChannelManager>,
 }
 
+impl UserManagerImpl {
+    /// Ids of the users sharing at least one chat with the given one, including themselves.
+    async fn contact_ids(
+        &self,
+        connection: &mut PgConnection,
+        user_id: &str,
+    ) -> Result<HashSet<String>, Status> {
+        let chat_ids = users_chats::table
+            .filter(users_chats::user_id.eq(user_id))
+            .select(users_chats::chat_id)
+            .load::<i32>(connection)
+            .map_err(|e| {
+                error!("Failed to get chats_container: {}", e);
+                Status::internal("Failed to get chats_container")
+            })?;
+
+        let contact_ids = users_chats::table
+            .filter(users_chats::chat_id.eq_any(chat_ids))
+            .select(users_chats::user_id)
+            .distinct()
+            .load::<String>(connection)
+            .map_err(|e| {
+                error!("Failed to get user bindings: {}", e);
+                Status::internal("Failed to get user bindings")
+            })?;
+
+        Ok(contact_ids.into_iter().collect())
+    }
+
+    async fn search_directory(
+        &self,
+        connection: &mut PgConnection,
+        viewer_id: &str,
+        query: &str,
+        offset: u32,
+        limit: u32,
+    ) -> Result<Users, Status> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Err(Status::invalid_argument("Search query can't be empty"));
+        }
+
+        let limit = match limit {
+            0 => DEFAULT_USER_SEARCH_PAGE_SIZE,
+            limit => limit.min(MAX_USER_SEARCH_PAGE_SIZE),
+        } as usize;
+        debug!(
+            "Searching {} users for {:?} from offset {}",
+            limit, query, offset
+        );
+
+        let prefix_pattern = format!(
+            "{}%",
+            query
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+
+        // One extra row is fetched to find out whether there is more past this page.
+        let mut hits = diesel::sql_query(SEARCH_USERS_QUERY)
+            .bind::<Text, _>(viewer_id)
+            .bind::<Text, _>(&prefix_pattern)
+            .bind::<Text, _>(&query)
+            .bind::<BigInt, _>(limit as i64 + 1)
+            .bind::<BigInt, _>(offset as i64)
+            .load::<UserSearchHit>(connection)
+            .map_err(|e| {
+                error!("Failed to search users: {}", e);
+                Status::internal("Failed to search users")
+            })?;
+
+        let has_more = hits.len() > limit;
+        hits.truncate(limit);
+
+        let mut found_users = users::table
+            .left_join(profiles::table)
+            .filter(users::id.eq_any(hits.iter().map(|hit| &hit.id)))
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .load::<(DBUser, Option<Profile>)>(connection)
+            .map_err(|e| {
+                error!("Failed to query user: {}", e);
+                Status::internal("Failed to query user")
+            })?
+            .into_iter()
+            .map(|(user, profile)| (user.id.clone(), (user, profile)))
+            .collect::<HashMap<_, _>>();
+
+        let users = hits
+            .into_iter()
+            .filter_map(|hit| {
+                let (user, profile) = found_users.remove(&hit.id)?;
+                Some(user_for_viewer(user, profile, viewer_id, hit.is_contact))
+            })
+            .collect();
+
+        Ok(Users { users, has_more })
+    }
+
+    /// Tells the live Chat streams of `user_id` about a block change.
+    async fn publish_chat_connect(
+        &self,
+        channel: &Channel,
+        user_id: &str,
+        command: &RabbitChatConnect,
+    ) -> Result<(), anyhow::Error> {
+        declare_chat_connect_exchange(channel, user_id).await?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serde_json::to_string(command)?.into_bytes(),
+                BasicPublishArguments::new(&chat_connect_exchange_name(user_id), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn notify_block_change(
+        &self,
+        user_id: &str,
+        command: RabbitChatConnect,
+    ) -> Result<(), Status> {
+        let channel = self.channel_manager.get_channel().await.map_err(|e| {
+            error!("Failed to get channel: {}", e);
+            Status::internal("Failed to get channel")
+        })?;
+
+        self.publish_chat_connect(&channel, user_id, &command)
+            .await
+            .map_err(|e| {
+                error!("Failed to publish block change: {}", e);
+                Status::internal("Failed to publish block change")
+            })
+    }
+
+    async fn validate_profile_update(&self, update: &UpdateProfileRequest) -> Result<(), Status> {
+        if update.display_name.is_none()
+            && update.status_message.is_none()
+            && update.timezone.is_none()
+            && update.email_visibility.is_none()
+        {
+            return Err(Status::invalid_argument("Nothing to update"));
+        }
+        if let Some(display_name) = &update.display_name {
+            let display_name = display_name.trim();
+            if display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH
+                || display_name.chars().any(char::is_control)
+            {
+                return Err(Status::invalid_argument(format!(
+                    "Display name must be at most {} printable characters",
+                    MAX_DISPLAY_NAME_LENGTH
+                )));
+            }
+        }
+        if update
+            .status_message
+            .as_ref()
+            .is_some_and(|status| status.trim().chars().count() > MAX_STATUS_MESSAGE_LENGTH)
+        {
+            return Err(Status::invalid_argument(format!(
+                "Status message must be at most {} characters",
+                MAX_STATUS_MESSAGE_LENGTH
+            )));
+        }
+        if update
+            .timezone
+            .as_ref()
+            .is_some_and(|timezone| !is_timezone_name(timezone))
+        {
+            return Err(Status::invalid_argument(
+                "Timezone must be an IANA time zone name, like Europe/Kyiv",
+            ));
+        }
+        if update
+            .email_visibility
+            .is_some_and(|visibility| EmailVisibility::try_from(visibility).is_err())
+        {
+            return Err(Status::invalid_argument("Unknown email visibility"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Only checks the shape of the name, the server doesn't carry the time zone database.
+fn is_timezone_name(timezone: &str) -> bool {
+    !timezone.is_empty()
+        && timezone.len() <= MAX_TIMEZONE_LENGTH
+        && timezone.split('/').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic())
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+        })
+}
+
 #[async_trait]
 impl UserManager for UserManagerImpl {
     #[tracing::instrument(skip(self, request), err)]
@@ -52,6 +329,7 @@ impl UserManager for UserManagerImpl {
     ) -> Result<Response<Users>, Status> {
         info!("Getting user");
 
+        let viewer_id = request.authenticated_user()?.id.clone();
         let get_user_req = request.into_inner();
 
         let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
@@ -59,18 +337,36 @@ impl UserManager for UserManagerImpl {
             Status::internal("Failed to get DB connection")
         })?;
 
-        let user_result = match (get_user_req.user_id, get_user_req.email) {
-            (Some(user_id), _) => {
+        // Blocked users are hidden in both directions, like in the directory search.
+        let query = users::table
+            .left_join(profiles::table)
+            .filter(diesel::dsl::not(users::id.eq_any(blocked_by(&viewer_id))))
+            .filter(diesel::dsl::not(users::id.eq_any(blockers_of(&viewer_id))))
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .into_boxed();
+
+        let lookup = (get_user_req.user_id, get_user_req.email, get_user_req.query);
+        let (query, by_email) = match lookup {
+            (Some(user_id), _, _) => {
                 debug!("Querying user by id: {}", user_id);
-                users::table
-                    .filter(users::id.eq(user_id))
-                    .load::<DBUser>(&mut connection)
+                (query.filter(users::id.eq(user_id)), false)
             }
-            (_, Some(email)) => {
+            (_, Some(email), _) => {
                 debug!("Querying user by email: {}", email);
-                users::table
-                    .filter(users::email.eq(email))
-                    .load::<DBUser>(&mut connection)
+                (query.filter(users::email.eq(email)), true)
+            }
+            (_, _, Some(search_query)) => {
+                let users = self
+                    .search_directory(
+                        &mut connection,
+                        &viewer_id,
+                        &search_query,
+                        get_user_req.offset,
+                        get_user_req.limit,
+                    )
+                    .await?;
+                info!("Returning {} users", users.users.len());
+                return Ok(Response::new(users));
             }
             _ => {
                 error!("No user identifier provided");
@@ -78,28 +374,39 @@ impl UserManager for UserManagerImpl {
             }
         };
 
-        let db_users = user_result.map_err(|e| {
-            error!("Failed to query user: {}", e);
-            Status::internal("Failed to query user")
-        })?;
+        let db_users = query
+            .load::<(DBUser, Option<Profile>)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to query user: {}", e);
+                Status::internal("Failed to query user")
+            })?;
+
+        let contact_ids = self.contact_ids(&mut connection, &viewer_id).await?;
 
+        // Looking a user up by email only works if they let the caller see it.
         let grpc_users = db_users
             .into_iter()
-            .map(|db_user| GUser {
-                id: db_user.id,
-                email: db_user.email,
+            .map(|(db_user, profile)| {
+                let is_contact = contact_ids.contains(&db_user.id);
+                user_for_viewer(db_user, profile, &viewer_id, is_contact)
             })
+            .filter(|user| !by_email || !user.email.is_empty())
             .collect::<Vec<_>>();
 
         info!("Returning users: {:?}", grpc_users);
-        Ok(Response::new(Users { users: grpc_users }))
+        Ok(Response::new(Users {
+            users: grpc_users,
+            has_more: false,
+        }))
     }
 
     async fn create_user(&self, user: DBUser) -> Result<(), anyhow::Error> {
         let mut connection = self.db_connection_manager.get_connection()?;
 
+        // Concurrent first requests of a user may race to create it.
         diesel::insert_into(users::table)
             .values(&user)
+            .on_conflict_do_nothing()
             .execute(&mut connection)
             .map_err(|e| {
                 error!("Failed to create user: {}", e);
@@ -118,41 +425,16 @@ impl UserManager for UserManagerImpl {
             Status::internal("Failed to get DB connection")
         })?;
 
-        let metadata = request.metadata();
-        let user_id = metadata.get("user_id").unwrap().to_str().unwrap();
+        let user_id = request.authenticated_user()?.id.as_str();
         debug!("User_id: {:?}", user_id);
 
-        let related_chats = users_chats::table
-            .filter(users_chats::user_id.eq(user_id))
-            .inner_join(chats::table.on(users_chats::chat_id.eq(chats::id)))
-            .select(chats::all_columns)
-            .load::<Chat>(&mut connection)
-            .map_err(|e| {
-                error!("Failed to get chats_container: {}", e);
-                Status::internal("Failed to get chats_container")
-            })?;
-
-        let related_user_bindings = users_chats::table
-            .filter(
-                users_chats::chat_id.eq_any(related_chats.iter().map(|c| c.id).collect::<Vec<_>>()),
-            )
-            .select(users_chats::all_columns)
-            .distinct()
-            .load::<UsersChats>(&mut connection)
-            .map_err(|e| {
-                error!("Failed to get user bindings: {}", e);
-                Status::internal("Failed to get user bindings")
-            })?;
-
-        let related_user_ids = related_user_bindings
-            .into_iter()
-            .map(|b| b.user_id)
-            .collect::<Vec<_>>();
+        let related_user_ids = self.contact_ids(&mut connection, user_id).await?;
 
         let related_users = users::table
+            .left_join(profiles::table)
             .filter(users::id.eq_any(related_user_ids))
-            .distinct()
-            .load::<DBUser>(&mut connection)
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .load::<(DBUser, Option<Profile>)>(&mut connection)
             .map_err(|e| {
                 error!("Failed to get unique users: {}", e);
                 Status::internal("Failed to get unique users")
@@ -160,10 +442,250 @@ impl UserManager for UserManagerImpl {
 
         let users = related_users
             .into_iter()
-            .map(|u| u.into())
+            .map(|(user, profile)| user_for_viewer(user, profile, user_id, true))
             .collect::<Vec<_>>();
 
-        Ok(Response::new(Users { users }))
+        Ok(Response::new(Users {
+            users,
+            has_more: false,
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn get_profile(
+        &self,
+        request: Request<GetProfileRequest>,
+    ) -> Result<Response<GProfile>, Status> {
+        let viewer_id = request.authenticated_user()?.id.clone();
+        let user_id = request
+            .into_inner()
+            .user_id
+            .unwrap_or_else(|| viewer_id.clone());
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let (user, profile) = users::table
+            .left_join(profiles::table)
+            .filter(users::id.eq(&user_id))
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .first::<(DBUser, Option<Profile>)>(&mut connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get profile: {}", e);
+                Status::internal("Failed to get profile")
+            })?
+            .ok_or_else(|| Status::not_found(format!("No user found with id: {}", user_id)))?;
+
+        let is_contact = self
+            .contact_ids(&mut connection, &viewer_id)
+            .await?
+            .contains(&user_id);
+
+        Ok(Response::new(profile_for_viewer(
+            user, profile, &viewer_id, is_contact,
+        )))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn update_profile(
+        &self,
+        request: Request<UpdateProfileRequest>,
+    ) -> Result<Response<GProfile>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let update = request.into_inner();
+        self.validate_profile_update(&update).await?;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let (user, profile) = users::table
+            .left_join(profiles::table)
+            .filter(users::id.eq(&user_id))
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .first::<(DBUser, Option<Profile>)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get profile: {}", e);
+                Status::internal("Failed to get profile")
+            })?;
+
+        let mut profile = profile.unwrap_or_else(|| Profile::new(user_id.clone()));
+        if let Some(display_name) = update.display_name {
+            let display_name = display_name.trim();
+            profile.display_name = (!display_name.is_empty()).then(|| display_name.to_string());
+        }
+        if let Some(status_message) = update.status_message {
+            profile.status_message = status_message.trim().to_string();
+        }
+        if let Some(timezone) = update.timezone {
+            profile.timezone = timezone;
+        }
+        if let Some(Ok(visibility)) = update.email_visibility.map(EmailVisibility::try_from) {
+            profile.email_visibility = email_visibility_name(visibility).to_string();
+        }
+        profile.updated_at = chrono::Utc::now().naive_utc();
+
+        let profile = diesel::insert_into(profiles::table)
+            .values(&profile)
+            .on_conflict(profiles::user_id)
+            .do_update()
+            .set(&profile)
+            .get_result::<Profile>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to update profile: {}", e);
+                Status::internal("Failed to update profile")
+            })?;
+
+        debug!("Updated profile of {}", user_id);
+        Ok(Response::new(profile_for_viewer(
+            user,
+            Some(profile),
+            &user_id,
+            true,
+        )))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn block_user(
+        &self,
+        request: Request<BlockUserRequest>,
+    ) -> Result<Response<BlockUserResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let blocked_user_id = request.into_inner().user_id;
+
+        if blocked_user_id == user_id {
+            return Err(Status::invalid_argument("Can't block yourself"));
+        }
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let exists = diesel::select(diesel::dsl::exists(
+            users::table.filter(users::id.eq(&blocked_user_id)),
+        ))
+        .get_result::<bool>(&mut connection)
+        .map_err(|e| {
+            error!("Failed to get user: {}", e);
+            Status::internal("Failed to get user")
+        })?;
+
+        if !exists {
+            return Err(Status::not_found(format!(
+                "No user {} found",
+                blocked_user_id
+            )));
+        }
+
+        // Pending invites from the blocked user go away with the block.
+        connection
+            .transaction(|connection| {
+                diesel::insert_into(user_blocks::table)
+                    .values(&InsertUserBlock {
+                        blocker_user_id: user_id.clone(),
+                        blocked_user_id: blocked_user_id.clone(),
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(connection)?;
+
+                diesel::delete(invites::table)
+                    .filter(invites::inviter_user_id.eq(&blocked_user_id))
+                    .filter(invites::invitee_user_id.eq(&user_id))
+                    .execute(connection)
+            })
+            .map_err(|e| {
+                error!("Failed to block user: {}", e);
+                Status::internal("Failed to block user")
+            })?;
+
+        self.notify_block_change(&user_id, RabbitChatConnect::Block(blocked_user_id.clone()))
+            .await?;
+
+        debug!("User {} blocked {}", user_id, blocked_user_id);
+        Ok(Response::new(BlockUserResponse { success: true }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn unblock_user(
+        &self,
+        request: Request<UnblockUserRequest>,
+    ) -> Result<Response<UnblockUserResponse>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+        let blocked_user_id = request.into_inner().user_id;
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let deleted = diesel::delete(user_blocks::table)
+            .filter(user_blocks::blocker_user_id.eq(&user_id))
+            .filter(user_blocks::blocked_user_id.eq(&blocked_user_id))
+            .execute(&mut connection)
+            .map_err(|e| {
+                error!("Failed to unblock user: {}", e);
+                Status::internal("Failed to unblock user")
+            })?;
+
+        if deleted == 0 {
+            return Err(Status::not_found(format!(
+                "User {} is not blocked",
+                blocked_user_id
+            )));
+        }
+
+        self.notify_block_change(
+            &user_id,
+            RabbitChatConnect::Unblock(blocked_user_id.clone()),
+        )
+        .await?;
+
+        debug!("User {} unblocked {}", user_id, blocked_user_id);
+        Ok(Response::new(UnblockUserResponse { success: true }))
+    }
+
+    #[tracing::instrument(skip(self, request), err)]
+    async fn list_blocked(
+        &self,
+        request: Request<ListBlockedRequest>,
+    ) -> Result<Response<Users>, Status> {
+        let user_id = request.authenticated_user()?.id.clone();
+
+        let mut connection = self.db_connection_manager.get_connection().map_err(|e| {
+            error!("Failed to get DB connection: {}", e);
+            Status::internal("Failed to get DB connection")
+        })?;
+
+        let blocked_users = user_blocks::table
+            .inner_join(users::table.on(users::id.eq(user_blocks::blocked_user_id)))
+            .left_join(profiles::table.on(profiles::user_id.eq(users::id)))
+            .filter(user_blocks::blocker_user_id.eq(&user_id))
+            .order(user_blocks::created_at.desc())
+            .select((users::all_columns, profiles::all_columns.nullable()))
+            .load::<(DBUser, Option<Profile>)>(&mut connection)
+            .map_err(|e| {
+                error!("Failed to get blocked users: {}", e);
+                Status::internal("Failed to get blocked users")
+            })?;
+
+        let contact_ids = self.contact_ids(&mut connection, &user_id).await?;
+        let users = blocked_users
+            .into_iter()
+            .map(|(user, profile)| {
+                let is_contact = contact_ids.contains(&user.id);
+                user_for_viewer(user, profile, &user_id, is_contact)
+            })
+            .collect();
+
+        Ok(Response::new(Users {
+            users,
+            has_more: false,
+        }))
     }
 }
 
@@ -2,6 +2,8 @@ use rand::distributions::Alphanumeric;
 use rand::Rng;
 
 pub mod auth;
+pub mod blob_store;
+pub mod identity_provider;
 pub mod messenger;
 pub mod rabbit_channel_manager;
 
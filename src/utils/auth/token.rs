@@ -1,3 +1,4 @@
+use jsonwebtoken::{decode, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -5,3 +6,19 @@ pub struct AccessToken {
     #[serde(rename = "sub")]
     pub id: String,
 }
+
+impl AccessToken {
+    /// Reads the claims of a token without verifying it. Only meant for the client,
+    /// which needs its own user id and trusts the token it was issued.
+    pub fn read_unverified(token: &str) -> Option<AccessToken> {
+        let mut validation = Validation::default();
+        validation.insecure_disable_signature_validation();
+        validation.validate_exp = false;
+        validation.validate_aud = false;
+        validation.required_spec_claims.clear();
+
+        decode::<AccessToken>(token, &DecodingKey::from_secret(&[]), &validation)
+            .map(|token_data| token_data.claims)
+            .ok()
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use dotenv::dotenv;
+use shaku::{module, Component, Interface};
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Stores attachment contents under keys generated by the server.
+#[async_trait]
+pub trait BlobStore: Interface {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+/// Keeps every blob as a file in a local directory.
+#[derive(Component)]
+#[shaku(interface = BlobStore)]
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, data: &[u8]) -> Result<()> {
+        // Written aside first so a failed upload never leaves a truncated blob behind.
+        let partial_path = self.path(&format!("{}.partial", key));
+        tokio::fs::write(&partial_path, data).await?;
+        tokio::fs::rename(&partial_path, self.path(key)).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        Ok(tokio::fs::read(self.path(key)).await?)
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+module! {
+    pub BlobStoreModule {
+        components = [LocalBlobStore],
+        providers = [],
+    }
+}
+
+pub fn build_blob_store_module() -> Arc<BlobStoreModule> {
+    dotenv().ok();
+
+    let root =
+        PathBuf::from(env::var("ATTACHMENTS_DIR").unwrap_or_else(|_| "attachments".to_string()));
+    std::fs::create_dir_all(&root).expect("Failed to create attachments directory");
+    Arc::new(
+        BlobStoreModule::builder()
+            .with_component_parameters::<LocalBlobStore>(LocalBlobStoreParameters { root })
+            .build(),
+    )
+}
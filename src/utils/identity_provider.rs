@@ -0,0 +1,516 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use dotenv::dotenv;
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use shaku::{module, Component, Interface};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{debug, error, info};
+
+use crate::utils::auth::token::AccessToken;
+use crate::utils::identity_provider::jwks::JwksCache;
+
+pub mod jwks;
+
+const DEFAULT_AUTH0_DOMAIN: &str = "crab-messenger.eu.auth0.com";
+/// Audience every access token the server accepts is issued for.
+pub const API_AUDIENCE: &str = "crab-api";
+/// Issuer of the tokens signed by the local provider.
+pub const LOCAL_ISSUER: &str = "crab-messenger-local";
+
+/// Verifies the access tokens clients send and knows the users they were issued to.
+#[async_trait]
+pub trait IdentityProvider: Interface {
+    /// Checks the signature and claims of a token and returns them.
+    async fn verify_token(&self, token: &str) -> Result<AccessToken>;
+    /// Email of a user seen for the first time, it's stored along with them.
+    async fn get_email(&self, user_id: &str) -> Result<String>;
+}
+
+/// Identity providers the server can be configured with through `AUTH_PROVIDER`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthProvider {
+    Auth0,
+    Local,
+}
+
+impl AuthProvider {
+    /// Reads `AUTH_PROVIDER`, `auth0` when it isn't set. The server checks it on start so
+    /// a typo is reported before any module is built.
+    pub fn from_env() -> Result<Self> {
+        dotenv().ok();
+
+        match env::var("AUTH_PROVIDER") {
+            Err(env::VarError::NotPresent) => Ok(Self::Auth0),
+            Ok(provider) => match provider.as_str() {
+                "auth0" => Ok(Self::Auth0),
+                "local" => Ok(Self::Local),
+                _ => Err(anyhow!(
+                    "Unknown AUTH_PROVIDER {:?}, expected one of: auth0, local",
+                    provider
+                )),
+            },
+            Err(err) => Err(anyhow!("Invalid AUTH_PROVIDER: {}", err)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct UserInfo {
+    email: String,
+}
+
+#[derive(Deserialize)]
+struct Auth0TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// A management API token is fetched again this long before it expires.
+const MANAGEMENT_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Tokens signed by Auth0, users are looked up with the management API.
+#[derive(Component)]
+#[shaku(interface = IdentityProvider)]
+pub struct Auth0IdentityProvider {
+    domain: String,
+    client_id: String,
+    client_secret: String,
+    /// Audience of the management API.
+    audience: String,
+    issuer: String,
+    /// Audiences accepted in the tokens clients send.
+    token_audiences: Vec<String>,
+    jwks: JwksCache,
+    /// Management API token and when it stops being reused, locked while fetching.
+    management_token: Mutex<Option<(String, Instant)>>,
+}
+
+impl Auth0IdentityProvider {
+    async fn get_auth0_access_token(&self) -> Result<String> {
+        let mut management_token = self.management_token.lock().await;
+        if let Some((access_token, refresh_at)) = management_token.as_ref() {
+            if Instant::now() < *refresh_at {
+                return Ok(access_token.clone());
+            }
+        }
+
+        let client = reqwest::Client::new();
+        let token_url = format!("https://{}/oauth/token", self.domain);
+        let request_body = json!({
+            "client_id": self.client_id,
+            "client_secret": self.client_secret,
+            "audience": self.audience,
+            "grant_type": "client_credentials"
+        });
+
+        let response = client
+            .post(token_url)
+            .json(&request_body)
+            .send()
+            .await
+            .map_err(|err| {
+                error!("Failed to retrieve Auth0 access token: {}", err);
+                err
+            })?;
+
+        let token_response: Auth0TokenResponse = response.json().await.map_err(|err| {
+            error!("Failed to parse Auth0 token response: {}", err);
+            anyhow::Error::new(err)
+        })?;
+
+        info!("Retrieved Auth0 access token");
+        let refresh_at = Instant::now()
+            + Duration::from_secs(token_response.expires_in)
+                .saturating_sub(MANAGEMENT_TOKEN_EXPIRY_MARGIN);
+        *management_token = Some((token_response.access_token.clone(), refresh_at));
+
+        Ok(token_response.access_token)
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for Auth0IdentityProvider {
+    async fn verify_token(&self, token: &str) -> Result<AccessToken> {
+        let kid = decode_header(token)?
+            .kid
+            .ok_or_else(|| anyhow!("Token has no key id"))?;
+        let key = self.jwks.get_key(&kid).await?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&self.token_audiences);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        Ok(decode::<AccessToken>(token, &key, &validation)?.claims)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn get_email(&self, user_id: &str) -> Result<String> {
+        info!("Getting user info");
+        let access_token = self.get_auth0_access_token().await?;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}users", self.audience);
+
+        let from_params = [
+            ("q", user_id.to_string()),
+            ("search_engine", "v3".to_string()),
+        ];
+
+        let response = client
+            .get(url)
+            .query(&from_params)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .map_err(|err| {
+                error!("Failed to get user info: {}", err);
+                err
+            })?;
+        debug!("Response: {:?}", response);
+        let response_body = response.text().await.map_err(|err| {
+            error!("Failed to get user info: {}", err);
+            err
+        })?;
+
+        debug!("Response body: {}", response_body);
+
+        let users: Vec<UserInfo> = serde_json::from_str(&response_body).map_err(|err| {
+            error!("Failed to parse user info: {}", err);
+            anyhow::Error::new(err)
+        })?;
+
+        users
+            .first()
+            .map(|user| user.email.clone())
+            .ok_or_else(|| anyhow!("User not found"))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LocalClaims {
+    sub: String,
+    iss: String,
+    aud: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Self-contained provider for running the stack without network access. Tokens are
+/// signed with a shared HS256 secret and users come from the configuration.
+pub struct LocalIdentityProvider {
+    secret: String,
+    /// Emails of the known users by their id.
+    users: HashMap<String, String>,
+}
+
+impl LocalIdentityProvider {
+    pub fn new(secret: String, users: HashMap<String, String>) -> Self {
+        Self { secret, users }
+    }
+
+    /// Reads `LOCAL_AUTH_SECRET` and `LOCAL_AUTH_USERS`, the latter being a comma
+    /// separated list of `user_id=email` pairs.
+    pub fn from_env() -> Result<Self> {
+        dotenv().ok();
+
+        let secret = env::var("LOCAL_AUTH_SECRET")
+            .map_err(|_| anyhow!("LOCAL_AUTH_SECRET must be set for local auth"))?;
+        if secret.is_empty() {
+            return Err(anyhow!("LOCAL_AUTH_SECRET can't be empty"));
+        }
+
+        let users = env::var("LOCAL_AUTH_USERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| match entry.split_once('=') {
+                Some((user_id, email)) if !user_id.trim().is_empty() => {
+                    Ok((user_id.trim().to_string(), email.trim().to_string()))
+                }
+                _ => Err(anyhow!("Invalid LOCAL_AUTH_USERS entry: {}", entry)),
+            })
+            .collect::<Result<HashMap<_, _>>>()?;
+
+        Ok(Self::new(secret, users))
+    }
+
+    /// Signs a token for one of the configured users, valid for `ttl`.
+    pub fn issue_token(&self, user_id: &str, ttl: chrono::Duration) -> Result<String> {
+        if !self.users.contains_key(user_id) {
+            return Err(anyhow!("Unknown local user: {}", user_id));
+        }
+
+        let now = chrono::Utc::now();
+        let claims = LocalClaims {
+            sub: user_id.to_string(),
+            iss: LOCAL_ISSUER.to_string(),
+            aud: API_AUDIENCE.to_string(),
+            iat: now.timestamp(),
+            exp: (now + ttl).timestamp(),
+        };
+
+        Ok(encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.secret.as_bytes()),
+        )?)
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for LocalIdentityProvider {
+    async fn verify_token(&self, token: &str) -> Result<AccessToken> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_audience(&[API_AUDIENCE]);
+        validation.set_issuer(&[LOCAL_ISSUER]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+        let key = DecodingKey::from_secret(self.secret.as_bytes());
+        let access_token = decode::<AccessToken>(token, &key, &validation)?.claims;
+
+        // Users removed from the configuration lose access even with a valid token.
+        if !self.users.contains_key(&access_token.id) {
+            return Err(anyhow!("Unknown local user: {}", access_token.id));
+        }
+
+        Ok(access_token)
+    }
+
+    async fn get_email(&self, user_id: &str) -> Result<String> {
+        self.users
+            .get(user_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("Unknown local user: {}", user_id))
+    }
+}
+
+module! {
+    pub IdentityProviderModule {
+        components = [Auth0IdentityProvider],
+        providers = [],
+    }
+}
+
+/// Picks the provider named by `AUTH_PROVIDER`, see [`AuthProvider::from_env`].
+pub fn build_identity_provider_module() -> Arc<IdentityProviderModule> {
+    dotenv().ok();
+
+    let provider = AuthProvider::from_env().unwrap_or_else(|err| {
+        error!("{}", err);
+        panic!("{}", err);
+    });
+
+    let builder = IdentityProviderModule::builder();
+    let builder = match provider {
+        AuthProvider::Local => {
+            info!("Using the local identity provider");
+            let provider = LocalIdentityProvider::from_env().unwrap_or_else(|err| {
+                error!("Failed to configure local auth: {}", err);
+                panic!("Failed to configure local auth: {}", err);
+            });
+            builder.with_component_override::<dyn IdentityProvider>(Box::new(provider))
+        }
+        AuthProvider::Auth0 => {
+            let domain =
+                env::var("AUTH0_DOMAIN").unwrap_or_else(|_| DEFAULT_AUTH0_DOMAIN.to_string());
+            let issuer =
+                env::var("AUTH0_ISSUER").unwrap_or_else(|_| format!("https://{}/", domain));
+            let jwks_url = env::var("AUTH0_JWKS_URL")
+                .unwrap_or_else(|_| format!("{}.well-known/jwks.json", issuer));
+            let token_audiences = match env::var("AUTH0_TOKEN_AUDIENCES") {
+                Ok(audiences) => audiences
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|audience| !audience.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+                Err(_) => vec![API_AUDIENCE.to_string(), format!("{}userinfo", issuer)],
+            };
+
+            builder.with_component_parameters::<Auth0IdentityProvider>(
+                Auth0IdentityProviderParameters {
+                    domain,
+                    client_id: required_var("AUTH0_CLIENT_ID"),
+                    client_secret: required_var("AUTH0_CLIENT_SECRET"),
+                    audience: required_var("AUTH0_AUDIENCE"),
+                    issuer,
+                    token_audiences,
+                    jwks: JwksCache::new(jwks_url),
+                    management_token: Default::default(),
+                },
+            )
+        }
+    };
+
+    Arc::new(builder.build())
+}
+
+fn required_var(name: &str) -> String {
+    env::var(name).unwrap_or_else(|err| {
+        error!("{} must be set in .env file: {}", name, err);
+        panic!("{} must be set in .env file", name);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::identity_provider::jwks::test::{jwks_path, write_jwks, TEST_KEY_PEM};
+
+    const SECRET: &str = "test-secret";
+    const USER_ID: &str = "local|alice";
+
+    fn local_provider(secret: &str, user_ids: &[&str]) -> LocalIdentityProvider {
+        let users = user_ids
+            .iter()
+            .map(|user_id| (user_id.to_string(), format!("{}@example.com", user_id)))
+            .collect();
+        LocalIdentityProvider::new(secret.to_string(), users)
+    }
+
+    fn local_token(issuer: &str, audience: &str) -> String {
+        let now = chrono::Utc::now();
+        let claims = LocalClaims {
+            sub: USER_ID.to_string(),
+            iss: issuer.to_string(),
+            aud: audience.to_string(),
+            iat: now.timestamp(),
+            exp: (now + chrono::Duration::hours(1)).timestamp(),
+        };
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    const AUTH0_ISSUER: &str = "https://tenant.example.com/";
+    const KID: &str = "test-key";
+
+    fn auth0_provider(jwks_url: String) -> Auth0IdentityProvider {
+        Auth0IdentityProvider {
+            domain: "tenant.example.com".to_string(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            audience: String::new(),
+            issuer: AUTH0_ISSUER.to_string(),
+            token_audiences: vec![API_AUDIENCE.to_string()],
+            jwks: JwksCache::new(jwks_url),
+            management_token: Default::default(),
+        }
+    }
+
+    fn auth0_token(issuer: &str, audience: &str) -> String {
+        let now = chrono::Utc::now();
+        let claims = LocalClaims {
+            sub: "auth0|alice".to_string(),
+            iss: issuer.to_string(),
+            aud: audience.to_string(),
+            iat: now.timestamp(),
+            exp: (now + chrono::Duration::hours(1)).timestamp(),
+        };
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(KID.to_string());
+        encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(TEST_KEY_PEM.as_bytes()).unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_auth0_rejects_wrong_issuer_and_audience() {
+        let path = jwks_path();
+        write_jwks(&path, &[KID]);
+        let provider = auth0_provider(format!("file://{}", path.display()));
+
+        let access_token = provider
+            .verify_token(&auth0_token(AUTH0_ISSUER, API_AUDIENCE))
+            .await
+            .unwrap();
+        assert_eq!(access_token.id, "auth0|alice");
+        assert!(provider
+            .verify_token(&auth0_token(
+                "https://someone-else.example.com/",
+                API_AUDIENCE
+            ))
+            .await
+            .is_err());
+        assert!(provider
+            .verify_token(&auth0_token(AUTH0_ISSUER, "other-api"))
+            .await
+            .is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_local_token_round_trip() {
+        let provider = local_provider(SECRET, &[USER_ID]);
+        let token = provider
+            .issue_token(USER_ID, chrono::Duration::hours(1))
+            .unwrap();
+
+        let access_token = provider.verify_token(&token).await.unwrap();
+        assert_eq!(access_token.id, USER_ID);
+        assert_eq!(
+            provider.get_email(USER_ID).await.unwrap(),
+            "local|alice@example.com"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_rejects_unknown_users() {
+        let provider = local_provider(SECRET, &[USER_ID]);
+        assert!(provider
+            .issue_token("local|mallory", chrono::Duration::hours(1))
+            .is_err());
+
+        // A user removed from the configuration keeps a validly signed token.
+        let token = provider
+            .issue_token(USER_ID, chrono::Duration::hours(1))
+            .unwrap();
+        let without_user = local_provider(SECRET, &[]);
+        assert!(without_user.verify_token(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_rejects_bad_signatures() {
+        let token = local_provider("other-secret", &[USER_ID])
+            .issue_token(USER_ID, chrono::Duration::hours(1))
+            .unwrap();
+
+        let provider = local_provider(SECRET, &[USER_ID]);
+        assert!(provider.verify_token(&token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_local_rejects_wrong_issuer_and_audience() {
+        let provider = local_provider(SECRET, &[USER_ID]);
+
+        assert!(provider
+            .verify_token(&local_token(LOCAL_ISSUER, API_AUDIENCE))
+            .await
+            .is_ok());
+        assert!(provider
+            .verify_token(&local_token("someone-else", API_AUDIENCE))
+            .await
+            .is_err());
+        assert!(provider
+            .verify_token(&local_token(LOCAL_ISSUER, "other-api"))
+            .await
+            .is_err());
+    }
+}
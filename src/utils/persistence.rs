@@ -4,3 +4,12 @@ pub mod chat;
 pub mod message;
 pub mod invite;
 pub mod users_chats;
+pub mod message_reaction;
+pub mod user_presence;
+pub mod chat_invite_link;
+pub mod attachment;
+pub mod profile;
+pub mod user_block;
+pub mod pinned_message;
+pub mod mention;
+pub mod api_key;
@@ -0,0 +1,117 @@
+use crate::utils::generate_random_string;
+use crate::utils::messenger::ApiKey as ProtoApiKey;
+use crate::utils::persistence::message::to_timestamp;
+use diesel::prelude::*;
+
+/// Reading chats and their messages, including the events of the Chat stream.
+pub const SCOPE_MESSAGES_READ: &str = "messages:read";
+/// Sending, editing and reacting to messages. Sending is done on the Chat stream, which
+/// needs `messages:read` as well.
+pub const SCOPE_MESSAGES_WRITE: &str = "messages:write";
+pub const API_KEY_SCOPES: [&str; 2] = [SCOPE_MESSAGES_READ, SCOPE_MESSAGES_WRITE];
+
+const API_KEY_PREFIX: &str = "crab_";
+const API_KEY_SECRET_LENGTH: usize = 40;
+/// Length of the start of a key that is kept in clear, enough to tell keys apart.
+const API_KEY_VISIBLE_LENGTH: usize = API_KEY_PREFIX.len() + 6;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub id: i32,
+    pub owner_user_id: String,
+    pub bot_user_id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub last_used_at: Option<chrono::NaiveDateTime>,
+    pub revoked_at: Option<chrono::NaiveDateTime>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertApiKey {
+    pub owner_user_id: String,
+    pub bot_user_id: String,
+    pub name: String,
+    pub key_prefix: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+}
+
+/// A new key along with the part of it that is stored in clear.
+pub fn generate_api_key() -> (String, String) {
+    let key = format!(
+        "{}{}",
+        API_KEY_PREFIX,
+        generate_random_string(API_KEY_SECRET_LENGTH)
+    );
+    let key_prefix = key[..API_KEY_VISIBLE_LENGTH].to_string();
+    (key, key_prefix)
+}
+
+/// Keys are random enough for a plain digest, only the digest is stored.
+pub fn hash_api_key(key: &str) -> String {
+    ring::digest::digest(&ring::digest::SHA256, key.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl From<ApiKey> for ProtoApiKey {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.id,
+            name: api_key.name,
+            key_prefix: api_key.key_prefix,
+            bot_user_id: api_key.bot_user_id,
+            scopes: api_key.scopes,
+            chat_ids: Vec::new(),
+            created_at: Some(to_timestamp(api_key.created_at)),
+            last_used_at: api_key.last_used_at.map(to_timestamp),
+            revoked_at: api_key.revoked_at.map(to_timestamp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_api_key() {
+        let (key, key_prefix) = generate_api_key();
+
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert_eq!(key.len(), API_KEY_PREFIX.len() + API_KEY_SECRET_LENGTH);
+        assert_eq!(key_prefix.len(), API_KEY_VISIBLE_LENGTH);
+        assert!(key.starts_with(&key_prefix));
+        assert_ne!(generate_api_key().0, key);
+    }
+
+    #[test]
+    fn test_hash_api_key() {
+        let (key, _) = generate_api_key();
+        let hash = hash_api_key(&key);
+
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(hash, hash_api_key(&key));
+        assert_ne!(hash, hash_api_key(&format!("{}x", key)));
+        assert_eq!(
+            hash_api_key("abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
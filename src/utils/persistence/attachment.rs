@@ -0,0 +1,56 @@
+use crate::utils::messenger::Attachment as ProtoAttachment;
+use crate::utils::persistence::message::to_timestamp;
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Clone, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::attachments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Attachment {
+    pub id: i32,
+    pub uploader_user_id: String,
+    pub chat_id: i32,
+    pub message_id: Option<i32>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub storage_key: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::attachments)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertAttachment {
+    pub uploader_user_id: String,
+    pub chat_id: i32,
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub storage_key: String,
+}
+
+impl From<Attachment> for ProtoAttachment {
+    fn from(attachment: Attachment) -> Self {
+        Self {
+            id: attachment.id,
+            chat_id: attachment.chat_id,
+            message_id: attachment.message_id,
+            uploader_user_id: attachment.uploader_user_id,
+            file_name: attachment.file_name,
+            content_type: attachment.content_type,
+            size: attachment.size,
+            created_at: Some(to_timestamp(attachment.created_at)),
+        }
+    }
+}
+
+/// Attachments of the given message, in the order they were uploaded.
+pub fn attachments_of(attachments: &[Attachment], message_id: i32) -> Vec<ProtoAttachment> {
+    attachments
+        .iter()
+        .filter(|attachment| attachment.message_id == Some(message_id))
+        .cloned()
+        .map(Into::into)
+        .collect()
+}
@@ -1,13 +1,32 @@
-use crate::utils::messenger::Chat as ProtoChat;
+use crate::utils::messenger::{Chat as ProtoChat, ChatKind};
 use diesel::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Selectable, Deserialize, Insertable)]
+pub const KIND_GROUP: &str = "group";
+pub const KIND_DIRECT: &str = "direct";
+
+#[derive(Queryable, Selectable, Deserialize, Serialize, Insertable, Clone)]
 #[diesel(table_name = crate::utils::persistence::schema::chats)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Chat {
     pub id: i32,
     pub name: String,
+    pub description: String,
+    pub kind: String,
+    pub direct_key: Option<String>,
+}
+
+impl Chat {
+    pub fn kind(&self) -> ChatKind {
+        parse_kind(&self.kind)
+    }
+
+    /// The other participant of a direct chat.
+    pub fn direct_peer<'a>(&'a self, user_id: &str) -> Option<&'a str> {
+        let mut participants = self.direct_key.as_deref()?.split(' ');
+        let (first, second) = (participants.next()?, participants.next()?);
+        Some(if first == user_id { second } else { first })
+    }
 }
 
 #[derive(Queryable, Selectable, Deserialize, Insertable, Debug)]
@@ -15,13 +34,63 @@ pub struct Chat {
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct InsertChat {
     pub name: String,
+    pub kind: String,
+    pub direct_key: Option<String>,
+}
+
+impl InsertChat {
+    pub fn group(name: String) -> Self {
+        Self {
+            name,
+            kind: KIND_GROUP.to_string(),
+            direct_key: None,
+        }
+    }
+
+    /// Direct chats are named after the peer when listed, so the stored name stays empty.
+    pub fn direct(user_id: &str, peer_id: &str) -> Self {
+        Self {
+            name: String::new(),
+            kind: KIND_DIRECT.to_string(),
+            direct_key: Some(direct_key(user_id, peer_id)),
+        }
+    }
+}
+
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::chats)]
+pub struct UpdateChat {
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Identifies the direct chat of two users whichever of them opens it, user ids have no spaces.
+pub fn direct_key(user_id: &str, peer_id: &str) -> String {
+    if user_id < peer_id {
+        format!("{} {}", user_id, peer_id)
+    } else {
+        format!("{} {}", peer_id, user_id)
+    }
+}
+
+pub fn parse_kind(kind: &str) -> ChatKind {
+    match kind {
+        KIND_DIRECT => ChatKind::Direct,
+        _ => ChatKind::Group,
+    }
 }
 
 impl From<Chat> for ProtoChat {
     fn from(diesel_chat: Chat) -> Self {
         ProtoChat {
             id: diesel_chat.id,
+            kind: diesel_chat.kind() as i32,
             name: diesel_chat.name,
+            unread_count: 0,
+            last_message: None,
+            role: 0,
+            description: diesel_chat.description,
+            muted: false,
         }
     }
 }
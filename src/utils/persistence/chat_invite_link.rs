@@ -0,0 +1,52 @@
+use crate::utils::messenger::InviteLink;
+use crate::utils::persistence::message::to_timestamp;
+use diesel::prelude::*;
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::chat_invite_links)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ChatInviteLink {
+    pub code: String,
+    pub chat_id: i32,
+    pub creator_user_id: String,
+    pub max_uses: Option<i32>,
+    pub uses: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+}
+
+impl ChatInviteLink {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= chrono::Utc::now().naive_utc())
+    }
+
+    pub fn is_used_up(&self) -> bool {
+        self.max_uses.is_some_and(|max_uses| self.uses >= max_uses)
+    }
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::chat_invite_links)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertChatInviteLink {
+    pub code: String,
+    pub chat_id: i32,
+    pub creator_user_id: String,
+    pub max_uses: Option<i32>,
+    pub expires_at: Option<chrono::NaiveDateTime>,
+}
+
+impl From<ChatInviteLink> for InviteLink {
+    fn from(link: ChatInviteLink) -> Self {
+        Self {
+            code: link.code,
+            chat_id: link.chat_id,
+            creator_user_id: link.creator_user_id,
+            max_uses: link.max_uses,
+            uses: link.uses,
+            created_at: Some(to_timestamp(link.created_at)),
+            expires_at: link.expires_at.map(to_timestamp),
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::persistence::message::{to_naive, to_timestamp};
 use diesel::prelude::*;
 use prost_types::Timestamp;
 use serde::{Deserialize, Serialize};
@@ -11,6 +12,13 @@ pub struct Invite {
     pub invitee_user_id: String,
     pub chat_id: i32,
     pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+}
+
+impl Invite {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= chrono::Utc::now().naive_utc()
+    }
 }
 
 #[derive(Insertable, Serialize, Deserialize, Queryable, Debug)]
@@ -33,6 +41,7 @@ impl From<Invite> for crate::utils::messenger::Invite {
                 seconds: invite.created_at.timestamp(),
                 nanos: invite.created_at.timestamp_subsec_nanos() as i32,
             }),
+            expires_at: Some(to_timestamp(invite.expires_at)),
         }
     }
 }
@@ -51,6 +60,7 @@ impl From<crate::utils::messenger::Invite> for Invite {
                 timestamp.nanos as u32,
             )
             .unwrap(),
+            expires_at: to_naive(value.expires_at.unwrap()).unwrap(),
         }
     }
 }
@@ -0,0 +1,69 @@
+use crate::utils::persistence::profile::Profile;
+use crate::utils::persistence::user::User;
+use diesel::prelude::*;
+use std::collections::HashSet;
+
+#[derive(Queryable, Selectable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::mentions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Mention {
+    pub message_id: i32,
+    pub mentioned_user_id: String,
+    pub chat_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::mentions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertMention {
+    pub message_id: i32,
+    pub mentioned_user_id: String,
+    pub chat_id: i32,
+}
+
+/// Handles mentioned in a text, lowercased and without the `@`, so `@Bob,` gives `bob`.
+/// Only an `@` starting a word starts a mention, emails in the text aren't mentions.
+pub fn parse_mentions(text: &str) -> HashSet<String> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let handle = word
+                .trim_start_matches(['(', '[', '"', '\''])
+                .strip_prefix('@')?;
+            let end = handle
+                .find(|c: char| !is_handle_char(c))
+                .unwrap_or(handle.len());
+            let handle = handle[..end].trim_end_matches(['.', '-']);
+
+            (!handle.is_empty()).then(|| handle.to_lowercase())
+        })
+        .collect()
+}
+
+/// Handles a chat member can be mentioned by: their display name without spaces, and the
+/// part of their email before the `@` if the author is allowed to see it.
+pub fn mention_handles(user: &User, profile: Option<&Profile>, author_id: &str) -> Vec<String> {
+    let mut handles = Vec::new();
+
+    if let Some(display_name) = profile.and_then(|profile| profile.display_name.as_ref()) {
+        let handle: String = display_name
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if !handle.is_empty() {
+            handles.push(handle.to_lowercase());
+        }
+    }
+
+    // Members share a chat with the author, so they are contacts.
+    let shows_email = profile.is_none_or(|profile| profile.shows_email_to(author_id, true));
+    if let Some((local_part, _)) = user.email.split_once('@').filter(|_| shows_email) {
+        handles.push(local_part.to_lowercase());
+    }
+
+    handles
+}
+
+fn is_handle_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
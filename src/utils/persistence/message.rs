@@ -1,9 +1,10 @@
 use crate::utils::messenger::Message as ProtoMessage;
-use diesel::{Insertable, Queryable, Selectable};
+use diesel::sql_types::{Float4, Text};
+use diesel::{Insertable, Queryable, QueryableByName, Selectable};
 use prost_types::Timestamp;
 use serde::{Deserialize, Serialize};
 
-#[derive(Queryable, Selectable, Deserialize, Serialize, Insertable)]
+#[derive(Queryable, QueryableByName, Selectable, Deserialize, Serialize, Insertable, Clone)]
 #[diesel(table_name = crate::utils::persistence::schema::messages)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Message {
@@ -12,6 +13,9 @@ pub struct Message {
     pub created_at: chrono::NaiveDateTime,
     pub user_id: String,
     pub chat_id: i32,
+    pub edited_at: Option<chrono::NaiveDateTime>,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub reply_to_message_id: Option<i32>,
 }
 
 #[derive(Queryable, Selectable, Deserialize, Serialize, Insertable)]
@@ -21,6 +25,33 @@ pub struct InsertMessage {
     pub text: String,
     pub user_id: String,
     pub chat_id: i32,
+    pub reply_to_message_id: Option<i32>,
+}
+
+/// A row of the full-text message search, see `MessageManager::search_messages`.
+#[derive(QueryableByName)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MessageSearchHit {
+    #[diesel(embed)]
+    pub message: Message,
+    #[diesel(sql_type = Text)]
+    pub snippet: String,
+    #[diesel(sql_type = Float4)]
+    pub rank: f32,
+}
+
+/// `None` for timestamps that can't be represented, like negative nanos or seconds out of
+/// range. Timestamps sent by clients have to be checked with it.
+pub fn to_naive(timestamp: Timestamp) -> Option<chrono::NaiveDateTime> {
+    let nanos = u32::try_from(timestamp.nanos).ok()?;
+    chrono::DateTime::from_timestamp(timestamp.seconds, nanos).map(|at| at.naive_utc())
+}
+
+pub fn to_timestamp(date_time: chrono::NaiveDateTime) -> Timestamp {
+    Timestamp {
+        seconds: date_time.timestamp(),
+        nanos: date_time.timestamp_subsec_nanos() as i32,
+    }
 }
 
 impl From<ProtoMessage> for Message {
@@ -32,31 +63,27 @@ impl From<ProtoMessage> for Message {
             user_id: proto_msg.user_id,
             chat_id: proto_msg.chat_id,
             text: proto_msg.text,
-            created_at: chrono::NaiveDateTime::from_timestamp_opt(
-                timestamp.seconds,
-                timestamp.nanos as u32,
-            )
-            .unwrap(),
+            created_at: to_naive(timestamp).unwrap(),
+            edited_at: proto_msg.edited_at.and_then(to_naive),
+            deleted_at: proto_msg.deleted_at.and_then(to_naive),
+            reply_to_message_id: proto_msg.reply_to_message_id,
         }
     }
 }
 
 impl From<Message> for ProtoMessage {
     fn from(diesel_msg: Message) -> Self {
-        // You would typically ensure this conversion cannot fail by making sure the fields match up correctly.
-        // If created_at is a NaiveDateTime, you must convert it to a Timestamp.
-        let created_at = Timestamp {
-            // Convert the NaiveDateTime to seconds and nanoseconds
-            seconds: diesel_msg.created_at.timestamp(),
-            nanos: diesel_msg.created_at.timestamp_subsec_nanos() as i32,
-        };
-
         ProtoMessage {
             id: diesel_msg.id,
             user_id: diesel_msg.user_id,
             chat_id: diesel_msg.chat_id,
             text: diesel_msg.text,
-            created_at: Some(created_at), // gRPC Timestamp is typically wrapped in an Option
+            created_at: Some(to_timestamp(diesel_msg.created_at)),
+            edited_at: diesel_msg.edited_at.map(to_timestamp),
+            deleted_at: diesel_msg.deleted_at.map(to_timestamp),
+            reply_to_message_id: diesel_msg.reply_to_message_id,
+            reactions: vec![],
+            attachments: vec![],
         }
     }
 }
@@ -0,0 +1,44 @@
+use crate::utils::messenger::Reaction as ProtoReaction;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Queryable, Selectable, Deserialize, Serialize, Insertable, Debug, Clone)]
+#[diesel(table_name = crate::utils::persistence::schema::message_reactions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct MessageReaction {
+    pub message_id: i32,
+    pub user_id: String,
+    pub emoji: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Deserialize, Serialize, Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::message_reactions)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertMessageReaction {
+    pub message_id: i32,
+    pub user_id: String,
+    pub emoji: String,
+}
+
+/// Groups the reactions of a single message by emoji, keeping the order in which
+/// each emoji was first used.
+pub fn aggregate_reactions(reactions: &[MessageReaction]) -> Vec<ProtoReaction> {
+    let mut aggregated: Vec<ProtoReaction> = Vec::new();
+
+    for reaction in reactions {
+        match aggregated.iter_mut().find(|r| r.emoji == reaction.emoji) {
+            Some(existing) => {
+                existing.count += 1;
+                existing.user_ids.push(reaction.user_id.clone());
+            }
+            None => aggregated.push(ProtoReaction {
+                emoji: reaction.emoji.clone(),
+                count: 1,
+                user_ids: vec![reaction.user_id.clone()],
+            }),
+        }
+    }
+
+    aggregated
+}
@@ -0,0 +1,32 @@
+use crate::utils::messenger::PinnedMessage as ProtoPinnedMessage;
+use crate::utils::persistence::message::{to_timestamp, Message};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Queryable, Selectable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = crate::utils::persistence::schema::pinned_messages)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PinnedMessage {
+    pub message_id: i32,
+    pub chat_id: i32,
+    pub pinned_by_user_id: String,
+    pub pinned_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::pinned_messages)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertPinnedMessage {
+    pub message_id: i32,
+    pub chat_id: i32,
+    pub pinned_by_user_id: String,
+}
+
+/// Pins are sent along with the message they point to.
+pub fn to_proto_pinned_message(pin: PinnedMessage, message: Message) -> ProtoPinnedMessage {
+    ProtoPinnedMessage {
+        message: Some(message.into()),
+        pinned_by: pin.pinned_by_user_id,
+        pinned_at: Some(to_timestamp(pin.pinned_at)),
+    }
+}
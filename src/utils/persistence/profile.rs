@@ -0,0 +1,119 @@
+use crate::utils::messenger::{EmailVisibility, Profile as ProtoProfile, User as ProtoUser};
+use crate::utils::persistence::message::to_timestamp;
+use crate::utils::persistence::user::User;
+use diesel::prelude::*;
+
+pub const VISIBILITY_EVERYONE: &str = "everyone";
+pub const VISIBILITY_CONTACTS: &str = "contacts";
+pub const VISIBILITY_NOBODY: &str = "nobody";
+
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Users without a row in `profiles` have the default one.
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Clone, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::profiles)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct Profile {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub status_message: String,
+    pub timezone: String,
+    pub email_visibility: String,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Profile {
+    pub fn new(user_id: String) -> Self {
+        Self {
+            user_id,
+            display_name: None,
+            status_message: String::new(),
+            timezone: DEFAULT_TIMEZONE.to_string(),
+            email_visibility: VISIBILITY_CONTACTS.to_string(),
+            updated_at: chrono::Utc::now().naive_utc(),
+        }
+    }
+
+    pub fn email_visibility(&self) -> EmailVisibility {
+        parse_email_visibility(&self.email_visibility)
+    }
+
+    /// The owner always sees their own email, contacts are the users sharing a chat with them.
+    pub fn shows_email_to(&self, viewer_id: &str, is_contact: bool) -> bool {
+        if viewer_id == self.user_id {
+            return true;
+        }
+
+        match self.email_visibility() {
+            EmailVisibility::Everyone => true,
+            EmailVisibility::Contacts => is_contact,
+            EmailVisibility::Nobody => false,
+        }
+    }
+}
+
+pub fn email_visibility_name(visibility: EmailVisibility) -> &'static str {
+    match visibility {
+        EmailVisibility::Everyone => VISIBILITY_EVERYONE,
+        EmailVisibility::Contacts => VISIBILITY_CONTACTS,
+        EmailVisibility::Nobody => VISIBILITY_NOBODY,
+    }
+}
+
+pub fn parse_email_visibility(visibility: &str) -> EmailVisibility {
+    match visibility {
+        VISIBILITY_EVERYONE => EmailVisibility::Everyone,
+        VISIBILITY_NOBODY => EmailVisibility::Nobody,
+        _ => EmailVisibility::Contacts,
+    }
+}
+
+/// Builds the user sent to `viewer_id`, hiding the email if the profile says so.
+pub fn user_for_viewer(
+    user: User,
+    profile: Option<Profile>,
+    viewer_id: &str,
+    is_contact: bool,
+) -> ProtoUser {
+    let profile = profile.unwrap_or_else(|| Profile::new(user.id.clone()));
+    let email = if profile.shows_email_to(viewer_id, is_contact) {
+        user.email
+    } else {
+        String::new()
+    };
+
+    ProtoUser {
+        id: user.id,
+        email,
+        display_name: profile.display_name.unwrap_or_default(),
+        status_message: profile.status_message,
+    }
+}
+
+/// Builds the profile sent to `viewer_id`, only the owner gets to see their email visibility.
+pub fn profile_for_viewer(
+    user: User,
+    profile: Option<Profile>,
+    viewer_id: &str,
+    is_contact: bool,
+) -> ProtoProfile {
+    let profile = profile.unwrap_or_else(|| Profile::new(user.id.clone()));
+    let email = if profile.shows_email_to(viewer_id, is_contact) {
+        user.email
+    } else {
+        String::new()
+    };
+    let email_visibility =
+        (viewer_id == profile.user_id).then(|| profile.email_visibility() as i32);
+
+    ProtoProfile {
+        user_id: user.id,
+        email,
+        display_name: profile.display_name.unwrap_or_default(),
+        status_message: profile.status_message,
+        timezone: profile.timezone,
+        email_visibility,
+        updated_at: Some(to_timestamp(profile.updated_at)),
+    }
+}
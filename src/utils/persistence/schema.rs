@@ -1,9 +1,53 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (id) {
+        id -> Int4,
+        owner_user_id -> Text,
+        bot_user_id -> Text,
+        name -> Text,
+        key_prefix -> Text,
+        secret_hash -> Text,
+        scopes -> Array<Text>,
+        created_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    attachments (id) {
+        id -> Int4,
+        uploader_user_id -> Text,
+        chat_id -> Int4,
+        message_id -> Nullable<Int4>,
+        file_name -> Text,
+        content_type -> Text,
+        size -> Int8,
+        storage_key -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    chat_invite_links (code) {
+        code -> Text,
+        chat_id -> Int4,
+        creator_user_id -> Text,
+        max_uses -> Nullable<Int4>,
+        uses -> Int4,
+        created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+    }
+}
+
 diesel::table! {
     chats (id) {
         id -> Int4,
         name -> Text,
+        description -> Text,
+        kind -> Text,
+        direct_key -> Nullable<Text>,
     }
 }
 
@@ -14,6 +58,25 @@ diesel::table! {
         invitee_user_id -> Text,
         chat_id -> Int4,
         created_at -> Timestamptz,
+        expires_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    mentions (message_id, mentioned_user_id) {
+        message_id -> Int4,
+        mentioned_user_id -> Text,
+        chat_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    message_reactions (message_id, user_id, emoji) {
+        message_id -> Int4,
+        user_id -> Text,
+        emoji -> Text,
+        created_at -> Timestamptz,
     }
 }
 
@@ -24,6 +87,46 @@ diesel::table! {
         created_at -> Timestamptz,
         user_id -> Text,
         chat_id -> Int4,
+        edited_at -> Nullable<Timestamptz>,
+        deleted_at -> Nullable<Timestamptz>,
+        reply_to_message_id -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    pinned_messages (message_id) {
+        message_id -> Int4,
+        chat_id -> Int4,
+        pinned_by_user_id -> Text,
+        pinned_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    profiles (user_id) {
+        user_id -> Text,
+        display_name -> Nullable<Text>,
+        status_message -> Text,
+        timezone -> Text,
+        email_visibility -> Text,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_blocks (blocker_user_id, blocked_user_id) {
+        blocker_user_id -> Text,
+        blocked_user_id -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    user_presence (user_id) {
+        user_id -> Text,
+        connections -> Int4,
+        last_seen_at -> Timestamptz,
+        last_active_at -> Timestamptz,
     }
 }
 
@@ -38,19 +141,48 @@ diesel::table! {
     users_chats (user_id, chat_id) {
         user_id -> Text,
         chat_id -> Int4,
+        last_read_message_id -> Nullable<Int4>,
+        role -> Text,
+        joined_at -> Timestamptz,
+        muted -> Bool,
     }
 }
 
+diesel::joinable!(attachments -> chats (chat_id));
+diesel::joinable!(attachments -> messages (message_id));
+diesel::joinable!(attachments -> users (uploader_user_id));
+diesel::joinable!(chat_invite_links -> chats (chat_id));
+diesel::joinable!(chat_invite_links -> users (creator_user_id));
 diesel::joinable!(invites -> chats (chat_id));
+diesel::joinable!(mentions -> chats (chat_id));
+diesel::joinable!(mentions -> messages (message_id));
+diesel::joinable!(mentions -> users (mentioned_user_id));
+diesel::joinable!(message_reactions -> messages (message_id));
+diesel::joinable!(message_reactions -> users (user_id));
 diesel::joinable!(messages -> chats (chat_id));
 diesel::joinable!(messages -> users (user_id));
+diesel::joinable!(pinned_messages -> chats (chat_id));
+diesel::joinable!(pinned_messages -> messages (message_id));
+diesel::joinable!(pinned_messages -> users (pinned_by_user_id));
+diesel::joinable!(profiles -> users (user_id));
+diesel::joinable!(user_presence -> users (user_id));
 diesel::joinable!(users_chats -> chats (chat_id));
+diesel::joinable!(users_chats -> messages (last_read_message_id));
 diesel::joinable!(users_chats -> users (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    attachments,
+    chat_invite_links,
     chats,
     invites,
+    mentions,
+    message_reactions,
     messages,
+    pinned_messages,
+    profiles,
+    user_blocks,
+    user_presence,
     users,
     users_chats,
 );
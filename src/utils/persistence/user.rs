@@ -1,4 +1,5 @@
 use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text};
 
 #[derive(Queryable, Selectable, Insertable)]
 #[diesel(table_name = crate::utils::persistence::schema::users)]
@@ -8,11 +9,23 @@ pub struct User {
     pub email: String,
 }
 
+/// A row of the user directory search, see `UserManager::search_user`.
+#[derive(QueryableByName, Debug)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserSearchHit {
+    #[diesel(sql_type = Text)]
+    pub id: String,
+    #[diesel(sql_type = Bool)]
+    pub is_contact: bool,
+}
+
 impl From<User> for crate::utils::messenger::User {
     fn from(user: User) -> Self {
         Self {
             id: user.id,
             email: user.email,
+            display_name: String::new(),
+            status_message: String::new(),
         }
     }
 }
@@ -0,0 +1,81 @@
+use crate::utils::persistence::schema::user_blocks;
+use diesel::prelude::*;
+use diesel::PgConnection;
+
+#[derive(Queryable, Selectable, Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::user_blocks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserBlock {
+    pub blocker_user_id: String,
+    pub blocked_user_id: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::user_blocks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InsertUserBlock {
+    pub blocker_user_id: String,
+    pub blocked_user_id: String,
+}
+
+/// Ids of the users the given one has blocked.
+pub fn blocked_user_ids(connection: &mut PgConnection, user_id: &str) -> QueryResult<Vec<String>> {
+    user_blocks::table
+        .filter(user_blocks::blocker_user_id.eq(user_id))
+        .select(user_blocks::blocked_user_id)
+        .load::<String>(connection)
+}
+
+/// Whether `blocker_user_id` has blocked `user_id`.
+pub fn is_blocked(
+    connection: &mut PgConnection,
+    blocker_user_id: &str,
+    user_id: &str,
+) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        user_blocks::table
+            .filter(user_blocks::blocker_user_id.eq(blocker_user_id))
+            .filter(user_blocks::blocked_user_id.eq(user_id)),
+    ))
+    .get_result::<bool>(connection)
+}
+
+/// Whether either of the two users has blocked the other.
+pub fn is_blocked_between(
+    connection: &mut PgConnection,
+    user_id: &str,
+    other_user_id: &str,
+) -> QueryResult<bool> {
+    diesel::select(diesel::dsl::exists(
+        user_blocks::table.filter(
+            user_blocks::blocker_user_id
+                .eq(user_id)
+                .and(user_blocks::blocked_user_id.eq(other_user_id))
+                .or(user_blocks::blocker_user_id
+                    .eq(other_user_id)
+                    .and(user_blocks::blocked_user_id.eq(user_id))),
+        ),
+    ))
+    .get_result::<bool>(connection)
+}
+
+/// Subquery of the users the given one has blocked, to filter their content out.
+pub fn blocked_by(
+    user_id: &str,
+) -> user_blocks::BoxedQuery<'_, diesel::pg::Pg, diesel::sql_types::Text> {
+    user_blocks::table
+        .filter(user_blocks::blocker_user_id.eq(user_id))
+        .select(user_blocks::blocked_user_id)
+        .into_boxed()
+}
+
+/// Subquery of the users who have blocked the given one.
+pub fn blockers_of(
+    user_id: &str,
+) -> user_blocks::BoxedQuery<'_, diesel::pg::Pg, diesel::sql_types::Text> {
+    user_blocks::table
+        .filter(user_blocks::blocked_user_id.eq(user_id))
+        .select(user_blocks::blocker_user_id)
+        .into_boxed()
+}
@@ -0,0 +1,67 @@
+use crate::utils::messenger::{Presence as ProtoPresence, PresenceStatus};
+use crate::utils::persistence::message::to_timestamp;
+use crate::utils::persistence::schema::user_presence;
+use diesel::prelude::*;
+use diesel::PgConnection;
+use serde::{Deserialize, Serialize};
+
+/// Seconds without activity after which a connected user is shown as away.
+pub const AWAY_AFTER_SECS: i64 = 5 * 60;
+
+#[derive(Queryable, Selectable, Deserialize, Serialize, Insertable, Debug)]
+#[diesel(table_name = crate::utils::persistence::schema::user_presence)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UserPresence {
+    pub user_id: String,
+    pub connections: i32,
+    pub last_seen_at: chrono::NaiveDateTime,
+    pub last_active_at: chrono::NaiveDateTime,
+}
+
+impl UserPresence {
+    pub fn status(&self, now: chrono::NaiveDateTime) -> PresenceStatus {
+        if self.connections <= 0 {
+            PresenceStatus::Offline
+        } else if (now - self.last_active_at).num_seconds() >= AWAY_AFTER_SECS {
+            PresenceStatus::Away
+        } else {
+            PresenceStatus::Online
+        }
+    }
+}
+
+impl From<UserPresence> for ProtoPresence {
+    fn from(presence: UserPresence) -> Self {
+        ProtoPresence {
+            status: presence.status(chrono::Utc::now().naive_utc()) as i32,
+            user_id: presence.user_id,
+            last_seen_at: Some(to_timestamp(presence.last_seen_at)),
+            last_active_at: Some(to_timestamp(presence.last_active_at)),
+        }
+    }
+}
+
+/// Marks every user as disconnected. Streams don't outlive the server, so the counters
+/// left behind by a crash or a missed disconnect would keep users online forever.
+pub fn reset_connections(connection: &mut PgConnection) -> QueryResult<usize> {
+    diesel::update(user_presence::table)
+        .filter(user_presence::connections.gt(0))
+        .set(user_presence::connections.eq(0))
+        .execute(connection)
+}
+
+/// Status of a presence as of `now_secs`, accounting for users that went idle since
+/// the presence was sent.
+pub fn effective_status(presence: &ProtoPresence, now_secs: i64) -> PresenceStatus {
+    match presence.status() {
+        PresenceStatus::Online
+            if presence
+                .last_active_at
+                .as_ref()
+                .is_some_and(|active| now_secs - active.seconds >= AWAY_AFTER_SECS) =>
+        {
+            PresenceStatus::Away
+        }
+        status => status,
+    }
+}
@@ -1,5 +1,11 @@
 use diesel::{Insertable, Queryable, Selectable};
 
+use crate::utils::messenger::ChatRole;
+
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_ADMIN: &str = "admin";
+pub const ROLE_MEMBER: &str = "member";
+
 #[derive(Queryable, Selectable, Insertable, Debug)]
 #[diesel(table_name = crate::utils::persistence::schema::users_chats)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -9,4 +15,47 @@ use diesel::{Insertable, Queryable, Selectable};
 pub struct UsersChats {
     pub user_id: String,
     pub chat_id: i32,
+    pub last_read_message_id: Option<i32>,
+    pub role: String,
+    pub joined_at: chrono::NaiveDateTime,
+    /// Muted chats stay joined but don't notify the member.
+    pub muted: bool,
+}
+
+impl UsersChats {
+    pub fn new(user_id: String, chat_id: i32, role: ChatRole) -> Self {
+        Self {
+            user_id,
+            chat_id,
+            last_read_message_id: None,
+            role: role_name(role).to_string(),
+            joined_at: chrono::Utc::now().naive_utc(),
+            muted: false,
+        }
+    }
+
+    pub fn role(&self) -> ChatRole {
+        parse_role(&self.role)
+    }
+
+    /// Admins and the owner manage the members of a chat.
+    pub fn is_admin(&self) -> bool {
+        self.role() >= ChatRole::Admin
+    }
+}
+
+pub fn role_name(role: ChatRole) -> &'static str {
+    match role {
+        ChatRole::Owner => ROLE_OWNER,
+        ChatRole::Admin => ROLE_ADMIN,
+        ChatRole::Member => ROLE_MEMBER,
+    }
+}
+
+pub fn parse_role(role: &str) -> ChatRole {
+    match role {
+        ROLE_OWNER => ChatRole::Owner,
+        ROLE_ADMIN => ChatRole::Admin,
+        _ => ChatRole::Member,
+    }
 }
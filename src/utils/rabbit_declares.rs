@@ -1,6 +1,6 @@
 use amqprs::channel::{
-    BasicPublishArguments, Channel, ExchangeDeclareArguments, ExchangeType, QueueBindArguments,
-    QueueDeclareArguments,
+    BasicPublishArguments, Channel, ExchangeDeclareArguments, ExchangeDeleteArguments,
+    ExchangeType, QueueBindArguments, QueueDeclareArguments,
 };
 use amqprs::error::Error;
 use amqprs::FieldValue::u;
@@ -20,6 +20,10 @@ pub const ACCEPT_INVITES_EXCHANGE: &str = "W_AcceptInviteExchange";
 
 pub const CHAT_CONNECT_EXCHANGE: &str = "S_ChatConnectExchange";
 
+pub const REACTION_EXCHANGE: &str = "W_ReactionExchange";
+
+pub const PRESENCE_EXCHANGE: &str = "S_PresenceExchange";
+
 pub async fn declare_chat_connect_exchange(channel: &Channel, user_id: &str) -> Result<(), Error> {
     channel
         .exchange_declare(
@@ -42,6 +46,28 @@ pub fn chat_connect_exchange_name(user_id: &str) -> String {
     format!("{}-{}", CHAT_CONNECT_EXCHANGE, user_id)
 }
 
+pub async fn declare_presence_exchange(channel: &Channel, user_id: &str) -> Result<(), Error> {
+    channel
+        .exchange_declare(
+            ExchangeDeclareArguments::of_type(
+                &presence_exchange_name(user_id),
+                ExchangeType::Fanout,
+            )
+            .passive(false)
+            .durable(false)
+            .auto_delete(false)
+            .internal(false)
+            .no_wait(false)
+            .arguments(FieldTable::default())
+            .finish(),
+        )
+        .await
+}
+
+pub fn presence_exchange_name(user_id: &str) -> String {
+    format!("{}-{}", PRESENCE_EXCHANGE, user_id)
+}
+
 pub async fn declare_accept_invites_exchange(channel: &Channel) -> Result<(), Error> {
     channel
         .exchange_declare(ExchangeDeclareArguments::new(
@@ -60,6 +86,12 @@ pub async fn declare_new_message_exchange(channel: &Channel) -> Result<(), Error
         .await
 }
 
+pub async fn declare_reaction_exchange(channel: &Channel) -> Result<(), Error> {
+    channel
+        .exchange_declare(ExchangeDeclareArguments::new(REACTION_EXCHANGE, "direct"))
+        .await
+}
+
 pub async fn declare_send_invite_exchange(channel: &Channel) -> Result<(), Error> {
     channel
         .exchange_declare(ExchangeDeclareArguments::new(
@@ -90,6 +122,13 @@ pub fn messages_exchange_name(chat: &str) -> String {
     format!("{}-{}", MESSAGES_EXCHANGE, chat)
 }
 
+pub async fn delete_messages_exchange(channel: &Channel, chat: &str) -> Result<(), Error> {
+    debug!("Deleting messages exchange: {}", chat);
+    channel
+        .exchange_delete(ExchangeDeleteArguments::new(&messages_exchange_name(chat)))
+        .await
+}
+
 pub async fn declare_invites_exchange(channel: &Channel, user_id: &str) -> Result<(), Error> {
     channel
         .exchange_declare(
@@ -1,7 +1,192 @@
 use serde::{Deserialize, Serialize};
 
+use crate::utils::messenger::chat_event::Event;
+use crate::utils::messenger::invite_event::Event as InviteEventKind;
+use crate::utils::messenger::{
+    ChatDeleted, ChatEvent, InviteEvent, InviteRevoked, MemberRemoved, Message as ProtoMessage,
+    MessageReactions, MessageUnpinned, Typing,
+};
+use crate::utils::persistence::attachment::Attachment;
+use crate::utils::persistence::chat::Chat;
+use crate::utils::persistence::invite::Invite;
+use crate::utils::persistence::message::{InsertMessage, Message};
+use crate::utils::persistence::message_reaction::{aggregate_reactions, MessageReaction};
+use crate::utils::persistence::pinned_message::{to_proto_pinned_message, PinnedMessage};
+
 #[derive(Serialize, Deserialize)]
 pub struct RabbitInviteAccept {
     pub invite_id: i32,
     pub user_id: String,
 }
+
+#[derive(Serialize, Deserialize)]
+pub struct RabbitJoinByCode {
+    pub code: String,
+    pub user_id: String,
+}
+
+/// Adds a user to a chat, whether they were invited or have an invite link.
+#[derive(Serialize, Deserialize)]
+pub enum RabbitChatJoin {
+    Invite(RabbitInviteAccept),
+    Code(RabbitJoinByCode),
+}
+
+/// Message sent on a Chat stream, queued for the worker to store.
+#[derive(Serialize, Deserialize)]
+pub struct RabbitNewMessage {
+    #[serde(flatten)]
+    pub message: InsertMessage,
+    #[serde(default)]
+    pub attachment_ids: Vec<i32>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RabbitReactionChange {
+    pub message_id: i32,
+    pub user_id: String,
+    pub emoji: String,
+    pub added: bool,
+}
+
+/// Update sent to a user's live Invites streams over their invites exchange.
+#[derive(Serialize, Deserialize)]
+pub enum RabbitInviteEvent {
+    NewInvite(Invite),
+    InviteRevoked { invite_id: i32, chat_id: i32 },
+}
+
+impl From<RabbitInviteEvent> for InviteEvent {
+    fn from(rabbit_event: RabbitInviteEvent) -> Self {
+        let event = match rabbit_event {
+            RabbitInviteEvent::NewInvite(invite) => InviteEventKind::NewInvite(invite.into()),
+            RabbitInviteEvent::InviteRevoked { invite_id, chat_id } => {
+                InviteEventKind::InviteRevoked(InviteRevoked { invite_id, chat_id })
+            }
+        };
+
+        InviteEvent { event: Some(event) }
+    }
+}
+
+/// Command sent to a user's live Chat streams over their chat connect exchange.
+#[derive(Serialize, Deserialize)]
+pub enum RabbitChatConnect {
+    Connect(i32),
+    Disconnect(i32),
+    /// The user blocked someone, whose messages the streams stop forwarding.
+    Block(String),
+    Unblock(String),
+    /// The user was mentioned in a message, the streams pass it on to the client.
+    Mention(Message),
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum RabbitChatEvent {
+    NewMessage(Message, Vec<Attachment>),
+    MessageEdited(Message),
+    MessageDeleted(Message),
+    ReactionsChanged {
+        chat_id: i32,
+        message_id: i32,
+        reactions: Vec<MessageReaction>,
+    },
+    TypingStarted {
+        chat_id: i32,
+        user_id: String,
+    },
+    TypingStopped {
+        chat_id: i32,
+        user_id: String,
+    },
+    MemberRemoved {
+        chat_id: i32,
+        user_id: String,
+        removed_by: String,
+    },
+    ChatUpdated(Chat),
+    ChatDeleted {
+        chat_id: i32,
+    },
+    MessagePinned(PinnedMessage, Message),
+    MessageUnpinned {
+        chat_id: i32,
+        message_id: i32,
+        unpinned_by: String,
+    },
+}
+
+impl RabbitChatEvent {
+    /// Author of a message or typing event, hidden from the users who blocked them.
+    pub fn author_user_id(&self) -> Option<&str> {
+        match self {
+            RabbitChatEvent::NewMessage(message, _)
+            | RabbitChatEvent::MessageEdited(message)
+            | RabbitChatEvent::MessageDeleted(message)
+            | RabbitChatEvent::MessagePinned(_, message) => Some(&message.user_id),
+            _ => self.typing_user_id(),
+        }
+    }
+
+    /// Author of an ephemeral event, which shouldn't be echoed back to them.
+    pub fn typing_user_id(&self) -> Option<&str> {
+        match self {
+            RabbitChatEvent::TypingStarted { user_id, .. }
+            | RabbitChatEvent::TypingStopped { user_id, .. } => Some(user_id),
+            _ => None,
+        }
+    }
+}
+
+impl From<RabbitChatEvent> for ChatEvent {
+    fn from(rabbit_event: RabbitChatEvent) -> Self {
+        let event = match rabbit_event {
+            RabbitChatEvent::NewMessage(message, attachments) => Event::NewMessage(ProtoMessage {
+                attachments: attachments.into_iter().map(Into::into).collect(),
+                ..message.into()
+            }),
+            RabbitChatEvent::MessageEdited(message) => Event::MessageEdited(message.into()),
+            RabbitChatEvent::MessageDeleted(message) => Event::MessageDeleted(message.into()),
+            RabbitChatEvent::ReactionsChanged {
+                chat_id,
+                message_id,
+                reactions,
+            } => Event::ReactionsChanged(MessageReactions {
+                message_id,
+                chat_id,
+                reactions: aggregate_reactions(&reactions),
+            }),
+            RabbitChatEvent::TypingStarted { chat_id, user_id } => {
+                Event::TypingStarted(Typing { chat_id, user_id })
+            }
+            RabbitChatEvent::TypingStopped { chat_id, user_id } => {
+                Event::TypingStopped(Typing { chat_id, user_id })
+            }
+            RabbitChatEvent::MemberRemoved {
+                chat_id,
+                user_id,
+                removed_by,
+            } => Event::MemberRemoved(MemberRemoved {
+                chat_id,
+                user_id,
+                removed_by,
+            }),
+            RabbitChatEvent::ChatUpdated(chat) => Event::ChatUpdated(chat.into()),
+            RabbitChatEvent::ChatDeleted { chat_id } => Event::ChatDeleted(ChatDeleted { chat_id }),
+            RabbitChatEvent::MessagePinned(pin, message) => {
+                Event::MessagePinned(to_proto_pinned_message(pin, message))
+            }
+            RabbitChatEvent::MessageUnpinned {
+                chat_id,
+                message_id,
+                unpinned_by,
+            } => Event::MessageUnpinned(MessageUnpinned {
+                chat_id,
+                message_id,
+                unpinned_by,
+            }),
+        };
+
+        ChatEvent { event: Some(event) }
+    }
+}
@@ -14,14 +14,16 @@ use crate::utils::rabbit_channel_manager::{
 };
 use crate::utils::rabbit_declares::{
     declare_accept_invites_exchange, declare_invites_exchange, declare_new_message_exchange,
-    declare_send_invite_exchange, setup_error_handling, ACCEPT_INVITES_EXCHANGE,
-    NEW_MESSAGE_EXCHANGE, SEND_INVITE_EXCHANGE,
+    declare_reaction_exchange, declare_send_invite_exchange, setup_error_handling,
+    ACCEPT_INVITES_EXCHANGE, NEW_MESSAGE_EXCHANGE, REACTION_EXCHANGE, SEND_INVITE_EXCHANGE,
 };
 use crate::worker::new_message_consumer::NewMessageConsumer;
+use crate::worker::reaction_consumer::ReactionConsumer;
 use crate::worker::send_invite_consumer::SendInviteConsumer;
 
 mod accept_invite_consumer;
 mod new_message_consumer;
+mod reaction_consumer;
 mod send_invite_consumer;
 
 #[async_trait]
@@ -173,6 +175,41 @@ impl Worker for WorkerImpl {
                 e
             })?;
 
+        declare_reaction_exchange(&channel).await.map_err(|e| {
+            error!("Failed to declare exchange: {:?}", e);
+            e
+        })?;
+        let reaction_queue = "reaction_queue";
+        channel
+            .queue_declare(QueueDeclareArguments::durable_client_named(reaction_queue))
+            .await
+            .map_err(|e| {
+                error!("Failed to declare queue: {:?}", e);
+                e
+            })?;
+
+        channel
+            .queue_bind(QueueBindArguments::new(
+                reaction_queue,
+                REACTION_EXCHANGE,
+                "",
+            ))
+            .await
+            .map_err(|e| {
+                error!("Failed to bind queue: {:?}", e);
+                e
+            })?;
+
+        let reaction_consumer = ReactionConsumer::new(self.connection_manager.clone());
+        let args = BasicConsumeArguments::new(reaction_queue, "worker-reactions");
+        channel
+            .basic_consume(reaction_consumer, args)
+            .await
+            .map_err(|e| {
+                error!("Failed to consume: {:?}", e);
+                e
+            })?;
+
         signal::ctrl_c().await.map_err(|e| {
             error!("Failed to wait for ctrl-c: {:?}", e);
             e
@@ -10,14 +10,18 @@ use tonic::Status;
 use tracing::{debug, error, info, instrument};
 
 use crate::utils::db_connection_manager::DBConnectionManager;
+use crate::utils::messenger::ChatRole;
+use crate::utils::persistence::chat_invite_link::ChatInviteLink;
 use crate::utils::persistence::invite::Invite;
-use crate::utils::persistence::schema::{invites, users_chats};
+use crate::utils::persistence::schema::{chat_invite_links, invites, users_chats};
 use crate::utils::persistence::users_chats::UsersChats;
 use crate::utils::rabbit_declares::{
     chat_connect_exchange_name, declare_chat_connect_exchange, CHAT_CONNECT_EXCHANGE,
     INVITES_EXCHANGE,
 };
-use crate::utils::rabbit_types::RabbitInviteAccept;
+use crate::utils::rabbit_types::{
+    RabbitChatConnect, RabbitChatJoin, RabbitInviteAccept, RabbitJoinByCode,
+};
 
 #[derive(Clone)]
 pub struct AcceptInviteConsumer {
@@ -58,17 +62,32 @@ impl AcceptInviteConsumer {
         content: &[u8],
     ) -> Result<(), anyhow::Error> {
         let mut db_connection = self.connection_manager.get_connection()?;
-        let accept_invite = self.deserialize_message(content)?;
-        let chat_id = self
-            .accept_invite(&mut db_connection, &accept_invite)
-            .await
-            .map_err(|e| {
-                error!("Failed to accept invite: {:?}", e);
-                Status::internal("Failed to accept invite")
-            })?;
+        let (chat_id, user_id) = match self.deserialize_message(content)? {
+            RabbitChatJoin::Invite(accept_invite) => {
+                let chat_id = self
+                    .accept_invite(&mut db_connection, &accept_invite)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to accept invite: {:?}", e);
+                        Status::internal("Failed to accept invite")
+                    })?;
+                info!("Accepted invite to chat {}", chat_id);
+                (chat_id, accept_invite.user_id)
+            }
+            RabbitChatJoin::Code(join_by_code) => {
+                let chat_id = self
+                    .join_by_code(&mut db_connection, &join_by_code)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to join by code: {:?}", e);
+                        Status::internal("Failed to join by code")
+                    })?;
+                info!("Joined chat {} by code", chat_id);
+                (chat_id, join_by_code.user_id)
+            }
+        };
 
-        info!("Accepted invite to chat {}", chat_id);
-        self.notify_chats(channel, chat_id, &accept_invite.user_id)
+        self.notify_chats(channel, chat_id, &user_id)
             .await
             .map_err(|e| {
                 error!("Failed to notify chats_container: {:?}", e);
@@ -90,16 +109,16 @@ impl AcceptInviteConsumer {
     ) -> Result<i32, anyhow::Error> {
         let invite = invites::table
             .filter(invites::id.eq(invite_accept.invite_id))
+            .filter(invites::expires_at.gt(diesel::dsl::now))
             .first::<Invite>(db_connection)?;
 
         info!("Accepted invite: {:?}", invite);
-        let user_chat = UsersChats {
-            user_id: invite.invitee_user_id,
-            chat_id: invite.chat_id,
-        };
+        let user_chat = UsersChats::new(invite.invitee_user_id, invite.chat_id, ChatRole::Member);
 
+        // The invitee may have joined by link meanwhile, the invite is used up either way.
         diesel::insert_into(users_chats::table)
             .values(user_chat)
+            .on_conflict_do_nothing()
             .execute(db_connection)
             .map_err(|e| {
                 error!("Failed to insert user_chat: {:?}", e);
@@ -117,6 +136,44 @@ impl AcceptInviteConsumer {
         Ok(invite.chat_id)
     }
 
+    /// Uses of the link are counted once per new member, joining twice is a no-op.
+    #[instrument(skip(self, db_connection, join_by_code))]
+    async fn join_by_code(
+        &self,
+        db_connection: &mut PgConnection,
+        join_by_code: &RabbitJoinByCode,
+    ) -> Result<i32, anyhow::Error> {
+        db_connection.transaction(|connection| {
+            let link = chat_invite_links::table
+                .find(&join_by_code.code)
+                .for_update()
+                .first::<ChatInviteLink>(connection)?;
+
+            if link.is_expired() || link.is_used_up() {
+                return Err(anyhow::anyhow!(
+                    "Invite link {} is no longer valid",
+                    link.code
+                ));
+            }
+
+            let user_chat =
+                UsersChats::new(join_by_code.user_id.clone(), link.chat_id, ChatRole::Member);
+            let inserted = diesel::insert_into(users_chats::table)
+                .values(user_chat)
+                .on_conflict((users_chats::user_id, users_chats::chat_id))
+                .do_nothing()
+                .execute(connection)?;
+
+            if inserted > 0 {
+                diesel::update(chat_invite_links::table.find(&link.code))
+                    .set(chat_invite_links::uses.eq(chat_invite_links::uses + 1))
+                    .execute(connection)?;
+            }
+
+            Ok(link.chat_id)
+        })
+    }
+
     #[instrument(skip(self, channel))]
     async fn notify_chats(
         &self,
@@ -131,10 +188,11 @@ impl AcceptInviteConsumer {
                 anyhow::Error::new(e)
             })?;
 
+        let connect = serde_json::to_string(&RabbitChatConnect::Connect(chat_id))?;
         channel
             .basic_publish(
                 BasicProperties::default(),
-                chat_id.to_string().into_bytes(),
+                connect.into_bytes(),
                 BasicPublishArguments::new(&chat_connect_exchange_name(&user_id), "")
                     .mandatory(false)
                     .immediate(false)
@@ -148,12 +206,12 @@ impl AcceptInviteConsumer {
     }
 
     #[instrument(skip(self, content))]
-    fn deserialize_message(&self, content: &[u8]) -> Result<RabbitInviteAccept, anyhow::Error> {
-        let invite_accept: RabbitInviteAccept = serde_json::from_slice(content).map_err(|e| {
+    fn deserialize_message(&self, content: &[u8]) -> Result<RabbitChatJoin, anyhow::Error> {
+        let chat_join: RabbitChatJoin = serde_json::from_slice(content).map_err(|e| {
             error!("Failed to deserialize invite: {:?}", e);
             anyhow::Error::new(e)
         })?;
-        Ok(invite_accept)
+        Ok(chat_join)
     }
 
     #[instrument(skip(self, channel, deliver, requeue, content))]
@@ -9,10 +9,24 @@ use tonic::Status;
 use tracing::{debug, error, info, instrument, warn};
 
 use crate::utils::db_connection_manager::DBConnectionManager;
+use crate::utils::persistence::attachment::Attachment;
+use crate::utils::persistence::mention::{mention_handles, parse_mentions, InsertMention};
 use crate::utils::persistence::message::{InsertMessage, Message};
-use crate::utils::persistence::schema::{messages, users_chats};
+use crate::utils::persistence::profile::Profile;
+use crate::utils::persistence::schema::{
+    attachments, mentions, messages, profiles, users, users_chats,
+};
+use crate::utils::persistence::user::User;
+use crate::utils::persistence::user_block::blockers_of;
 use crate::utils::persistence::users_chats::UsersChats;
-use crate::utils::rabbit_declares::{send_to_error_queue, MESSAGES_EXCHANGE, messages_exchange_name};
+use crate::utils::rabbit_declares::{
+    chat_connect_exchange_name, declare_chat_connect_exchange, messages_exchange_name,
+    send_to_error_queue,
+};
+use crate::utils::rabbit_types::{RabbitChatConnect, RabbitChatEvent, RabbitNewMessage};
+
+/// Most attachments a single message can carry.
+const MAX_ATTACHMENTS_PER_MESSAGE: usize = 10;
 
 #[derive(Clone)]
 pub struct NewMessageConsumer {
@@ -52,16 +66,20 @@ impl NewMessageConsumer {
         content: &[u8],
     ) -> Result<(), anyhow::Error> {
         let mut db_connection = self.connection_manager.get_connection()?;
-        let insert_message = self.deserialize_message(content)?;
+        let new_message = self.deserialize_message(content)?;
         if self
             .check_authority(
                 &mut db_connection,
-                insert_message.chat_id,
-                &insert_message.user_id,
+                new_message.message.chat_id,
+                &new_message.message.user_id,
             )
             .await?
+            && self
+                .check_reply(&mut db_connection, &new_message.message)
+                .await?
+            && self.check_attachments(&new_message)
         {
-            self.insert_and_publish_message(&mut db_connection, channel, &insert_message, deliver)
+            self.insert_and_publish_message(&mut db_connection, channel, &new_message, deliver)
                 .await?;
         }
         channel
@@ -90,45 +108,206 @@ impl NewMessageConsumer {
         Ok(chat.is_some())
     }
 
-    fn deserialize_message(&self, content: &[u8]) -> Result<InsertMessage, serde_json::Error> {
+    async fn check_reply(
+        &self,
+        db_connection: &mut PgConnection,
+        insert_message: &InsertMessage,
+    ) -> Result<bool, anyhow::Error> {
+        let parent_id = match insert_message.reply_to_message_id {
+            Some(parent_id) => parent_id,
+            None => return Ok(true),
+        };
+
+        let parent_chat_id = messages::table
+            .filter(messages::id.eq(parent_id))
+            .select(messages::chat_id)
+            .first::<i32>(db_connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get parent message: {}", e);
+                Status::internal("Failed to get parent message")
+            })?;
+
+        if parent_chat_id != Some(insert_message.chat_id) {
+            warn!(
+                "Message {} is not in chat {}, dropping reply",
+                parent_id, insert_message.chat_id
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    fn check_attachments(&self, new_message: &RabbitNewMessage) -> bool {
+        if new_message.attachment_ids.len() > MAX_ATTACHMENTS_PER_MESSAGE {
+            warn!(
+                "Message has {} attachments, at most {} are allowed, dropping it",
+                new_message.attachment_ids.len(),
+                MAX_ATTACHMENTS_PER_MESSAGE
+            );
+            return false;
+        }
+
+        true
+    }
+
+    fn deserialize_message(&self, content: &[u8]) -> Result<RabbitNewMessage, serde_json::Error> {
         let message_str = String::from_utf8_lossy(content);
-        serde_json::from_str::<InsertMessage>(&message_str)
+        serde_json::from_str::<RabbitNewMessage>(&message_str)
     }
 
     async fn insert_and_publish_message(
         &self,
         db_connection: &mut PgConnection,
         channel: &Channel,
-        insert_message: &InsertMessage,
+        new_message: &RabbitNewMessage,
         deliver: &Deliver,
     ) -> Result<(), anyhow::Error> {
-        let message = self.insert_message(db_connection, insert_message)?;
-        self.publish_message(channel, &message, deliver).await?;
+        let (message, attachments, notified_user_ids) =
+            self.insert_message(db_connection, new_message)?;
+        let chat_id = message.chat_id;
+        self.publish_message(
+            channel,
+            chat_id,
+            &RabbitChatEvent::NewMessage(message.clone(), attachments),
+            deliver,
+        )
+        .await?;
+
+        for user_id in notified_user_ids {
+            self.publish_mention(channel, &user_id, &message).await?;
+        }
         Ok(())
     }
 
+    /// Stores the message with its mentions and claims its attachments. Only unsent
+    /// attachments the author uploaded to the same chat are claimed, other ids are ignored.
+    /// Returns the mentioned users to notify as well.
     fn insert_message(
         &self,
         db_connection: &mut PgConnection,
-        insert_message: &InsertMessage,
-    ) -> Result<Message, diesel::result::Error> {
-        diesel::insert_into(messages::table)
-            .values(insert_message)
-            .get_result(db_connection)
+        new_message: &RabbitNewMessage,
+    ) -> Result<(Message, Vec<Attachment>, Vec<String>), diesel::result::Error> {
+        db_connection.transaction(|connection| {
+            let message = diesel::insert_into(messages::table)
+                .values(&new_message.message)
+                .get_result::<Message>(connection)?;
+
+            let mut message_attachments = diesel::update(attachments::table)
+                .filter(attachments::id.eq_any(&new_message.attachment_ids))
+                .filter(attachments::uploader_user_id.eq(&message.user_id))
+                .filter(attachments::chat_id.eq(message.chat_id))
+                .filter(attachments::message_id.is_null())
+                .set(attachments::message_id.eq(message.id))
+                .get_results::<Attachment>(connection)?;
+            message_attachments.sort_by_key(|attachment| attachment.id);
+
+            let notified_user_ids = self.insert_mentions(connection, &message)?;
+
+            Ok((message, message_attachments, notified_user_ids))
+        })
     }
 
-    async fn publish_message(
+    /// Resolves the `@handles` of the message against the other chat members. Members who
+    /// blocked the author aren't mentioned, those who muted the chat aren't notified.
+    fn insert_mentions(
+        &self,
+        connection: &mut PgConnection,
+        message: &Message,
+    ) -> Result<Vec<String>, diesel::result::Error> {
+        let handles = parse_mentions(&message.text);
+        if handles.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let members = users_chats::table
+            .inner_join(users::table.left_join(profiles::table))
+            .filter(users_chats::chat_id.eq(message.chat_id))
+            .filter(users_chats::user_id.ne(&message.user_id))
+            .filter(diesel::dsl::not(
+                users_chats::user_id.eq_any(blockers_of(&message.user_id)),
+            ))
+            .select((
+                users_chats::muted,
+                User::as_select(),
+                Option::<Profile>::as_select(),
+            ))
+            .load::<(bool, User, Option<Profile>)>(connection)?;
+
+        let mentioned: Vec<_> = members
+            .into_iter()
+            .filter(|(_, user, profile)| {
+                mention_handles(user, profile.as_ref(), &message.user_id)
+                    .iter()
+                    .any(|handle| handles.contains(handle))
+            })
+            .map(|(muted, user, _)| (muted, user.id))
+            .collect();
+
+        if mentioned.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let insert_mentions: Vec<_> = mentioned
+            .iter()
+            .map(|(_, user_id)| InsertMention {
+                message_id: message.id,
+                mentioned_user_id: user_id.clone(),
+                chat_id: message.chat_id,
+            })
+            .collect();
+        diesel::insert_into(mentions::table)
+            .values(&insert_mentions)
+            .execute(connection)?;
+
+        debug!(
+            "Message {} mentions {} users",
+            message.id,
+            insert_mentions.len()
+        );
+        Ok(mentioned
+            .into_iter()
+            .filter(|(muted, _)| !muted)
+            .map(|(_, user_id)| user_id)
+            .collect())
+    }
+
+    /// Tells the mentioned user's live Chat streams over their chat connect exchange.
+    async fn publish_mention(
         &self,
         channel: &Channel,
+        user_id: &str,
         message: &Message,
+    ) -> Result<(), anyhow::Error> {
+        declare_chat_connect_exchange(channel, user_id).await?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serde_json::to_string(&RabbitChatConnect::Mention(message.clone()))?.into_bytes(),
+                BasicPublishArguments::new(&chat_connect_exchange_name(user_id), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn publish_message(
+        &self,
+        channel: &Channel,
+        chat_id: i32,
+        event: &RabbitChatEvent,
         deliver: &Deliver,
     ) -> Result<(), anyhow::Error> {
-        let serialized_message = serde_json::to_string(message)?;
+        let serialized_message = serde_json::to_string(event)?;
         channel
             .basic_publish(
                 BasicProperties::default(),
                 serialized_message.into_bytes(),
-                BasicPublishArguments::new(&messages_exchange_name(&message.chat_id.to_string()), "")
+                BasicPublishArguments::new(&messages_exchange_name(&chat_id.to_string()), "")
                     .mandatory(false)
                     .immediate(false)
                     .finish(),
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use amqprs::channel::{BasicAckArguments, BasicPublishArguments, BasicRejectArguments};
+use amqprs::{channel::Channel, consumer::AsyncConsumer, BasicProperties, Deliver};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use tonic::Status;
+use tracing::{debug, error, instrument, warn};
+
+use crate::utils::db_connection_manager::DBConnectionManager;
+use crate::utils::persistence::message_reaction::{InsertMessageReaction, MessageReaction};
+use crate::utils::persistence::schema::{message_reactions, messages, users_chats};
+use crate::utils::persistence::users_chats::UsersChats;
+use crate::utils::rabbit_declares::{
+    declare_messages_exchange, messages_exchange_name, send_to_error_queue,
+};
+use crate::utils::rabbit_types::{RabbitChatEvent, RabbitReactionChange};
+
+#[derive(Clone)]
+pub struct ReactionConsumer {
+    connection_manager: Arc<dyn DBConnectionManager>,
+}
+
+#[async_trait]
+impl AsyncConsumer for ReactionConsumer {
+    #[instrument(skip(self, channel, content))]
+    async fn consume(
+        &mut self,
+        channel: &Channel,
+        deliver: Deliver,
+        _: BasicProperties,
+        content: Vec<u8>,
+    ) {
+        debug!("Received reaction");
+        if let Err(e) = self.process_reaction(channel, &deliver, &content).await {
+            error!("Failed to process reaction: {:?}", e);
+            if let Err(e) = self.reject_message(channel, &deliver, false, content).await {
+                error!("Failed to reject message: {:?}", e);
+            };
+        }
+        debug!("Reaction processed");
+    }
+}
+
+impl ReactionConsumer {
+    pub fn new(connection_manager: Arc<dyn DBConnectionManager>) -> Self {
+        Self { connection_manager }
+    }
+
+    async fn process_reaction(
+        &self,
+        channel: &Channel,
+        deliver: &Deliver,
+        content: &[u8],
+    ) -> Result<(), anyhow::Error> {
+        let mut db_connection = self.connection_manager.get_connection()?;
+        let reaction_change = self.deserialize_message(content)?;
+
+        if let Some(chat_id) = self
+            .check_authority(&mut db_connection, &reaction_change)
+            .await?
+        {
+            self.apply_reaction(&mut db_connection, &reaction_change)?;
+
+            let reactions = message_reactions::table
+                .filter(message_reactions::message_id.eq(reaction_change.message_id))
+                .order(message_reactions::created_at.asc())
+                .load::<MessageReaction>(&mut db_connection)
+                .map_err(|e| {
+                    error!("Failed to get reactions: {}", e);
+                    Status::internal("Failed to get reactions")
+                })?;
+
+            self.publish_reactions(
+                channel,
+                chat_id,
+                &RabbitChatEvent::ReactionsChanged {
+                    chat_id,
+                    message_id: reaction_change.message_id,
+                    reactions,
+                },
+            )
+            .await?;
+        }
+
+        channel
+            .basic_ack(BasicAckArguments::new(deliver.delivery_tag(), false))
+            .await
+            .map_err(anyhow::Error::new)?;
+
+        Ok(())
+    }
+
+    /// Returns the chat of the reacted message if the user is a member of it and the
+    /// message has not been deleted.
+    async fn check_authority(
+        &self,
+        db_connection: &mut PgConnection,
+        reaction_change: &RabbitReactionChange,
+    ) -> Result<Option<i32>, anyhow::Error> {
+        let chat_id = messages::table
+            .filter(messages::id.eq(reaction_change.message_id))
+            .filter(messages::deleted_at.is_null())
+            .select(messages::chat_id)
+            .first::<i32>(db_connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get message: {}", e);
+                Status::internal("Failed to get message")
+            })?;
+
+        let chat_id = match chat_id {
+            Some(chat_id) => chat_id,
+            None => {
+                warn!(
+                    "Message {} does not exist, dropping reaction",
+                    reaction_change.message_id
+                );
+                return Ok(None);
+            }
+        };
+
+        let membership = users_chats::table
+            .filter(users_chats::chat_id.eq(chat_id))
+            .filter(users_chats::user_id.eq(&reaction_change.user_id))
+            .first::<UsersChats>(db_connection)
+            .optional()
+            .map_err(|e| {
+                error!("Failed to get chat: {}", e);
+                Status::internal("Failed to get chat")
+            })?;
+
+        if membership.is_none() {
+            warn!(
+                "User {} is not a member of chat {}, dropping reaction",
+                reaction_change.user_id, chat_id
+            );
+            return Ok(None);
+        }
+
+        Ok(Some(chat_id))
+    }
+
+    fn apply_reaction(
+        &self,
+        db_connection: &mut PgConnection,
+        reaction_change: &RabbitReactionChange,
+    ) -> Result<(), diesel::result::Error> {
+        if reaction_change.added {
+            diesel::insert_into(message_reactions::table)
+                .values(InsertMessageReaction {
+                    message_id: reaction_change.message_id,
+                    user_id: reaction_change.user_id.clone(),
+                    emoji: reaction_change.emoji.clone(),
+                })
+                .on_conflict_do_nothing()
+                .execute(db_connection)?;
+        } else {
+            diesel::delete(message_reactions::table)
+                .filter(message_reactions::message_id.eq(reaction_change.message_id))
+                .filter(message_reactions::user_id.eq(&reaction_change.user_id))
+                .filter(message_reactions::emoji.eq(&reaction_change.emoji))
+                .execute(db_connection)?;
+        }
+        Ok(())
+    }
+
+    fn deserialize_message(
+        &self,
+        content: &[u8],
+    ) -> Result<RabbitReactionChange, serde_json::Error> {
+        serde_json::from_slice::<RabbitReactionChange>(content)
+    }
+
+    async fn publish_reactions(
+        &self,
+        channel: &Channel,
+        chat_id: i32,
+        event: &RabbitChatEvent,
+    ) -> Result<(), anyhow::Error> {
+        declare_messages_exchange(channel, &chat_id.to_string()).await?;
+
+        let serialized_event = serde_json::to_string(event)?;
+        channel
+            .basic_publish(
+                BasicProperties::default(),
+                serialized_event.into_bytes(),
+                BasicPublishArguments::new(&messages_exchange_name(&chat_id.to_string()), "")
+                    .mandatory(false)
+                    .immediate(false)
+                    .finish(),
+            )
+            .await
+            .map_err(anyhow::Error::new)?;
+
+        debug!("Reactions published successfully");
+        Ok(())
+    }
+
+    #[instrument(skip(self, channel))]
+    async fn reject_message(
+        &self,
+        channel: &Channel,
+        deliver: &Deliver,
+        requeue: bool,
+        content: Vec<u8>,
+    ) -> Result<(), anyhow::Error> {
+        warn!("Rejecting reaction");
+        send_to_error_queue(channel, content).await?;
+
+        channel
+            .basic_reject(BasicRejectArguments::new(deliver.delivery_tag(), requeue))
+            .await
+            .map_err(|e| {
+                error!("Failed to reject message: {:?}", e);
+                anyhow::Error::new(e)
+            })
+    }
+}
@@ -4,15 +4,18 @@ use amqprs::channel::{BasicAckArguments, BasicPublishArguments, BasicRejectArgum
 use amqprs::consumer::AsyncConsumer;
 use amqprs::{BasicProperties, Deliver};
 use async_trait::async_trait;
-use diesel::{PgConnection, RunQueryDsl};
+use diesel::prelude::*;
+use diesel::PgConnection;
 use tracing::{debug, error, instrument, warn};
 
 use crate::utils::db_connection_manager::DBConnectionManager;
 use crate::utils::persistence::invite::{InsertInvite, Invite};
-use crate::utils::persistence::schema::invites;
+use crate::utils::persistence::schema::{invites, users_chats};
+use crate::utils::persistence::user_block::is_blocked;
 use crate::utils::rabbit_declares::{
     declare_invites_exchange, invites_exchange_name, send_to_error_queue, INVITES_EXCHANGE,
 };
+use crate::utils::rabbit_types::RabbitInviteEvent;
 
 #[derive(Clone)]
 pub struct SendInviteConsumer {
@@ -78,38 +81,73 @@ impl SendInviteConsumer {
         insert_invite: &InsertInvite,
         deliver: &Deliver,
     ) -> Result<(), anyhow::Error> {
-        let invite = self.insert_invite(db_connection, insert_invite)?;
-        self.publish_message(channel, &invite, deliver).await?;
+        match self.insert_invite(db_connection, insert_invite)? {
+            Some(invite) => self.publish_message(channel, invite, deliver).await?,
+            None => debug!("Invitee is already a member, invited or blocks the inviter, skipping"),
+        }
         Ok(())
     }
 
+    /// Inserts the invite unless the invitee is a member, has a pending invite to the chat
+    /// or has blocked the inviter, an expired invite gets replaced.
     #[instrument(skip(self, db_connection, insert_invite))]
     fn insert_invite(
         &self,
         db_connection: &mut PgConnection,
         insert_invite: &InsertInvite,
-    ) -> Result<Invite, diesel::result::Error> {
-        diesel::insert_into(invites::table)
-            .values(insert_invite)
-            .get_result(db_connection)
+    ) -> Result<Option<Invite>, diesel::result::Error> {
+        db_connection.transaction(|connection| {
+            // Dropped silently, the inviter isn't told they are blocked.
+            if is_blocked(
+                connection,
+                &insert_invite.invitee_user_id,
+                &insert_invite.inviter_user_id,
+            )? {
+                return Ok(None);
+            }
+
+            let membership = users_chats::table
+                .filter(users_chats::user_id.eq(&insert_invite.invitee_user_id))
+                .filter(users_chats::chat_id.eq(insert_invite.chat_id))
+                .count()
+                .get_result::<i64>(connection)?;
+
+            if membership > 0 {
+                return Ok(None);
+            }
+
+            diesel::delete(invites::table)
+                .filter(invites::chat_id.eq(insert_invite.chat_id))
+                .filter(invites::invitee_user_id.eq(&insert_invite.invitee_user_id))
+                .filter(invites::expires_at.le(diesel::dsl::now))
+                .execute(connection)?;
+
+            diesel::insert_into(invites::table)
+                .values(insert_invite)
+                .on_conflict((invites::chat_id, invites::invitee_user_id))
+                .do_nothing()
+                .get_result(connection)
+                .optional()
+        })
     }
 
     #[instrument(skip(self, channel, invite, deliver))]
     async fn publish_message(
         &self,
         channel: &Channel,
-        invite: &Invite,
+        invite: Invite,
         deliver: &Deliver,
     ) -> Result<(), anyhow::Error> {
-        let serialized_message = serde_json::to_string(invite)?;
-        declare_invites_exchange(channel, &invite.invitee_user_id)
+        let invitee_user_id = invite.invitee_user_id.clone();
+        let serialized_message = serde_json::to_string(&RabbitInviteEvent::NewInvite(invite))?;
+        declare_invites_exchange(channel, &invitee_user_id)
             .await
             .map_err(|e| anyhow::Error::new(e))?;
         channel
             .basic_publish(
                 BasicProperties::default(),
                 serialized_message.into_bytes(),
-                BasicPublishArguments::new(&invites_exchange_name(&invite.invitee_user_id), "")
+                BasicPublishArguments::new(&invites_exchange_name(&invitee_user_id), "")
                     .mandatory(false)
                     .immediate(false)
                     .finish(),